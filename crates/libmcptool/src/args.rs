@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use serde_json::Value;
+use serde_json::{Map, Value};
 use tenx_mcp::Arguments;
 
 use crate::{Error, Result};
@@ -15,13 +15,25 @@ impl ArgumentParser {
             return Ok(None);
         }
 
+        Ok(Some(Arguments::from(Self::parse_key_value_map(args)?)))
+    }
+
+    /// Arguments already given as a JSON object, sent exactly as they are
+    pub fn from_map(map: Map<String, Value>) -> Option<Arguments> {
+        if map.is_empty() {
+            return None;
+        }
+        Some(Arguments::from(map.into_iter().collect::<HashMap<_, _>>()))
+    }
+
+    /// Parse arguments from key=value format into a map of JSON values
+    pub fn parse_key_value_map(args: Vec<String>) -> Result<HashMap<String, Value>> {
         let mut arg_map = HashMap::new();
         for arg in args {
             let (key, value) = Self::parse_key_value_pair(&arg)?;
             arg_map.insert(key, Self::parse_value_type(value));
         }
-
-        Ok(Some(Arguments::from(arg_map)))
+        Ok(arg_map)
     }
 
     /// Parse a single key=value pair
@@ -1,4 +1,5 @@
 mod add;
+mod discover;
 mod list;
 mod remove;
 mod renew;
@@ -6,7 +7,7 @@ mod renew;
 pub use add::{AddCommandArgs, add_command};
 pub use list::list_command;
 pub use remove::remove_command;
-pub use renew::renew_command;
+pub use renew::{refresh, renew_command};
 
 use crate::{Error, Result};
 
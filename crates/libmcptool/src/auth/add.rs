@@ -1,10 +1,28 @@
 use std::time::{Duration, SystemTime};
 
+use oauth2::{
+    AuthUrl, AuthorizationCode, ClientId, ClientSecret, CsrfToken, EndpointNotSet, EndpointSet,
+    PkceCodeChallenge, PkceCodeVerifier, RedirectUrl, RequestTokenError, Scope, TokenResponse,
+    TokenUrl,
+    basic::{BasicClient, BasicErrorResponse, BasicTokenResponse},
+};
 use rustyline::DefaultEditor;
-use tenx_mcp::auth::{OAuth2CallbackServer, OAuth2Client, OAuth2Config};
+use tenx_mcp::auth::OAuth2CallbackServer;
 use tokio::time::timeout;
 
-use crate::{Error, Result, auth::validate_auth_name, ctx::Ctx, storage::StoredAuth};
+use crate::{
+    Error, Result,
+    auth::{
+        discover::{Discovery, discover},
+        validate_auth_name,
+    },
+    ctx::Ctx,
+    storage::{AuthKind, StoredAuth},
+};
+
+/// An OAuth client with its authorization and token endpoints set
+type OAuthClient =
+    BasicClient<EndpointSet, EndpointNotSet, EndpointNotSet, EndpointNotSet, EndpointSet>;
 
 pub struct AddCommandArgs {
     pub name: String,
@@ -17,6 +35,10 @@ pub struct AddCommandArgs {
     pub resource: Option<String>,
     pub scopes: Option<String>,
     pub show_redirect_url: bool,
+    /// Store this bearer token instead of running an OAuth flow
+    pub bearer: Option<String>,
+    /// Store these headers instead of running an OAuth flow
+    pub headers: Vec<(String, String)>,
 }
 
 pub async fn add_command(ctx: &Ctx, args: AddCommandArgs) -> Result<()> {
@@ -25,9 +47,6 @@ pub async fn add_command(ctx: &Ctx, args: AddCommandArgs) -> Result<()> {
     // Validate auth name
     validate_auth_name(&name)?;
 
-    ctx.output
-        .h1(format!("Adding OAuth authentication entry: {name}"))?;
-
     // Check if entry already exists
     let storage = ctx.storage()?;
     if storage.list_auth()?.contains(&name) {
@@ -39,6 +58,40 @@ pub async fn add_command(ctx: &Ctx, args: AddCommandArgs) -> Result<()> {
     // Use rustyline for interactive prompts only when needed
     let mut rl = DefaultEditor::new()?;
 
+    if args.bearer.is_some() || !args.headers.is_empty() {
+        ctx.output
+            .h1(format!("Adding static authentication entry: {name}"))?;
+        let server_url = match args.server_url {
+            Some(url) => url,
+            None => rl.readline("Server URL (e.g., https://api.example.com): ")?,
+        };
+        let stored_auth = StoredAuth {
+            name: name.clone(),
+            server_url,
+            client_id: String::new(),
+            client_secret: None,
+            access_token: args.bearer,
+            refresh_token: None,
+            expires_at: None,
+            auth_url: String::new(),
+            token_url: String::new(),
+            redirect_url: None,
+            scopes: vec![],
+            kind: AuthKind::Static,
+            headers: args.headers,
+            resource: None,
+        };
+        storage.store_auth(&stored_auth)?;
+        ctx.output
+            .trace_success(format!("Authentication entry '{name}' saved successfully!"))?;
+        ctx.output
+            .text(format!("You can now use: mcptool connect auth://{name}"))?;
+        return Ok(());
+    }
+
+    ctx.output
+        .h1(format!("Adding OAuth authentication entry: {name}"))?;
+
     // Use provided arguments or prompt for missing values
     let server_url = match args.server_url {
         Some(url) => url,
@@ -49,12 +102,33 @@ pub async fn add_command(ctx: &Ctx, args: AddCommandArgs) -> Result<()> {
         }
     };
 
-    let auth_url = match args.auth_url {
+    // Discover the authorization server's endpoints, so that only what
+    // can't be discovered needs to be entered
+    let discovery = if args.auth_url.is_none() || args.token_url.is_none() {
+        ctx.output.text(format!(
+            "Discovering authorization server for {server_url}..."
+        ))?;
+        let client = ctx.connect_options.http.without_credentials().client()?;
+        match discover(&server_url, &client).await {
+            Ok(discovery) => {
+                show_discovery(ctx, &discovery)?;
+                discovery
+            }
+            Err(e) => {
+                ctx.output.trace_warn(format!("Discovery failed: {e}"))?;
+                Discovery::default()
+            }
+        }
+    } else {
+        Discovery::default()
+    };
+
+    let auth_url = match args.auth_url.or(discovery.authorization_endpoint) {
         Some(url) => url,
         None => rl.readline("Authorization URL: ")?,
     };
 
-    let token_url = match args.token_url {
+    let token_url = match args.token_url.or(discovery.token_endpoint) {
         Some(url) => url,
         None => rl.readline("Token URL: ")?,
     };
@@ -116,34 +190,55 @@ pub async fn add_command(ctx: &Ctx, args: AddCommandArgs) -> Result<()> {
         return Ok(());
     }
 
-    // Resource (audience) - use flag or default
-    let resource = args.resource.unwrap_or_default();
+    // Resource (audience) - use flag, the discovered resource or default
+    let resource = args.resource.or(discovery.resource).unwrap_or_default();
 
-    // Scopes - use flag or default to empty
+    // Scopes - use flag, the discovered scopes or default to empty
     let scopes: Vec<String> = match args.scopes {
         Some(s) => s.split(',').map(|s| s.trim().to_string()).collect(),
-        None => vec![],
+        None => discovery.scopes,
     };
 
     ctx.output.text("")?;
     ctx.output.text("Initiating OAuth flow...")?;
 
-    // Create OAuth configuration
-    let oauth_config = OAuth2Config {
-        client_id: client_id.clone(),
-        client_secret: client_secret.clone(),
-        auth_url: auth_url.clone(),
-        token_url: token_url.clone(),
-        redirect_url: redirect_url.clone(),
-        resource: resource.clone(),
-        scopes: scopes.clone(),
-    };
-
     // Create OAuth client
-    let mut oauth_client = OAuth2Client::new(oauth_config)?;
+    let mut oauth_client = BasicClient::new(ClientId::new(client_id.clone()))
+        .set_auth_uri(
+            AuthUrl::new(auth_url.clone())
+                .map_err(|e| Error::Other(format!("Invalid auth URL: {e}")))?,
+        )
+        .set_token_uri(
+            TokenUrl::new(token_url.clone())
+                .map_err(|e| Error::Other(format!("Invalid token URL: {e}")))?,
+        )
+        .set_redirect_uri(
+            RedirectUrl::new(redirect_url.clone())
+                .map_err(|e| Error::Other(format!("Invalid redirect URL: {e}")))?,
+        );
+    if let Some(client_secret) = &client_secret {
+        oauth_client = oauth_client.set_client_secret(ClientSecret::new(client_secret.clone()));
+    }
 
-    // Get authorization URL
-    let (auth_url_with_params, csrf_token) = oauth_client.get_authorization_url();
+    // Get authorization URL, protected with PKCE
+    let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
+    let mut request = oauth_client
+        .authorize_url(CsrfToken::new_random)
+        .set_pkce_challenge(pkce_challenge)
+        .add_scopes(scopes.iter().cloned().map(Scope::new));
+    if !resource.is_empty() {
+        request = request.add_extra_param("resource", resource.clone());
+    }
+    let (auth_url_with_params, csrf_token) = request.url();
+
+    // The token request is made with our own HTTP client, so that it goes
+    // through any proxy and TLS options
+    let exchange = CodeExchange {
+        client: oauth_client,
+        verifier: pkce_verifier,
+        resource: resource.clone(),
+        http: ctx.connect_options.http.without_credentials().client()?,
+    };
 
     ctx.output.text("")?;
     ctx.output.h1("Authorization required")?;
@@ -192,7 +287,7 @@ pub async fn add_command(ctx: &Ctx, args: AddCommandArgs) -> Result<()> {
 
         // Use tokio::select to handle both callback and cancellation
         tokio::select! {
-            result = wait_for_callback(&mut oauth_client, callback_server, csrf_token.secret().to_string()) => {
+            result = wait_for_callback(&exchange, callback_server, csrf_token.secret().to_string()) => {
                 Ok(result)
             }
             _ = tokio::signal::ctrl_c() => {
@@ -200,7 +295,7 @@ pub async fn add_command(ctx: &Ctx, args: AddCommandArgs) -> Result<()> {
                 ctx.output.trace_warn("Cancelled! Switching to manual mode...")?;
                 timeout(
                     Duration::from_secs(300),
-                    wait_for_manual_callback(&mut oauth_client, csrf_token.secret().to_string(), &ctx.output),
+                    wait_for_manual_callback(&exchange, csrf_token.secret().to_string(), &ctx.output),
                 ).await
             }
         }
@@ -214,11 +309,7 @@ pub async fn add_command(ctx: &Ctx, args: AddCommandArgs) -> Result<()> {
 
         timeout(
             Duration::from_secs(300), // 5 minute timeout
-            wait_for_manual_callback(
-                &mut oauth_client,
-                csrf_token.secret().to_string(),
-                &ctx.output,
-            ),
+            wait_for_manual_callback(&exchange, csrf_token.secret().to_string(), &ctx.output),
         )
         .await
     };
@@ -278,11 +369,9 @@ pub async fn add_command(ctx: &Ctx, args: AddCommandArgs) -> Result<()> {
 
     ctx.output.trace_success("Authorization successful!")?;
 
-    // Convert token expiration from Instant to SystemTime
-    let expires_at = token.expires_at.map(|instant| {
-        let duration_since_now = instant.duration_since(std::time::Instant::now());
-        SystemTime::now() + duration_since_now
-    });
+    let expires_at = token
+        .expires_in()
+        .map(|duration| SystemTime::now() + duration);
 
     // Store the authentication
     let stored_auth = StoredAuth {
@@ -290,13 +379,16 @@ pub async fn add_command(ctx: &Ctx, args: AddCommandArgs) -> Result<()> {
         server_url,
         client_id,
         client_secret,
-        access_token: Some(token.access_token),
-        refresh_token: token.refresh_token,
+        access_token: Some(token.access_token().secret().clone()),
+        refresh_token: token.refresh_token().map(|token| token.secret().clone()),
         expires_at,
         auth_url,
         token_url,
         redirect_url: Some(redirect_url),
         scopes,
+        kind: AuthKind::OAuth,
+        headers: vec![],
+        resource: (!resource.is_empty()).then_some(resource),
     };
 
     storage.store_auth(&stored_auth)?;
@@ -311,11 +403,77 @@ pub async fn add_command(ctx: &Ctx, args: AddCommandArgs) -> Result<()> {
     Ok(())
 }
 
+/// Show what discovery found, warning if the server can't be used with PKCE
+fn show_discovery(ctx: &Ctx, discovery: &Discovery) -> Result<()> {
+    if let Some(issuer) = &discovery.issuer {
+        ctx.output.kv("Authorization server", issuer)?;
+    }
+    if let Some(url) = &discovery.authorization_endpoint {
+        ctx.output.kv("Authorization URL", url)?;
+    }
+    if let Some(url) = &discovery.token_endpoint {
+        ctx.output.kv("Token URL", url)?;
+    }
+    if let Some(resource) = &discovery.resource {
+        ctx.output.kv("Resource", resource)?;
+    }
+    if !discovery.scopes.is_empty() {
+        ctx.output.kv("Scopes", discovery.scopes.join(" "))?;
+    }
+    if !discovery.code_challenge_methods.is_empty() {
+        ctx.output
+            .kv("PKCE methods", discovery.code_challenge_methods.join(", "))?;
+        if !discovery.code_challenge_methods.iter().any(|m| m == "S256") {
+            ctx.output.trace_warn(
+                "The authorization server doesn't support PKCE with S256, which MCP requires",
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Exchanges an authorization code for tokens, with the PKCE verifier and
+/// resource that match the authorization URL
+struct CodeExchange {
+    client: OAuthClient,
+    verifier: PkceCodeVerifier,
+    /// Resource indicator, empty if none was requested
+    resource: String,
+    http: reqwest::Client,
+}
+
+impl CodeExchange {
+    async fn exchange(&self, code: String) -> Result<BasicTokenResponse> {
+        let mut request = self
+            .client
+            .exchange_code(AuthorizationCode::new(code))
+            .set_pkce_verifier(PkceCodeVerifier::new(self.verifier.secret().clone()));
+        if !self.resource.is_empty() {
+            request = request.add_extra_param("resource", self.resource.clone());
+        }
+        request
+            .request_async(&self.http)
+            .await
+            .map_err(|e| Error::Other(token_error(e)))
+    }
+}
+
+/// A readable message for a failed token request, including the server's
+/// error description
+fn token_error<E: std::error::Error>(error: RequestTokenError<E, BasicErrorResponse>) -> String {
+    match error {
+        RequestTokenError::ServerResponse(response) => response.to_string(),
+        RequestTokenError::Request(e) => format!("Request error: {e}"),
+        RequestTokenError::Parse(e, _) => format!("Parse error: {e}"),
+        RequestTokenError::Other(e) => e,
+    }
+}
+
 async fn wait_for_callback(
-    oauth_client: &mut OAuth2Client,
+    exchange: &CodeExchange,
     callback_server: OAuth2CallbackServer,
     expected_state: String,
-) -> Result<tenx_mcp::auth::OAuth2Token> {
+) -> Result<BasicTokenResponse> {
     // Wait for the OAuth callback
     let (code, state) = callback_server.wait_for_callback().await?;
 
@@ -327,16 +485,14 @@ async fn wait_for_callback(
     }
 
     // Exchange the authorization code for an access token
-    let token = oauth_client.exchange_code(code, state).await?;
-
-    Ok(token)
+    exchange.exchange(code).await
 }
 
 async fn wait_for_manual_callback(
-    oauth_client: &mut OAuth2Client,
+    exchange: &CodeExchange,
     expected_state: String,
     output: &crate::output::Output,
-) -> Result<tenx_mcp::auth::OAuth2Token> {
+) -> Result<BasicTokenResponse> {
     let mut rl = DefaultEditor::new()?;
 
     output.text("")?;
@@ -385,12 +541,10 @@ async fn wait_for_manual_callback(
     }
 
     // Exchange the authorization code for an access token
-    let token = oauth_client
-        .exchange_code(code, state)
+    exchange
+        .exchange(code)
         .await
-        .map_err(|e| Error::Other(format!("Token exchange failed: {e}")))?;
-
-    Ok(token)
+        .map_err(|e| Error::Other(format!("Token exchange failed: {e}")))
 }
 
 fn find_available_port() -> Result<u16> {
@@ -0,0 +1,463 @@
+//! OAuth discovery for MCP servers: the `WWW-Authenticate` challenge and
+//! protected resource metadata (RFC 9728) lead to the authorization server,
+//! whose metadata (RFC 8414, or OpenID Connect discovery) gives its endpoints,
+//! scopes and PKCE methods.
+
+use std::collections::BTreeMap;
+
+use reqwest::{StatusCode, header::WWW_AUTHENTICATE};
+use serde::Deserialize;
+use serde_json::json;
+use url::Url;
+
+use crate::{Error, Result};
+
+/// What could be discovered about how to authorize with a server
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Discovery {
+    /// The server's resource identifier, sent as the `resource` parameter
+    pub resource: Option<String>,
+    pub issuer: Option<String>,
+    pub authorization_endpoint: Option<String>,
+    pub token_endpoint: Option<String>,
+    /// Scopes the server asks for, or failing that those its resource
+    /// metadata lists
+    pub scopes: Vec<String>,
+    pub code_challenge_methods: Vec<String>,
+}
+
+/// RFC 9728 protected resource metadata
+#[derive(Debug, Default, Deserialize)]
+struct ResourceMetadata {
+    resource: Option<String>,
+    #[serde(default)]
+    authorization_servers: Vec<String>,
+    #[serde(default)]
+    scopes_supported: Vec<String>,
+}
+
+/// RFC 8414 authorization server metadata
+#[derive(Debug, Deserialize)]
+struct ServerMetadata {
+    issuer: Option<String>,
+    authorization_endpoint: Option<String>,
+    token_endpoint: Option<String>,
+    #[serde(default)]
+    code_challenge_methods_supported: Vec<String>,
+}
+
+/// Discover the authorization server of an MCP server. Fails if the server
+/// URL is invalid, no authorization server metadata can be found, or the
+/// metadata found is for a different resource or issuer.
+pub async fn discover(server_url: &str, client: &reqwest::Client) -> Result<Discovery> {
+    let server = Url::parse(server_url)
+        .map_err(|e| Error::Format(format!("Invalid server URL {server_url}: {e}")))?;
+
+    // An unauthenticated request is answered with a challenge that may point
+    // to the metadata and name the scopes needed
+    let challenge = challenge(&server, client).await;
+    let metadata_urls = match challenge.get("resource_metadata") {
+        Some(url) => vec![
+            Url::parse(url)
+                .map_err(|e| Error::Format(format!("Invalid resource metadata URL {url}: {e}")))?,
+        ],
+        None => well_known_urls(&server, "oauth-protected-resource"),
+    };
+    let mut resource = ResourceMetadata::default();
+    for url in metadata_urls {
+        if let Some(metadata) = fetch_json::<ResourceMetadata>(&url, client).await {
+            // Metadata for another resource could send the authorization code
+            // to someone else's authorization server (RFC 9728 section 3.3)
+            if let Some(claimed) = &metadata.resource
+                && !resource_matches(claimed, &server)
+            {
+                return Err(Error::Other(format!(
+                    "Resource metadata at {url} is for {claimed}, not {server}"
+                )));
+            }
+            resource = metadata;
+            break;
+        }
+    }
+
+    // Servers without resource metadata are their own authorization server
+    let issuer = match resource.authorization_servers.first() {
+        Some(issuer) => Url::parse(issuer)
+            .map_err(|e| Error::Format(format!("Invalid authorization server {issuer}: {e}")))?,
+        None => {
+            let mut origin = server.clone();
+            origin.set_path("");
+            origin.set_query(None);
+            origin
+        }
+    };
+    let mut server_metadata = None;
+    for url in server_metadata_urls(&issuer) {
+        if let Some(metadata) = fetch_json::<ServerMetadata>(&url, client).await {
+            // The metadata must name the issuer it was fetched for (RFC 8414
+            // section 3.3)
+            let claimed = metadata.issuer.as_deref().unwrap_or_default();
+            if claimed.trim_end_matches('/') != issuer.as_str().trim_end_matches('/') {
+                return Err(Error::Other(format!(
+                    "Authorization server metadata at {url} is for issuer '{claimed}', not {issuer}"
+                )));
+            }
+            server_metadata = Some(metadata);
+            break;
+        }
+    }
+    let server_metadata = server_metadata.ok_or_else(|| {
+        Error::Other(format!(
+            "No authorization server metadata found for {issuer}"
+        ))
+    })?;
+
+    // The authorization server's own list covers every resource it protects,
+    // so it isn't a useful default
+    let scopes = match challenge.get("scope") {
+        Some(scope) => scope.split_whitespace().map(String::from).collect(),
+        None => resource.scopes_supported,
+    };
+    Ok(Discovery {
+        resource: resource.resource,
+        issuer: server_metadata.issuer,
+        authorization_endpoint: server_metadata.authorization_endpoint,
+        token_endpoint: server_metadata.token_endpoint,
+        scopes,
+        code_challenge_methods: server_metadata.code_challenge_methods_supported,
+    })
+}
+
+/// True if a protected resource identifier covers the server URL: the same
+/// origin, with the server's path at or below the resource's
+fn resource_matches(resource: &str, server: &Url) -> bool {
+    let Ok(resource) = Url::parse(resource) else {
+        return false;
+    };
+    if resource.origin() != server.origin() {
+        return false;
+    }
+    let resource_path = resource.path().trim_end_matches('/');
+    let server_path = server.path().trim_end_matches('/');
+    server_path == resource_path
+        || server_path
+            .strip_prefix(resource_path)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// The parameters of the server's Bearer challenge, if it sends one
+async fn challenge(server: &Url, client: &reqwest::Client) -> BTreeMap<String, String> {
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": tenx_mcp::schema::LATEST_PROTOCOL_VERSION,
+            "capabilities": {},
+            "clientInfo": {"name": "mcptool", "version": crate::ctx::VERSION}
+        }
+    });
+    let response = client
+        .post(server.clone())
+        .header("Accept", "application/json, text/event-stream")
+        .json(&request)
+        .send()
+        .await;
+    match response {
+        Ok(response) if response.status() == StatusCode::UNAUTHORIZED => response
+            .headers()
+            .get_all(WWW_AUTHENTICATE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .find_map(parse_bearer_challenge)
+            .unwrap_or_default(),
+        _ => BTreeMap::new(),
+    }
+}
+
+/// The parameters of a `Bearer` challenge in a `WWW-Authenticate` header,
+/// e.g. `Bearer resource_metadata="https://...", scope="read write"`
+pub fn parse_bearer_challenge(header: &str) -> Option<BTreeMap<String, String>> {
+    let (scheme, mut rest) = header.trim().split_once(' ').unwrap_or((header.trim(), ""));
+    if !scheme.eq_ignore_ascii_case("bearer") {
+        return None;
+    }
+
+    let mut params = BTreeMap::new();
+    loop {
+        rest = rest.trim_start_matches([' ', ',']);
+        let Some((name, value)) = rest.split_once('=') else {
+            break;
+        };
+        let name = name.trim().to_ascii_lowercase();
+        let value = value.trim_start();
+        let (value, remainder) = match value.strip_prefix('"') {
+            Some(quoted) => {
+                let mut unquoted = String::new();
+                let mut chars = quoted.char_indices();
+                let mut end = quoted.len();
+                while let Some((idx, c)) = chars.next() {
+                    match c {
+                        '\\' => unquoted.extend(chars.next().map(|(_, c)| c)),
+                        '"' => {
+                            end = idx + 1;
+                            break;
+                        }
+                        c => unquoted.push(c),
+                    }
+                }
+                (unquoted, &quoted[end..])
+            }
+            None => {
+                let end = value.find(',').unwrap_or(value.len());
+                (value[..end].trim().to_string(), &value[end..])
+            }
+        };
+        params.insert(name, value);
+        rest = remainder;
+    }
+    Some(params)
+}
+
+/// Well-known URLs for a resource, with the path inserted after the
+/// well-known suffix as RFC 8414 and RFC 9728 specify, then at the root
+pub fn well_known_urls(url: &Url, suffix: &str) -> Vec<Url> {
+    let path = url.path().trim_end_matches('/');
+    let mut paths = vec![];
+    if !path.is_empty() {
+        paths.push(format!("/.well-known/{suffix}{path}"));
+    }
+    paths.push(format!("/.well-known/{suffix}"));
+    paths
+        .into_iter()
+        .map(|path| {
+            let mut url = url.clone();
+            url.set_path(&path);
+            url.set_query(None);
+            url.set_fragment(None);
+            url
+        })
+        .collect()
+}
+
+/// Where an issuer's metadata may be found: RFC 8414, then OpenID Connect
+/// discovery with the path inserted, then appended
+fn server_metadata_urls(issuer: &Url) -> Vec<Url> {
+    let mut urls = well_known_urls(issuer, "oauth-authorization-server");
+    urls.extend(well_known_urls(issuer, "openid-configuration"));
+    let path = issuer.path().trim_end_matches('/');
+    if !path.is_empty() {
+        let mut url = issuer.clone();
+        url.set_path(&format!("{path}/.well-known/openid-configuration"));
+        urls.push(url);
+    }
+    urls.dedup();
+    urls
+}
+
+/// Fetch and parse a metadata document, or None if it isn't there
+async fn fetch_json<T: serde::de::DeserializeOwned>(
+    url: &Url,
+    client: &reqwest::Client,
+) -> Option<T> {
+    let response = client.get(url.clone()).send().await.ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    response.json().await.ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn test_parse_bearer_challenge() {
+        let params = parse_bearer_challenge(
+            r#"Bearer error="invalid_token", resource_metadata="https://mcp.example.com/.well-known/oauth-protected-resource", scope="files:read \"all\"""#,
+        )
+        .unwrap();
+        assert_eq!(
+            params["resource_metadata"],
+            "https://mcp.example.com/.well-known/oauth-protected-resource"
+        );
+        assert_eq!(params["scope"], r#"files:read "all""#);
+        assert_eq!(params["error"], "invalid_token");
+
+        let params = parse_bearer_challenge("bearer realm=mcp,scope=read").unwrap();
+        assert_eq!(params["realm"], "mcp");
+        assert_eq!(params["scope"], "read");
+        assert!(parse_bearer_challenge("Bearer").unwrap().is_empty());
+        assert!(parse_bearer_challenge(r#"Basic realm="x""#).is_none());
+    }
+
+    #[test]
+    fn test_well_known_urls() {
+        let url = Url::parse("https://mcp.example.com/v1/mcp?x=1").unwrap();
+        assert_eq!(
+            well_known_urls(&url, "oauth-protected-resource")
+                .iter()
+                .map(Url::as_str)
+                .collect::<Vec<_>>(),
+            vec![
+                "https://mcp.example.com/.well-known/oauth-protected-resource/v1/mcp",
+                "https://mcp.example.com/.well-known/oauth-protected-resource",
+            ]
+        );
+
+        let issuer = Url::parse("https://auth.example.com/tenant").unwrap();
+        assert_eq!(
+            server_metadata_urls(&issuer)
+                .iter()
+                .map(Url::as_str)
+                .collect::<Vec<_>>(),
+            vec![
+                "https://auth.example.com/.well-known/oauth-authorization-server/tenant",
+                "https://auth.example.com/.well-known/oauth-authorization-server",
+                "https://auth.example.com/.well-known/openid-configuration/tenant",
+                "https://auth.example.com/.well-known/openid-configuration",
+                "https://auth.example.com/tenant/.well-known/openid-configuration",
+            ]
+        );
+    }
+
+    /// Serve a protected MCP endpoint and its metadata, with the
+    /// authorization server on the same host under /auth
+    async fn serve(listener: tokio::net::TcpListener) {
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0; 8192];
+            let n = stream.read(&mut buf).await.unwrap();
+            let request = String::from_utf8_lossy(&buf[..n]).to_string();
+            let path = request.split_whitespace().nth(1).unwrap_or("/").to_string();
+            let (status, headers, body) = match path.as_str() {
+                "/mcp" => (
+                    "401 Unauthorized",
+                    format!(
+                        "WWW-Authenticate: Bearer resource_metadata=\"{base}/.well-known/oauth-protected-resource/mcp\"\r\n"
+                    ),
+                    String::new(),
+                ),
+                "/.well-known/oauth-protected-resource/mcp" => (
+                    "200 OK",
+                    String::new(),
+                    json!({
+                        "resource": format!("{base}/mcp"),
+                        "authorization_servers": [format!("{base}/auth")],
+                        "scopes_supported": ["mcp:tools"]
+                    })
+                    .to_string(),
+                ),
+                "/.well-known/oauth-authorization-server/auth" => (
+                    "200 OK",
+                    String::new(),
+                    json!({
+                        "issuer": format!("{base}/auth"),
+                        "authorization_endpoint": format!("{base}/auth/authorize"),
+                        "token_endpoint": format!("{base}/auth/token"),
+                        "scopes_supported": ["mcp:tools", "openid"],
+                        "code_challenge_methods_supported": ["S256"]
+                    })
+                    .to_string(),
+                ),
+                // Metadata claiming to be for other servers
+                "/.well-known/oauth-protected-resource/stolen" => (
+                    "200 OK",
+                    String::new(),
+                    json!({
+                        "resource": "https://mcp.example.com/mcp",
+                        "authorization_servers": [format!("{base}/auth")]
+                    })
+                    .to_string(),
+                ),
+                "/.well-known/oauth-protected-resource/spoofed" => (
+                    "200 OK",
+                    String::new(),
+                    json!({
+                        "resource": format!("{base}/spoofed"),
+                        "authorization_servers": [format!("{base}/spoofer")]
+                    })
+                    .to_string(),
+                ),
+                "/.well-known/oauth-authorization-server/spoofer" => (
+                    "200 OK",
+                    String::new(),
+                    json!({
+                        "issuer": "https://auth.example.com",
+                        "authorization_endpoint": format!("{base}/spoofer/authorize"),
+                        "token_endpoint": format!("{base}/spoofer/token")
+                    })
+                    .to_string(),
+                ),
+                // The server is its own authorization server
+                "/.well-known/oauth-authorization-server" => (
+                    "200 OK",
+                    String::new(),
+                    json!({
+                        "issuer": base,
+                        "authorization_endpoint": format!("{base}/authorize"),
+                        "token_endpoint": format!("{base}/token"),
+                        "scopes_supported": ["everything"]
+                    })
+                    .to_string(),
+                ),
+                _ => ("404 Not Found", String::new(), String::new()),
+            };
+            let response = format!(
+                "HTTP/1.1 {status}\r\n{headers}Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_discover() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(serve(listener));
+
+        let client = reqwest::Client::new();
+        let discovery = discover(&format!("{base}/mcp"), &client).await.unwrap();
+        assert_eq!(
+            discovery,
+            Discovery {
+                resource: Some(format!("{base}/mcp")),
+                issuer: Some(format!("{base}/auth")),
+                authorization_endpoint: Some(format!("{base}/auth/authorize")),
+                token_endpoint: Some(format!("{base}/auth/token")),
+                scopes: vec!["mcp:tools".to_string()],
+                code_challenge_methods: vec!["S256".to_string()],
+            }
+        );
+
+        // Without resource metadata the server's origin is the issuer, and
+        // the issuer's scopes aren't requested
+        let discovery = discover(&format!("{base}/other"), &client).await.unwrap();
+        assert_eq!(discovery.issuer, Some(base.clone()));
+        assert_eq!(discovery.token_endpoint, Some(format!("{base}/token")));
+        assert!(discovery.scopes.is_empty());
+
+        // Metadata for a different resource or issuer is rejected
+        assert!(discover(&format!("{base}/stolen"), &client).await.is_err());
+        assert!(discover(&format!("{base}/spoofed"), &client).await.is_err());
+    }
+
+    #[test]
+    fn test_resource_matches() {
+        let server = Url::parse("https://mcp.example.com/v1/mcp?x=1").unwrap();
+        assert!(resource_matches("https://mcp.example.com/v1/mcp", &server));
+        assert!(resource_matches("https://mcp.example.com/v1/", &server));
+        assert!(resource_matches("https://mcp.example.com", &server));
+        assert!(!resource_matches("https://mcp.example.com/v1/m", &server));
+        assert!(!resource_matches("https://mcp.example.com/v2", &server));
+        assert!(!resource_matches("http://mcp.example.com/v1/mcp", &server));
+        assert!(!resource_matches(
+            "https://evil.example.com/v1/mcp",
+            &server
+        ));
+        assert!(!resource_matches("not a url", &server));
+    }
+}
@@ -1,6 +1,18 @@
 use std::time::SystemTime;
 
-use crate::{Result, ctx::Ctx};
+use crate::{
+    Result,
+    ctx::Ctx,
+    storage::{AuthKind, StoredAuth},
+};
+
+/// The client ID, or a placeholder for entries that don't have one
+fn client_label(auth: &StoredAuth) -> &str {
+    match auth.kind {
+        AuthKind::OAuth => &auth.client_id,
+        AuthKind::Static => "-",
+    }
+}
 
 pub async fn list_command(ctx: &Ctx) -> Result<()> {
     let storage = ctx.storage()?;
@@ -28,7 +40,7 @@ pub async fn list_command(ctx: &Ctx) -> Result<()> {
         .max(6);
     let max_client_len = auths
         .iter()
-        .map(|a| a.client_id.len())
+        .map(|a| client_label(a).len())
         .max()
         .unwrap_or(9)
         .max(9);
@@ -59,6 +71,7 @@ pub async fn list_command(ctx: &Ctx) -> Result<()> {
     // Print each entry
     for auth in auths {
         let status = match (auth.access_token.is_some(), auth.expires_at) {
+            _ if auth.kind == AuthKind::Static => "Static".to_string(),
             (false, _) => "No token".to_string(),
             (true, None) => "Valid".to_string(),
             (true, Some(expires)) => {
@@ -84,7 +97,7 @@ pub async fn list_command(ctx: &Ctx) -> Result<()> {
             "{:<width_name$}  {:<width_server$}  {:<width_client$}  {}",
             auth.name,
             auth.server_url,
-            auth.client_id,
+            client_label(&auth),
             status,
             width_name = max_name_len,
             width_server = max_server_len,
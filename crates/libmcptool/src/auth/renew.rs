@@ -5,7 +5,11 @@ use oauth2::{
     basic::BasicClient,
 };
 
-use crate::{Error, Result, ctx::Ctx};
+use crate::{
+    Error, Result,
+    ctx::Ctx,
+    storage::{AuthKind, StoredAuth},
+};
 
 pub async fn renew_command(ctx: &Ctx, name: String) -> Result<()> {
     ctx.output
@@ -13,11 +17,18 @@ pub async fn renew_command(ctx: &Ctx, name: String) -> Result<()> {
 
     let storage = ctx.storage()?;
     let mut auth = storage.get_auth(&name)?;
+    if auth.kind == AuthKind::Static {
+        return Err(Error::Other(format!(
+            "'{name}' holds static credentials, which can't be renewed. Remove and re-add it to change them"
+        )));
+    }
 
     // Check if we have a refresh token
-    let refresh_token = auth.refresh_token.as_ref().ok_or(Error::Other(
-        "No refresh token available for this authentication entry".to_string(),
-    ))?;
+    if auth.refresh_token.is_none() {
+        return Err(Error::Other(
+            "No refresh token available for this authentication entry".to_string(),
+        ));
+    }
 
     ctx.output.text("Current token status:")?;
     match &auth.expires_at {
@@ -41,6 +52,37 @@ pub async fn renew_command(ctx: &Ctx, name: String) -> Result<()> {
     ctx.output.text("")?;
     ctx.output.text("Refreshing token...")?;
 
+    refresh(ctx, &mut auth).await?;
+
+    ctx.output.trace_success("Token refreshed successfully!")?;
+    ctx.output.text("")?;
+    ctx.output.text("New token status:")?;
+
+    if let Some(expires_at) = auth.expires_at {
+        let now = SystemTime::now();
+        if expires_at > now {
+            let remaining = expires_at.duration_since(now).unwrap_or(Duration::ZERO);
+            let hours = remaining.as_secs() / 3600;
+            let minutes = (remaining.as_secs() % 3600) / 60;
+            ctx.output
+                .text(format!("  Token expires in {hours}h {minutes}m"))?;
+        } else {
+            ctx.output.text("  Token is already expired")?;
+        }
+    } else {
+        ctx.output.text("  No expiration information available")?;
+    }
+
+    Ok(())
+}
+
+/// Exchange an entry's refresh token for a new access token, and store the
+/// result
+pub async fn refresh(ctx: &Ctx, auth: &mut StoredAuth) -> Result<()> {
+    let refresh_token = auth.refresh_token.as_ref().ok_or(Error::Other(
+        "No refresh token available for this authentication entry".to_string(),
+    ))?;
+
     // Create OAuth client directly using oauth2 crate
     let mut client = BasicClient::new(ClientId::new(auth.client_id.clone()))
         .set_auth_uri(
@@ -58,9 +100,12 @@ pub async fn renew_command(ctx: &Ctx, name: String) -> Result<()> {
 
     // Exchange refresh token for new access token
     let refresh_token_obj = RefreshToken::new(refresh_token.clone());
-    let token_result = client
-        .exchange_refresh_token(&refresh_token_obj)
-        .request_async(&reqwest::Client::new())
+    let mut request = client.exchange_refresh_token(&refresh_token_obj);
+    if let Some(resource) = &auth.resource {
+        request = request.add_extra_param("resource", resource.clone());
+    }
+    let token_result = request
+        .request_async(&ctx.connect_options.http.without_credentials().client()?)
         .await
         .map_err(|e| {
             Error::Other(match e {
@@ -87,26 +132,6 @@ pub async fn renew_command(ctx: &Ctx, name: String) -> Result<()> {
         .map(|duration| SystemTime::now() + duration);
 
     // Save the updated auth
-    storage.store_auth(&auth)?;
-
-    ctx.output.trace_success("Token refreshed successfully!")?;
-    ctx.output.text("")?;
-    ctx.output.text("New token status:")?;
-
-    if let Some(expires_at) = auth.expires_at {
-        let now = SystemTime::now();
-        if expires_at > now {
-            let remaining = expires_at.duration_since(now).unwrap_or(Duration::ZERO);
-            let hours = remaining.as_secs() / 3600;
-            let minutes = (remaining.as_secs() % 3600) / 60;
-            ctx.output
-                .text(format!("  Token expires in {hours}h {minutes}m"))?;
-        } else {
-            ctx.output.text("  Token is already expired")?;
-        }
-    } else {
-        ctx.output.text("  No expiration information available")?;
-    }
-
+    ctx.storage()?.store_auth(auth)?;
     Ok(())
 }
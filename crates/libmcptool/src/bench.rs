@@ -0,0 +1,358 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use hdrhistogram::Histogram;
+use serde::Serialize;
+use tenx_mcp::{Arguments, Client, ServerAPI, schema::InitializeResult};
+use tokio::task::JoinSet;
+
+use crate::{
+    Error, Result, calltool, client,
+    command::{McpCommand, execute_mcp_command_with_client},
+    ctx::Ctx,
+    output::Output,
+    target::Target,
+};
+
+/// Latencies are recorded in microseconds, up to an hour
+const MAX_LATENCY_MICROS: u64 = 3_600_000_000;
+
+pub struct BenchCommandArgs {
+    pub target: String,
+    pub command: McpCommand,
+    /// Total number of requests, ignored when a duration is given
+    pub requests: u64,
+    /// Keep sending requests for this long
+    pub duration: Option<Duration>,
+    /// Number of concurrent workers
+    pub concurrency: usize,
+    /// Share one connection between all workers instead of one each
+    pub shared: bool,
+}
+
+/// Benchmark results
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchReport {
+    pub requests: u64,
+    pub errors: u64,
+    pub error_rate: f64,
+    pub elapsed_secs: f64,
+    /// Requests per second, including failed requests
+    pub throughput: f64,
+    /// Latency of successful requests
+    pub latency_ms: Latency,
+    /// Error messages and how often each occurred
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub error_messages: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Latency {
+    pub min: f64,
+    pub mean: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+impl BenchReport {
+    pub fn new(
+        histogram: &Histogram<u64>,
+        error_messages: BTreeMap<String, u64>,
+        elapsed: Duration,
+    ) -> Self {
+        let errors: u64 = error_messages.values().sum();
+        let requests = histogram.len() + errors;
+        let millis = |micros: u64| micros as f64 / 1000.0;
+        let latency = if histogram.is_empty() {
+            Latency {
+                min: 0.0,
+                mean: 0.0,
+                p50: 0.0,
+                p90: 0.0,
+                p99: 0.0,
+                max: 0.0,
+            }
+        } else {
+            Latency {
+                min: millis(histogram.min()),
+                mean: histogram.mean() / 1000.0,
+                p50: millis(histogram.value_at_quantile(0.50)),
+                p90: millis(histogram.value_at_quantile(0.90)),
+                p99: millis(histogram.value_at_quantile(0.99)),
+                max: millis(histogram.max()),
+            }
+        };
+        let elapsed_secs = elapsed.as_secs_f64();
+        BenchReport {
+            requests,
+            errors,
+            error_rate: if requests == 0 {
+                0.0
+            } else {
+                errors as f64 / requests as f64
+            },
+            elapsed_secs,
+            throughput: if elapsed_secs > 0.0 {
+                requests as f64 / elapsed_secs
+            } else {
+                0.0
+            },
+            latency_ms: latency,
+            error_messages,
+        }
+    }
+}
+
+/// Decides when workers stop sending requests
+enum Budget {
+    Requests(AtomicU64),
+    Until(Instant),
+}
+
+impl Budget {
+    /// Claim the next request, returning false when the budget is spent
+    fn take(&self) -> bool {
+        match self {
+            Budget::Requests(remaining) => remaining
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok(),
+            Budget::Until(deadline) => Instant::now() < *deadline,
+        }
+    }
+}
+
+/// The request each worker sends
+enum Request {
+    /// Tool calls go straight to `tools/call`, without the tool lookup the
+    /// `calltool` command does first
+    CallTool {
+        name: String,
+        arguments: Option<Arguments>,
+    },
+    Command(McpCommand),
+}
+
+impl Request {
+    /// Prepare a command, parsing any tool arguments once up front
+    fn new(command: McpCommand, output: &Output) -> Result<Self> {
+        match command {
+            McpCommand::Calltool {
+                interactive: true, ..
+            } => Err(Error::Other(
+                "calltool --interactive can't be benchmarked; pass --arg or --json".to_string(),
+            )),
+            McpCommand::Calltool {
+                tool_name,
+                args,
+                json,
+                ..
+            } => {
+                if json && !args.is_empty() {
+                    return Err(Error::Other(
+                        "Cannot combine --interactive, --json, and --arg modes".to_string(),
+                    ));
+                }
+                let arguments = if json {
+                    calltool::json::parse_json_arguments(output)?
+                } else {
+                    calltool::cmdline::parse_command_line_arguments(args, output)?
+                };
+                Ok(Request::CallTool {
+                    name: tool_name,
+                    arguments,
+                })
+            }
+            command => Ok(Request::Command(command)),
+        }
+    }
+
+    async fn send(
+        &self,
+        client: &mut Client<()>,
+        init_result: &InitializeResult,
+        ctx: &Ctx,
+    ) -> Result<()> {
+        match self {
+            Request::CallTool { name, arguments } => {
+                client.call_tool(name, arguments.clone()).await?;
+            }
+            Request::Command(command) => {
+                execute_mcp_command_with_client(command.clone(), client, init_result, ctx).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+struct WorkerStats {
+    histogram: Histogram<u64>,
+    errors: BTreeMap<String, u64>,
+}
+
+fn new_histogram() -> Histogram<u64> {
+    Histogram::new_with_bounds(1, MAX_LATENCY_MICROS, 3).expect("valid histogram bounds")
+}
+
+pub async fn bench_command(ctx: &Ctx, args: BenchCommandArgs) -> Result<()> {
+    let target = Target::parse(&args.target)?;
+    let concurrency = args.concurrency.max(1);
+
+    // Individual requests run silently; only the summary is shown
+    let quiet_ctx = Ctx {
+        output: ctx.output.clone().with_quiet(true),
+        ..ctx.clone()
+    };
+
+    // Establish every connection before the clock starts
+    ctx.output
+        .text(format!("Connecting to {}", target.redacted()))?;
+    let connection_count = if args.shared { 1 } else { concurrency };
+    let mut clients = vec![];
+    for _ in 0..connection_count {
+        clients.push(client::get_client(&quiet_ctx, &target).await?);
+    }
+    let init_result = Arc::new(clients[0].1.clone());
+    // Clones of a client share its connection, and responses are matched to
+    // requests by id, so shared workers still send concurrently
+    let clients: Vec<Client<()>> = if args.shared {
+        let (client, _) = clients.pop().expect("one connection");
+        (0..concurrency).map(|_| client.clone()).collect()
+    } else {
+        clients.into_iter().map(|(client, _)| client).collect()
+    };
+    let request = Arc::new(Request::new(args.command, &ctx.output)?);
+
+    match args.duration {
+        Some(duration) => ctx.output.h1(format!(
+            "Running for {:.1}s with {concurrency} worker(s)",
+            duration.as_secs_f64()
+        ))?,
+        None => ctx.output.h1(format!(
+            "Running {} request(s) with {concurrency} worker(s)",
+            args.requests
+        ))?,
+    }
+
+    let budget = Arc::new(match args.duration {
+        Some(duration) => Budget::Until(Instant::now() + duration),
+        None => Budget::Requests(AtomicU64::new(args.requests)),
+    });
+    let start = Instant::now();
+    let mut workers = JoinSet::new();
+    for mut client in clients {
+        let budget = budget.clone();
+        let request = request.clone();
+        let init_result = init_result.clone();
+        let ctx = quiet_ctx.clone();
+        workers.spawn(async move {
+            let mut stats = WorkerStats {
+                histogram: new_histogram(),
+                errors: BTreeMap::new(),
+            };
+            while budget.take() {
+                let sent = Instant::now();
+                let result = request.send(&mut client, &init_result, &ctx).await;
+                let elapsed = sent.elapsed();
+                match result {
+                    Ok(_) => {
+                        let micros = elapsed.as_micros().clamp(1, MAX_LATENCY_MICROS as u128);
+                        stats.histogram.saturating_record(micros as u64);
+                    }
+                    Err(e) => *stats.errors.entry(e.to_string()).or_default() += 1,
+                }
+            }
+            stats
+        });
+    }
+
+    let mut histogram = new_histogram();
+    let mut errors = BTreeMap::new();
+    while let Some(stats) = workers.join_next().await {
+        let stats = stats.map_err(|e| Error::Internal(e.to_string()))?;
+        histogram
+            .add(&stats.histogram)
+            .map_err(|e| Error::Internal(e.to_string()))?;
+        for (message, count) in stats.errors {
+            *errors.entry(message).or_default() += count;
+        }
+    }
+    let report = BenchReport::new(&histogram, errors, start.elapsed());
+
+    if ctx.output.json {
+        return ctx.output.json_value(&report);
+    }
+
+    let out = ctx.output.indent();
+    out.kv("Requests", report.requests.to_string())?;
+    out.kv(
+        "Errors",
+        format!("{} ({:.2}%)", report.errors, report.error_rate * 100.0),
+    )?;
+    out.kv("Elapsed", format!("{:.2}s", report.elapsed_secs))?;
+    out.kv("Throughput", format!("{:.1} req/s", report.throughput))?;
+    ctx.output.h2("Latency")?;
+    let latency = &report.latency_ms;
+    for (label, value) in [
+        ("min", latency.min),
+        ("mean", latency.mean),
+        ("p50", latency.p50),
+        ("p90", latency.p90),
+        ("p99", latency.p99),
+        ("max", latency.max),
+    ] {
+        out.kv(label, format!("{value:.2}ms"))?;
+    }
+    if !report.error_messages.is_empty() {
+        ctx.output.h2("Errors")?;
+        for (message, count) in &report.error_messages {
+            out.trace_error(format!("{count} x {message}"))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let mut histogram = new_histogram();
+        for millis in 1..=100 {
+            histogram.record(millis * 1000).unwrap();
+        }
+        let errors = BTreeMap::from([("timeout".to_string(), 25)]);
+
+        let report = BenchReport::new(&histogram, errors, Duration::from_secs(5));
+        assert_eq!(report.requests, 125);
+        assert_eq!(report.errors, 25);
+        assert_eq!(report.error_rate, 0.2);
+        assert_eq!(report.throughput, 25.0);
+        assert_eq!(report.latency_ms.min, 1.0);
+        assert!((report.latency_ms.p50 - 50.0).abs() < 0.1);
+        assert!((report.latency_ms.p99 - 99.0).abs() < 0.1);
+        assert!((report.latency_ms.max - 100.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_empty_report() {
+        let report = BenchReport::new(&new_histogram(), BTreeMap::new(), Duration::ZERO);
+        assert_eq!(report.requests, 0);
+        assert_eq!(report.error_rate, 0.0);
+        assert_eq!(report.throughput, 0.0);
+        assert_eq!(report.latency_ms.max, 0.0);
+    }
+
+    #[test]
+    fn test_request_budget() {
+        let budget = Budget::Requests(AtomicU64::new(2));
+        assert!(budget.take());
+        assert!(budget.take());
+        assert!(!budget.take());
+        assert!(!budget.take());
+    }
+}
@@ -1,10 +1,96 @@
-use std::sync::Arc;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use tenx_mcp::auth::{OAuth2Client, OAuth2Config};
-use tenx_mcp::{Client, ClientConn, schema::InitializeResult};
+use tenx_mcp::{
+    Client, ClientConn, ServerAPI,
+    schema::{
+        ClientCapabilities, ClientNotification, Implementation, InitializeResult,
+        LATEST_PROTOCOL_VERSION,
+    },
+    transport::GenericDuplex,
+};
 
 use crate::ctx::VERSION;
-use crate::{Error, Result, ctx::Ctx, target::Target, utils::TimedFuture};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    process::Child,
+    sync::oneshot,
+};
+
+use crate::{
+    Error, Result,
+    ctx::Ctx,
+    http::{self, HttpOptions},
+    output::Output,
+    profile::Config,
+    storage::AuthKind,
+    target::{ProcessEnv, Target},
+    transcript::Transcript,
+    transport::{self, LineStream},
+    utils::TimedFuture,
+};
+
+/// Options that control how connections are established
+#[derive(Clone, Default)]
+pub struct ConnectOptions {
+    /// Protocol revision to request instead of the latest
+    pub protocol_version: Option<String>,
+    /// Append the stderr of cmd:// servers to this file
+    pub server_stderr: Option<PathBuf>,
+    /// Extra headers and bearer token for HTTP targets
+    pub http: HttpOptions,
+    /// Environment and working directory for cmd:// servers, applied on top
+    /// of any given in the target
+    pub process_env: ProcessEnv,
+    /// Show the stderr of cmd:// servers here, and report when they exit with
+    /// a failure. Without this or `server_stderr`, servers share our stderr.
+    pub server_output: Option<Output>,
+    /// Record everything sent and received here
+    pub transcript: Option<Transcript>,
+    /// Keep track of cmd:// servers spawned, so they can be stopped
+    pub processes: Option<ServerProcesses>,
+}
+
+impl ConnectOptions {
+    /// The requested protocol version, if it differs from the latest
+    fn older_protocol_version(&self) -> Option<&str> {
+        self.protocol_version
+            .as_deref()
+            .filter(|version| *version != LATEST_PROTOCOL_VERSION)
+    }
+}
+
+/// Server processes spawned for connections. Each one is otherwise killed
+/// only when its connection is dropped, which a caller that replaces a
+/// connection can't wait for.
+#[derive(Clone, Default)]
+pub struct ServerProcesses {
+    /// Asks a process's supervisor to kill it, answering once it has exited
+    stops: Arc<Mutex<Vec<oneshot::Sender<oneshot::Sender<()>>>>>,
+}
+
+impl ServerProcesses {
+    fn add(&self, stop: oneshot::Sender<oneshot::Sender<()>>) {
+        let mut stops = self.stops.lock().unwrap();
+        // Processes that have already exited need no stopping
+        stops.retain(|stop| !stop.is_closed());
+        stops.push(stop);
+    }
+
+    /// Kill every process and wait for each to exit
+    pub async fn kill_all(&self) {
+        let stops = std::mem::take(&mut *self.stops.lock().unwrap());
+        for stop in stops {
+            let (done, exited) = oneshot::channel();
+            if stop.send(done).is_ok() {
+                let _ = exited.await;
+            }
+        }
+    }
+}
 
 pub async fn get_client(ctx: &Ctx, target: &Target) -> Result<(Client<()>, InitializeResult)> {
     get_client_with_connection(ctx, target, ()).await
@@ -15,20 +101,51 @@ pub async fn get_client_with_connection<C: ClientConn + Send + 'static>(
     conn: C,
 ) -> Result<(Client<C>, InitializeResult)> {
     match target {
+        Target::Profile { name } => {
+            let profile = Config::load(&ctx.config_path)?.profile(name)?.clone();
+            let (ctx, resolved_target) = profile.resolve(ctx)?;
+            ctx.output.text(format!(
+                "Using profile {name} ({})",
+                resolved_target.redacted()
+            ))?;
+            let connect = Box::pin(get_client_with_connection(&ctx, &resolved_target, conn));
+            match profile.timeout {
+                Some(seconds) => tokio::time::timeout(Duration::from_secs(seconds), connect)
+                    .await
+                    .map_err(|_| {
+                        Error::Other(format!(
+                            "Timed out connecting to {} after {seconds}s",
+                            resolved_target.redacted()
+                        ))
+                    })?,
+                None => connect.await,
+            }
+        }
         Target::Auth { name } => {
             let storage = ctx.storage()?;
             let auth_entry = storage.get_auth(name)?;
             ctx.output
                 .text(format!("Using auth {name} ({})", auth_entry.server_url))?;
             let resolved_target = Target::parse(&auth_entry.server_url)?;
+            if auth_entry.kind == AuthKind::Static {
+                // Static credentials are just extra request headers
+                let options = ConnectOptions {
+                    http: auth_entry.http_options().merged(&ctx.connect_options.http),
+                    ..ctx.connect_options.clone()
+                };
+                return connect_to_server_with_options(&resolved_target, conn, &options)
+                    .timed("Connected and initialized", &ctx.output)
+                    .await;
+            }
             connect_with_auth(ctx, &resolved_target, name, conn)
                 .timed("Connected and initialized", &ctx.output)
                 .await
         }
         _ => {
             // For other targets, connect directly without auth
-            ctx.output.text(format!("Connecting to {target}"))?;
-            connect_to_server(target, conn)
+            ctx.output
+                .text(format!("Connecting to {}", target.redacted()))?;
+            connect_to_server_with_options(target, conn, &ctx.connect_options)
                 .timed("Connected and initialized", &ctx.output)
                 .await
         }
@@ -52,17 +169,43 @@ async fn connect_with_auth<C: ClientConn + Send + 'static>(
     }
 
     let storage = ctx.storage()?;
-    let auth = storage.get_auth(auth_name)?;
-    if let Some(expires_at) = auth.expires_at {
-        if expires_at <= std::time::SystemTime::now() {
-            return Err(Error::Other(
-                "Access token has expired. Please re-authenticate with 'mcptool auth add/renew'"
-                    .to_string(),
-            ));
+    let mut auth = storage.get_auth(auth_name)?;
+
+    // The OAuth client makes its own requests and its own handshake, so when
+    // they need options it can't be given, the stored token is sent like a
+    // static one instead. It can't be refreshed during the session, so it is
+    // refreshed once up front.
+    if !ctx.connect_options.http.is_empty()
+        || ctx.connect_options.older_protocol_version().is_some()
+        || ctx.connect_options.transcript.is_some()
+    {
+        ctx.output.trace_warn(format!(
+            "The token for '{auth_name}' won't be refreshed during this session, since the connection needs options the OAuth client doesn't support"
+        ))?;
+        if auth.refresh_token.is_some()
+            && let Err(e) = crate::auth::refresh(ctx, &mut auth).await
+        {
+            ctx.output.trace_warn(format!(
+                "Failed to refresh the token for '{auth_name}': {e}"
+            ))?;
         }
+        check_expiry(auth.expires_at)?;
+        let token = HttpOptions {
+            bearer: Some(auth.access_token.ok_or_else(|| {
+                Error::Other(format!(
+                    "No access token stored for '{auth_name}'. Please re-authenticate with 'mcptool auth add'"
+                ))
+            })?),
+            ..Default::default()
+        };
+        let options = ConnectOptions {
+            http: token.merged(&ctx.connect_options.http),
+            ..ctx.connect_options.clone()
+        };
+        return connect_to_server_with_options(target, conn, &options).await;
     }
+    check_expiry(auth.expires_at)?;
 
-    // Create OAuth config
     let oauth_config = OAuth2Config {
         client_id: auth.client_id,
         client_secret: auth.client_secret,
@@ -71,7 +214,7 @@ async fn connect_with_auth<C: ClientConn + Send + 'static>(
         redirect_url: auth
             .redirect_url
             .unwrap_or_else(|| "http://localhost:0".to_string()),
-        resource: "".to_string(), // Empty resource, could be stored in auth if needed
+        resource: auth.resource.unwrap_or_default(),
         scopes: auth.scopes,
     };
 
@@ -99,8 +242,8 @@ async fn connect_with_auth<C: ClientConn + Send + 'static>(
     let mut client = Client::new_with_connection("mcptool", crate::ctx::VERSION, conn);
 
     let init_result = match target {
-        Target::Http { host, port } => {
-            let url = format!("http://{host}:{port}");
+        Target::Http { .. } => {
+            let url = target.to_string();
             client
                 .connect_http_with_oauth(&url, oauth_client)
                 .await
@@ -110,8 +253,8 @@ async fn connect_with_auth<C: ClientConn + Send + 'static>(
                     ))
                 })?
         }
-        Target::Https { host, port } => {
-            let url = format!("https://{host}:{port}");
+        Target::Https { .. } => {
+            let url = target.to_string();
             client
                 .connect_http_with_oauth(&url, oauth_client)
                 .await
@@ -127,52 +270,240 @@ async fn connect_with_auth<C: ClientConn + Send + 'static>(
     Ok((client, init_result))
 }
 
+/// Refuse a stored token that has already expired
+fn check_expiry(expires_at: Option<std::time::SystemTime>) -> Result<()> {
+    if let Some(expires_at) = expires_at
+        && expires_at <= std::time::SystemTime::now()
+    {
+        return Err(Error::Other(
+            "Access token has expired. Please re-authenticate with 'mcptool auth add/renew'"
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
+
 pub async fn connect_to_server<C: ClientConn + Send + 'static>(
     target: &Target,
     conn: C,
+) -> Result<(Client<C>, InitializeResult)> {
+    connect_to_server_with_options(target, conn, &ConnectOptions::default()).await
+}
+
+pub async fn connect_to_server_with_options<C: ClientConn + Send + 'static>(
+    target: &Target,
+    conn: C,
+    options: &ConnectOptions,
 ) -> Result<(Client<C>, InitializeResult)> {
     let mut client = Client::new_with_connection("mcptool", VERSION, conn);
 
     let init_result = match target {
-        Target::Tcp { host, port } => {
-            let addr = format!("{host}:{port}");
-            client.connect_tcp(&addr).await.map_err(|e| {
-                Error::Other(format!("Failed to connect to TCP address {addr}: {e}"))
-            })?
-        }
-        Target::Stdio { command, args } => {
+        Target::Stdio { command, args, env } => {
             let mut cmd = tokio::process::Command::new(command);
-            cmd.args(args);
+            cmd.args(args).kill_on_drop(true);
+            env.merged(&options.process_env).apply(&mut cmd)?;
+            cmd.stdin(Stdio::piped()).stdout(Stdio::piped());
+            let stderr_sink = StderrSink::new(options)?;
+            if !matches!(stderr_sink, StderrSink::Inherit) {
+                cmd.stderr(Stdio::piped());
+            }
 
-            let _child = client
-                .connect_process(cmd)
-                .await
+            let mut child = cmd
+                .spawn()
                 .map_err(|e| Error::Other(format!("Failed to spawn MCP server process: {e}")))?;
-
-            // The new API handles initialization automatically
-            client
-                .init()
+            let (Some(stdout), Some(stdin)) = (child.stdout.take(), child.stdin.take()) else {
+                return Err(Error::Internal(
+                    "Server process was spawned without stdio pipes".to_string(),
+                ));
+            };
+            supervise_process(command, child, stderr_sink, options);
+            connect_stream(&mut client, tokio::io::join(stdout, stdin), options)
                 .await
-                .map_err(|e| Error::Other(format!("Failed to initialize MCP client: {e}")))?
+                .map_err(|e| {
+                    Error::Other(format!("Failed to connect to MCP server process: {e}"))
+                })?;
+            initialize(&mut client, options).await?
         }
-        Target::Http { host, port } => {
-            let url = format!("http://{host}:{port}");
-            client.connect_http(&url).await.map_err(|e| {
-                Error::Other(format!("Failed to connect to HTTP endpoint {url}: {e}"))
-            })?
+        Target::Http { .. } | Target::Https { .. } => {
+            // Spoken here rather than by the client library, which can't be
+            // given our headers, TLS or proxy settings
+            let url = target.to_string();
+            let stream = http::connect(&url, &options.http).await?;
+            connect_stream(&mut client, stream, options)
+                .await
+                .map_err(|e| Error::Other(format!("Failed to connect to {url}: {e}")))?;
+            initialize(&mut client, options).await?
         }
-        Target::Https { host, port } => {
-            let url = format!("https://{host}:{port}");
-            client.connect_http(&url).await.map_err(|e| {
-                Error::Other(format!("Failed to connect to HTTPS endpoint {url}: {e}"))
-            })?
+        Target::Tcp { .. }
+        | Target::Tls { .. }
+        | Target::Sse { .. }
+        | Target::Sses { .. }
+        | Target::Ws { .. }
+        | Target::Wss { .. }
+        | Target::Unix { .. } => {
+            let stream = transport::connect(target, &options.http).await?;
+            connect_stream(&mut client, stream, options)
+                .await
+                .map_err(|e| {
+                    Error::Other(format!("Failed to connect to {}: {e}", target.redacted()))
+                })?;
+            initialize(&mut client, options).await?
         }
-        Target::Auth { .. } => {
+        Target::Auth { .. } | Target::Profile { .. } => {
             return Err(Error::Other(
-                "Auth targets should be resolved to actual targets before calling connect_to_server".to_string()
+                "Auth and profile targets should be resolved to actual targets before calling connect_to_server".to_string()
             ));
         }
     };
 
     Ok((client, init_result))
 }
+
+/// Connect the client over an open stream of newline-delimited JSON-RPC,
+/// recording it in the transcript if there is one. The stream closes when the
+/// client is dropped.
+async fn connect_stream<C, S>(
+    client: &mut Client<C>,
+    stream: S,
+    options: &ConnectOptions,
+) -> tenx_mcp::Result<()>
+where
+    C: ClientConn + Send + 'static,
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let stream: Box<dyn LineStream> = match &options.transcript {
+        Some(transcript) => Box::new(transcript.tap(stream)),
+        None => Box::new(stream),
+    };
+    let (reader, writer) = tokio::io::split(stream);
+    client
+        .connect(Box::new(GenericDuplex::new(reader, writer)))
+        .await
+}
+
+/// Where a server process's stderr goes
+enum StderrSink {
+    Inherit,
+    Display(Output),
+    File(tokio::fs::File),
+}
+
+impl StderrSink {
+    fn new(options: &ConnectOptions) -> Result<Self> {
+        if let Some(path) = &options.server_stderr {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| {
+                    Error::Other(format!(
+                        "Failed to open server stderr file {}: {e}",
+                        path.display()
+                    ))
+                })?;
+            return Ok(StderrSink::File(tokio::fs::File::from_std(file)));
+        }
+        // JSON output has nowhere to put free-form text, so leave stderr alone
+        match &options.server_output {
+            Some(output) if !output.json => Ok(StderrSink::Display(output.clone())),
+            _ => Ok(StderrSink::Inherit),
+        }
+    }
+}
+
+/// Forward a server process's stderr to its sink, and report the process
+/// exiting with a failure so that a crash isn't just an opaque transport error.
+///
+/// The child is owned by the supervising task and killed if that task is
+/// dropped, so servers don't outlive mcptool. It can also be killed through
+/// the options' [`ServerProcesses`], if there are any.
+fn supervise_process(command: &str, mut child: Child, sink: StderrSink, options: &ConnectOptions) {
+    if let Some(stderr) = child.stderr.take() {
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            match sink {
+                StderrSink::Display(output) => {
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        let _ = output.server_stderr(line);
+                    }
+                }
+                StderrSink::File(mut file) => {
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        if file
+                            .write_all(format!("{line}\n").as_bytes())
+                            .await
+                            .is_err()
+                        {
+                            break;
+                        }
+                        let _ = file.flush().await;
+                    }
+                }
+                StderrSink::Inherit => {}
+            }
+        });
+    }
+
+    let (stop, mut stopped) = oneshot::channel::<oneshot::Sender<()>>();
+    if let Some(processes) = &options.processes {
+        processes.add(stop);
+    }
+    let output = options.server_output.clone();
+    let command = command.to_string();
+    tokio::spawn(async move {
+        let status = tokio::select! {
+            status = child.wait() => status,
+            Ok(done) = &mut stopped => {
+                // Killed on purpose, so there's nothing to report
+                let _ = child.kill().await;
+                let _ = done.send(());
+                return;
+            }
+        };
+        let Some(output) = output else {
+            return;
+        };
+        let _ = match status {
+            Ok(status) if status.success() => Ok(()),
+            Ok(status) => {
+                output.trace_error(format!("Server process '{command}' exited: {status}"))
+            }
+            Err(e) => output.trace_error(format!("Failed to wait for server process: {e}")),
+        };
+    });
+}
+
+/// Perform the initialize handshake, requesting the protocol version asked for
+async fn initialize<C: ClientConn + Send + 'static>(
+    client: &mut Client<C>,
+    options: &ConnectOptions,
+) -> Result<InitializeResult> {
+    match options.older_protocol_version() {
+        Some(version) => initialize_with_version(client, version).await,
+        None => client.init().await,
+    }
+    .map_err(|e| Error::Other(format!("Failed to initialize MCP client: {e}")))
+}
+
+/// Perform the initialize handshake requesting a specific protocol version
+async fn initialize_with_version<C: ClientConn + Send + 'static>(
+    client: &mut Client<C>,
+    protocol_version: &str,
+) -> tenx_mcp::Result<InitializeResult> {
+    let client_info = Implementation {
+        name: "mcptool".to_string(),
+        version: VERSION.to_string(),
+        title: None,
+    };
+    let init_result = client
+        .initialize(
+            protocol_version.to_string(),
+            ClientCapabilities::default(),
+            client_info,
+        )
+        .await?;
+    client
+        .send_notification(ClientNotification::Initialized)
+        .await?;
+    Ok(init_result)
+}
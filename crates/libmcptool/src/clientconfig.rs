@@ -0,0 +1,326 @@
+//! Server definitions in the `mcpServers` JSON format of MCP clients such as
+//! Claude Desktop, Cursor and VS Code, imported as profiles and exported from
+//! them.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::{
+    Error, Result,
+    ctx::Ctx,
+    profile::{Config, Profile},
+    sse,
+    target::Target,
+};
+
+/// One server in an MCP client configuration
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServerEntry {
+    /// `stdio`, `http` or `sse`. Optional in most clients, which go by
+    /// whether there is a command or a URL.
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+}
+
+impl ServerEntry {
+    /// The profile for this server
+    pub fn to_profile(&self) -> Result<Profile> {
+        let target = match (&self.command, &self.url) {
+            (Some(command), _) => {
+                let words = std::iter::once(command).chain(&self.args);
+                format!("cmd://{}", shell_words::join(words))
+            }
+            (None, Some(url)) if self.kind.as_deref() == Some("sse") => {
+                if let Some(rest) = url.strip_prefix("http://") {
+                    format!("sse://{rest}")
+                } else if let Some(rest) = url.strip_prefix("https://") {
+                    format!("sses://{rest}")
+                } else {
+                    return Err(Error::Format(format!(
+                        "SSE servers need an http:// or https:// URL, not {url}"
+                    )));
+                }
+            }
+            (None, Some(url)) => url.clone(),
+            (None, None) => {
+                return Err(Error::Format(
+                    "The server has neither a command nor a URL".to_string(),
+                ));
+            }
+        };
+        // Check that mcptool can connect to it
+        Target::parse(&target)?;
+        Ok(Profile {
+            target,
+            env: self.env.clone(),
+            headers: self.headers.clone(),
+            ..Default::default()
+        })
+    }
+
+    /// The entry for a profile, if its target is one MCP clients can use
+    pub fn from_profile(profile: &Profile) -> Result<Self> {
+        let target = Target::parse(&profile.target)?;
+        match target {
+            Target::Stdio { command, args, env } => {
+                if env.cwd.is_some() || env.clear || !env.env_files.is_empty() {
+                    return Err(Error::Format(
+                        "--cwd, --env-file and --clear-env can't be written to MCP client configs"
+                            .to_string(),
+                    ));
+                }
+                let mut vars: BTreeMap<_, _> = env.vars.into_iter().collect();
+                vars.extend(profile.env.clone());
+                Ok(ServerEntry {
+                    command: Some(command),
+                    args,
+                    env: vars,
+                    ..Default::default()
+                })
+            }
+            Target::Http { .. } | Target::Https { .. } => Ok(ServerEntry {
+                kind: Some("http".to_string()),
+                url: Some(target.to_string()),
+                headers: profile.headers.clone(),
+                ..Default::default()
+            }),
+            Target::Sse { host, port, path } => Ok(ServerEntry {
+                kind: Some("sse".to_string()),
+                url: Some(sse::stream_url("http", &host, port, &path)),
+                headers: profile.headers.clone(),
+                ..Default::default()
+            }),
+            Target::Sses { host, port, path } => Ok(ServerEntry {
+                kind: Some("sse".to_string()),
+                url: Some(sse::stream_url("https", &host, port, &path)),
+                headers: profile.headers.clone(),
+                ..Default::default()
+            }),
+            other => Err(Error::Format(format!(
+                "{other} can't be written to MCP client configs, which only support commands and HTTP servers"
+            ))),
+        }
+    }
+}
+
+/// The servers in a client configuration: `mcpServers` (Claude Desktop,
+/// Cursor), `servers` (VS Code's mcp.json) or `mcp.servers` (VS Code's
+/// settings.json)
+pub fn parse_servers(config: &Value) -> Result<BTreeMap<String, ServerEntry>> {
+    let servers = config
+        .get("mcpServers")
+        .or_else(|| config.get("servers"))
+        .or_else(|| config.pointer("/mcp/servers"))
+        .ok_or_else(|| {
+            Error::Format("No mcpServers or servers section in the configuration".to_string())
+        })?;
+    Ok(serde_json::from_value(servers.clone())?)
+}
+
+/// A valid profile name for a server, which may use other characters
+pub fn profile_name(server: &str) -> String {
+    server
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+pub struct ImportCommandArgs {
+    pub file: PathBuf,
+    /// Replace profiles that already exist
+    pub overwrite: bool,
+}
+
+pub async fn import_command(ctx: &Ctx, args: ImportCommandArgs) -> Result<()> {
+    let contents = std::fs::read_to_string(&args.file)
+        .map_err(|e| Error::Other(format!("Failed to read {}: {e}", args.file.display())))?;
+    let servers = parse_servers(&serde_json::from_str(&contents)?)?;
+
+    let mut config = Config::load(&ctx.config_path)?;
+    let mut imported = vec![];
+    let mut skipped = vec![];
+    for (server, entry) in &servers {
+        let name = profile_name(server);
+        if imported.contains(&name) || (config.profiles.contains_key(&name) && !args.overwrite) {
+            skipped.push((name, "a profile with this name exists".to_string()));
+            continue;
+        }
+        match entry.to_profile() {
+            Ok(profile) => {
+                config.profiles.insert(name.clone(), profile);
+                imported.push(name);
+            }
+            Err(e) => skipped.push((name, e.to_string())),
+        }
+    }
+    config.save(&ctx.config_path)?;
+
+    if ctx.output.json {
+        let skipped: Vec<_> = skipped
+            .iter()
+            .map(|(name, reason)| json!({"name": name, "reason": reason}))
+            .collect();
+        return ctx
+            .output
+            .json_value(&json!({"imported": imported, "skipped": skipped}));
+    }
+    for name in &imported {
+        ctx.output
+            .text(format!("Imported {name}: {}", config.profile(name)?.target))?;
+    }
+    for (name, reason) in &skipped {
+        ctx.output.trace_warn(format!("Skipped {name}: {reason}"))?;
+    }
+    ctx.output.trace_success(format!(
+        "Imported {} of {} server(s) from {}",
+        imported.len(),
+        servers.len(),
+        args.file.display()
+    ))?;
+    Ok(())
+}
+
+pub struct ExportCommandArgs {
+    /// Write to this file instead of stdout
+    pub out: Option<PathBuf>,
+    /// Profiles to export, or all of them if empty
+    pub profiles: Vec<String>,
+}
+
+pub async fn export_command(ctx: &Ctx, args: ExportCommandArgs) -> Result<()> {
+    let config = Config::load(&ctx.config_path)?;
+    let names: Vec<String> = if args.profiles.is_empty() {
+        config.profiles.keys().cloned().collect()
+    } else {
+        args.profiles
+    };
+
+    let mut servers = BTreeMap::new();
+    for name in names {
+        let profile = config.profile(&name)?;
+        match ServerEntry::from_profile(profile) {
+            Ok(entry) => {
+                if let Some(auth) = &profile.auth {
+                    ctx.output.trace_warn(format!(
+                        "{name} uses auth entry '{auth}', whose credentials are not exported"
+                    ))?;
+                }
+                servers.insert(name, entry);
+            }
+            Err(e) => ctx.output.trace_warn(format!("Skipped {name}: {e}"))?,
+        }
+    }
+
+    let exported = json!({ "mcpServers": servers });
+    match &args.out {
+        Some(path) => {
+            std::fs::write(path, serde_json::to_string_pretty(&exported)?)?;
+            ctx.output.trace_success(format!(
+                "Exported {} server(s) to {}",
+                servers.len(),
+                path.display()
+            ))?;
+            Ok(())
+        }
+        None => ctx.output.json_value(&exported),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_servers() {
+        let config = json!({
+            "mcpServers": {
+                "filesystem": {
+                    "command": "npx",
+                    "args": ["-y", "@modelcontextprotocol/server-filesystem", "/Users/me/My Files"],
+                    "env": {"DEBUG": "1"}
+                },
+                "remote-api": {
+                    "url": "https://mcp.example.com/v1",
+                    "headers": {"Authorization": "Bearer abc"}
+                },
+                "legacy": {"type": "sse", "url": "http://localhost:3000/sse"}
+            }
+        });
+        let servers = parse_servers(&config).unwrap();
+
+        let filesystem = servers["filesystem"].to_profile().unwrap();
+        assert_eq!(
+            filesystem.target,
+            "cmd://npx -y @modelcontextprotocol/server-filesystem '/Users/me/My Files'"
+        );
+        assert_eq!(filesystem.env["DEBUG"], "1");
+
+        let remote = servers["remote-api"].to_profile().unwrap();
+        assert_eq!(remote.target, "https://mcp.example.com/v1");
+        assert_eq!(remote.headers["Authorization"], "Bearer abc");
+        assert_eq!(profile_name("remote-api"), "remote_api");
+
+        let legacy = servers["legacy"].to_profile().unwrap();
+        assert_eq!(legacy.target, "sse://localhost:3000/sse");
+
+        // VS Code keeps servers under "servers" or "mcp.servers"
+        let vscode = json!({"mcp": {"servers": {"local": {"type": "stdio", "command": "server"}}}});
+        assert!(parse_servers(&vscode).unwrap().contains_key("local"));
+        assert!(parse_servers(&json!({"other": {}})).is_err());
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let entries = [
+            ServerEntry {
+                command: Some("uvx".to_string()),
+                args: vec![
+                    "mcp-server-git".to_string(),
+                    "--repository".to_string(),
+                    "/src/my repo".to_string(),
+                ],
+                env: BTreeMap::from([("TOKEN".to_string(), "x".to_string())]),
+                ..Default::default()
+            },
+            ServerEntry {
+                kind: Some("http".to_string()),
+                url: Some("https://mcp.example.com/v1?team=tools".to_string()),
+                headers: BTreeMap::from([("X-Team".to_string(), "tools".to_string())]),
+                ..Default::default()
+            },
+            ServerEntry {
+                kind: Some("sse".to_string()),
+                url: Some("http://localhost:3000/sse".to_string()),
+                ..Default::default()
+            },
+            ServerEntry {
+                kind: Some("sse".to_string()),
+                url: Some("https://mcp.example.com/v1/sse".to_string()),
+                ..Default::default()
+            },
+        ];
+        for entry in entries {
+            let profile = entry.to_profile().unwrap();
+            assert_eq!(ServerEntry::from_profile(&profile).unwrap(), entry);
+        }
+
+        let tcp = Profile {
+            target: "tcp://localhost:3000".to_string(),
+            ..Default::default()
+        };
+        assert!(ServerEntry::from_profile(&tcp).is_err());
+    }
+}
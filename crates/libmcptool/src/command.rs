@@ -1,4 +1,7 @@
+use std::path::PathBuf;
+
 use clap::{Args, CommandFactory, Parser, Subcommand};
+use serde_json::{Map, Value, json};
 use tenx_mcp::{Client, ClientConn, schema::InitializeResult};
 
 use crate::{Result, client, ctx::Ctx, mcp, target::Target};
@@ -9,7 +12,7 @@ pub struct McpArgs {
 }
 
 // Base commands without target - used by both CLI and REPL
-#[derive(Subcommand)]
+#[derive(Subcommand, Clone)]
 #[command(no_binary_name = true)]
 pub enum McpCommand {
     /// Send a ping request to an MCP server
@@ -52,6 +55,10 @@ pub enum McpCommand {
         /// JSON mode: read arguments from stdin as JSON
         #[arg(long, short)]
         json: bool,
+
+        /// Arguments already given as JSON, by callers such as test suites
+        #[arg(skip)]
+        arguments: Option<Map<String, Value>>,
     },
 
     /// Read a resource by URI
@@ -68,6 +75,10 @@ pub enum McpCommand {
         /// Arguments in key=value format (can be specified multiple times)
         #[arg(long = "arg", short = 'a')]
         args: Vec<String>,
+
+        /// Arguments already given as JSON, by callers such as test suites
+        #[arg(skip)]
+        arguments: Option<Map<String, Value>>,
     },
 
     /// Subscribe to resource update notifications
@@ -92,11 +103,32 @@ pub enum McpCommand {
     },
 }
 
-// For CLI use - target is required at this level
+// For CLI use - a target, or a set of targets to run the command against
 #[derive(Parser)]
+#[command(subcommand_precedence_over_arg = true)]
 pub struct CliMcpCommand {
     /// The MCP server target (e.g., "localhost:3000", "tcp://host:port", "http://host:port", "auth://name")
-    pub target: String,
+    #[arg(
+        required_unless_present_any = ["all_profiles", "targets"],
+        conflicts_with_all = ["all_profiles", "targets"]
+    )]
+    pub target: Option<String>,
+
+    /// Run the command against every profile
+    #[arg(long, conflicts_with = "targets")]
+    pub all_profiles: bool,
+
+    /// Run the command against each target listed in a file, one per line
+    #[arg(long, value_name = "FILE")]
+    pub targets: Option<PathBuf>,
+
+    /// Maximum number of targets to run against at once
+    #[arg(long, short = 'j', default_value_t = 8)]
+    pub jobs: usize,
+
+    /// Seconds to wait for each target, with --all-profiles or --targets
+    #[arg(long, default_value_t = 30, conflicts_with = "target")]
+    pub timeout: u64,
 
     #[command(subcommand)]
     pub command: McpCommand,
@@ -110,70 +142,89 @@ pub struct ReplCommandWrapper {
     pub command: McpCommand,
 }
 
-// For REPL use - reuses existing client connection
+// For REPL use - reuses existing client connection. Returns the server's
+// response as JSON so callers can record or inspect it.
 pub async fn execute_mcp_command_with_client<C: ClientConn + 'static>(
     command: McpCommand,
     client: &mut Client<C>,
     init_result: &InitializeResult,
     ctx: &Ctx,
-) -> Result<()> {
-    match command {
+) -> Result<Value> {
+    let value = match command {
         McpCommand::Ping => {
             mcp::ping(client, &ctx.output).await?;
+            json!({})
         }
-        McpCommand::Listtools => {
-            mcp::listtools(client, &ctx.output).await?;
-        }
+        McpCommand::Listtools => serde_json::to_value(mcp::listtools(client, &ctx.output).await?)?,
         McpCommand::Init => {
             mcp::init(init_result, &ctx.output)?;
+            serde_json::to_value(init_result)?
         }
         McpCommand::Listresources => {
-            mcp::listresources(client, &ctx.output).await?;
+            serde_json::to_value(mcp::listresources(client, &ctx.output).await?)?
         }
         McpCommand::Listprompts => {
-            mcp::listprompts(client, &ctx.output).await?;
+            serde_json::to_value(mcp::listprompts(client, &ctx.output).await?)?
         }
         McpCommand::Listresourcetemplates => {
-            mcp::listresourcetemplates(client, &ctx.output).await?;
+            serde_json::to_value(mcp::listresourcetemplates(client, &ctx.output).await?)?
         }
         McpCommand::Setlevel { level } => {
             mcp::set_level(client, &ctx.output, &level).await?;
+            json!({})
         }
         McpCommand::Calltool {
             tool_name,
             args,
             interactive,
             json,
-        } => {
-            mcp::calltool(client, &ctx.output, &tool_name, args, interactive, json).await?;
-        }
+            arguments,
+        } => serde_json::to_value(
+            mcp::calltool(
+                client,
+                &ctx.output,
+                &tool_name,
+                args,
+                interactive,
+                json,
+                arguments,
+            )
+            .await?,
+        )?,
         McpCommand::Readresource { uri } => {
-            mcp::read_resource(client, &ctx.output, &uri).await?;
-        }
-        McpCommand::Getprompt { name, args } => {
-            mcp::get_prompt(client, &ctx.output, &name, args).await?;
+            serde_json::to_value(mcp::read_resource(client, &ctx.output, &uri).await?)?
         }
+        McpCommand::Getprompt {
+            name,
+            args,
+            arguments,
+        } => serde_json::to_value(
+            mcp::get_prompt(client, &ctx.output, &name, args, arguments).await?,
+        )?,
         McpCommand::Subscriberesource { uri } => {
             mcp::subscribe_resource(client, &ctx.output, &uri).await?;
+            json!({})
         }
         McpCommand::Unsubscriberesource { uri } => {
             mcp::unsubscribe_resource(client, &ctx.output, &uri).await?;
+            json!({})
         }
         McpCommand::Complete {
             reference,
             argument,
         } => {
-            mcp::complete(client, &ctx.output, &reference, &argument).await?;
+            serde_json::to_value(mcp::complete(client, &ctx.output, &reference, &argument).await?)?
         }
-    }
-    Ok(())
+    };
+    Ok(value)
 }
 
 // For CLI use - creates new client connection for single command
 pub async fn execute_mcp_command(command: McpCommand, target: &str, ctx: &Ctx) -> Result<()> {
     let target = Target::parse(target)?;
     let (mut client, init_result) = client::get_client(ctx, &target).await?;
-    execute_mcp_command_with_client(command, &mut client, &init_result, ctx).await
+    execute_mcp_command_with_client(command, &mut client, &init_result, ctx).await?;
+    Ok(())
 }
 
 /// Generate help text for the REPL using clap's built-in help generation
@@ -196,6 +247,7 @@ pub fn generate_repl_help() -> String {
 
     help.push_str("\nAdditional REPL commands:\n");
     help.push_str("  help                 - Show this help message\n");
+    help.push_str("  reconnect            - Reconnect and re-initialize the session\n");
     help.push_str("  quit/exit            - Exit the REPL\n");
 
     help
@@ -0,0 +1,614 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::{Value, json};
+use tenx_mcp::schema::InitializeResult;
+
+use crate::{
+    Error, Result,
+    ctx::Ctx,
+    output::initresult,
+    rawclient::{PROTOCOL_VERSIONS, RawClient, Reply},
+    target::Target,
+};
+
+/// JSON-RPC error codes the spec requires for specific failures
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const RESOURCE_NOT_FOUND: i64 = -32002;
+
+/// Stop following cursors after this many pages, in case a server never ends
+const MAX_PAGES: usize = 1000;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", content = "detail", rename_all = "lowercase")]
+pub enum Status {
+    Pass,
+    Fail(String),
+    Skip(String),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    pub name: String,
+    #[serde(flatten)]
+    pub status: Status,
+}
+
+/// A list endpoint and the fields that identify its items
+struct ListMethod {
+    method: &'static str,
+    items: &'static str,
+    key: &'static str,
+}
+
+const TOOLS: ListMethod = ListMethod {
+    method: "tools/list",
+    items: "tools",
+    key: "name",
+};
+const PROMPTS: ListMethod = ListMethod {
+    method: "prompts/list",
+    items: "prompts",
+    key: "name",
+};
+const RESOURCES: ListMethod = ListMethod {
+    method: "resources/list",
+    items: "resources",
+    key: "uri",
+};
+const RESOURCE_TEMPLATES: ListMethod = ListMethod {
+    method: "resources/templates/list",
+    items: "resourceTemplates",
+    key: "uriTemplate",
+};
+
+pub struct ConformanceCommandArgs {
+    pub target: String,
+    pub timeout: Duration,
+}
+
+pub async fn conformance_command(ctx: &Ctx, args: ConformanceCommandArgs) -> Result<()> {
+    let target = Target::parse(&args.target)?;
+    ctx.output
+        .text(format!("Checking {} for conformance", target.redacted()))?;
+
+    let mut checker = Checker {
+        ctx,
+        target: &target,
+        timeout: args.timeout,
+        results: vec![],
+    };
+    checker.run().await?;
+    let results = checker.results;
+
+    if ctx.output.json {
+        ctx.output.json_value(&results)?;
+    } else {
+        ctx.output.h1("Conformance checks")?;
+        let out = ctx.output.indent();
+        for result in &results {
+            match &result.status {
+                Status::Pass => out.success(format!("PASS {}", result.name))?,
+                Status::Fail(detail) => {
+                    out.trace_error(format!("FAIL {}", result.name))?;
+                    out.indent().text(detail)?;
+                }
+                Status::Skip(detail) => out.note(format!("SKIP {}: {detail}", result.name))?,
+            }
+        }
+    }
+
+    let failed = results
+        .iter()
+        .filter(|r| matches!(r.status, Status::Fail(_)))
+        .count();
+    if failed > 0 {
+        return Err(Error::Other(format!(
+            "{failed} of {} conformance check(s) failed",
+            results.len()
+        )));
+    }
+    Ok(())
+}
+
+struct Checker<'a> {
+    ctx: &'a Ctx,
+    target: &'a Target,
+    timeout: Duration,
+    results: Vec<CheckResult>,
+}
+
+impl Checker<'_> {
+    fn record(&mut self, name: impl Into<String>, status: Status) {
+        self.results.push(CheckResult {
+            name: name.into(),
+            status,
+        });
+    }
+
+    async fn connect(&self) -> Result<RawClient> {
+        RawClient::connect(self.ctx, self.target, self.timeout).await
+    }
+
+    async fn run(&mut self) -> Result<()> {
+        let latest = PROTOCOL_VERSIONS[PROTOCOL_VERSIONS.len() - 1];
+        let mut client = match self.connect().await {
+            Ok(client) => client,
+            Err(e) => {
+                self.record(
+                    "initialize handshake",
+                    Status::Fail(format!("Failed to connect: {e}")),
+                );
+                return Ok(());
+            }
+        };
+
+        // Servers must tolerate notifications that arrive before initialize
+        let early_notification = client
+            .notify("notifications/roots/list_changed", json!({}))
+            .await;
+
+        let init = match client.handshake(latest).await {
+            Ok(result) => result,
+            Err(e) => {
+                self.record(
+                    "initialize handshake",
+                    Status::Fail(format!("initialize failed: {e}")),
+                );
+                return Ok(());
+            }
+        };
+        // Anything the server said about the notification has arrived by the
+        // time the handshake completes
+        self.record(
+            "notification before initialize is ignored",
+            match early_notification {
+                Err(e) => Status::Fail(format!("Server rejected the notification: {e}")),
+                Ok(()) => no_responses(&client.unexpected),
+            },
+        );
+        let answered = client.unexpected.len();
+
+        let init_result = match validate_initialize(&init) {
+            Ok(init_result) => {
+                self.record("initialize handshake", Status::Pass);
+                init_result
+            }
+            Err(problem) => {
+                self.record("initialize handshake", Status::Fail(problem));
+                return Ok(());
+            }
+        };
+        if !self.ctx.output.json {
+            initresult::init_result(&self.ctx.output, &init_result)?;
+        }
+
+        self.check_version_negotiation().await;
+
+        let status = check(&mut client, "ping", json!({}), |reply| match reply {
+            Reply::Result(Value::Object(map)) if map.is_empty() => Status::Pass,
+            Reply::Result(other) => Status::Fail(format!("Expected an empty result, got {other}")),
+            Reply::Error { code, message, .. } => {
+                Status::Fail(format!("JSON-RPC error {code}: {message}"))
+            }
+        })
+        .await;
+        self.record("ping", status);
+
+        let status = check(&mut client, "mcptool/nonexistent", json!({}), |reply| {
+            expect_error(&reply, &[METHOD_NOT_FOUND])
+        })
+        .await;
+        self.record("unknown method returns -32601", status);
+
+        let capabilities = &init_result.capabilities;
+        let mut lists = vec![];
+        if capabilities.tools.is_some() {
+            lists.push(TOOLS);
+        }
+        if capabilities.prompts.is_some() {
+            lists.push(PROMPTS);
+        }
+        if capabilities.resources.is_some() {
+            lists.push(RESOURCES);
+            lists.push(RESOURCE_TEMPLATES);
+        }
+        for (name, advertised) in [
+            ("tools", capabilities.tools.is_some()),
+            ("prompts", capabilities.prompts.is_some()),
+            ("resources", capabilities.resources.is_some()),
+        ] {
+            if !advertised {
+                self.record(
+                    format!("{name} capability"),
+                    Status::Skip("not advertised".to_string()),
+                );
+            }
+        }
+
+        let mut fetched: Vec<(&'static str, Vec<Value>)> = vec![];
+        for list in &lists {
+            let (status, items) = fetch_all(&mut client, list).await;
+            self.record(format!("{} with pagination", list.method), status);
+            fetched.push((list.items, items));
+        }
+        let items = |key: &str| {
+            fetched
+                .iter()
+                .find(|(items, _)| *items == key)
+                .map(|(_, items)| items.clone())
+                .unwrap_or_default()
+        };
+
+        match lists.first() {
+            Some(list) => {
+                let status = check(
+                    &mut client,
+                    list.method,
+                    json!({"cursor": "mcptool-invalid-cursor"}),
+                    |reply| expect_error(&reply, &[INVALID_PARAMS]),
+                )
+                .await;
+                self.record("invalid cursor returns -32602", status);
+            }
+            None => self.record(
+                "invalid cursor returns -32602",
+                Status::Skip("no list capabilities advertised".to_string()),
+            ),
+        }
+
+        if capabilities.tools.is_some() {
+            let status = check(
+                &mut client,
+                "tools/call",
+                json!({"name": "mcptool-nonexistent-tool", "arguments": {}}),
+                |reply| expect_error(&reply, &[INVALID_PARAMS]),
+            )
+            .await;
+            self.record("unknown tool returns -32602", status);
+        }
+
+        if let Some(resources) = &capabilities.resources {
+            let status = check(
+                &mut client,
+                "resources/read",
+                json!({"uri": "mcptool://nonexistent-resource"}),
+                |reply| expect_error(&reply, &[RESOURCE_NOT_FOUND]),
+            )
+            .await;
+            self.record("unknown resource returns -32002", status);
+
+            let name = "resources.subscribe capability";
+            let resources_list = items("resources");
+            let uri = resources_list
+                .first()
+                .and_then(|r| r.get("uri"))
+                .and_then(Value::as_str);
+            match (resources.subscribe.unwrap_or(false), uri) {
+                (false, _) => self.record(name, Status::Skip("not advertised".to_string())),
+                (true, None) => self.record(
+                    name,
+                    Status::Skip("no resources to subscribe to".to_string()),
+                ),
+                (true, Some(uri)) => {
+                    let mut status = check(
+                        &mut client,
+                        "resources/subscribe",
+                        json!({"uri": uri}),
+                        |reply| expect_result(&reply, "resources/subscribe"),
+                    )
+                    .await;
+                    if status == Status::Pass {
+                        status = check(
+                            &mut client,
+                            "resources/unsubscribe",
+                            json!({"uri": uri}),
+                            |reply| expect_result(&reply, "resources/unsubscribe"),
+                        )
+                        .await;
+                    }
+                    self.record(name, status);
+                }
+            }
+        }
+
+        if capabilities.logging.is_some() {
+            let status = check(
+                &mut client,
+                "logging/setLevel",
+                json!({"level": "info"}),
+                |reply| expect_result(&reply, "logging/setLevel"),
+            )
+            .await;
+            self.record("logging capability", status);
+        } else {
+            self.record(
+                "logging capability",
+                Status::Skip("not advertised".to_string()),
+            );
+        }
+
+        let name = "completions capability";
+        if capabilities.completions.is_some() {
+            let prompt = items("prompts").into_iter().find_map(|prompt| {
+                let argument = prompt
+                    .get("arguments")?
+                    .as_array()?
+                    .first()?
+                    .get("name")?
+                    .as_str()?
+                    .to_string();
+                Some((prompt.get("name")?.as_str()?.to_string(), argument))
+            });
+            match prompt {
+                Some((prompt, argument)) => {
+                    let status = check(
+                        &mut client,
+                        "completion/complete",
+                        json!({
+                            "ref": {"type": "ref/prompt", "name": prompt},
+                            "argument": {"name": argument, "value": ""},
+                        }),
+                        |reply| expect_result(&reply, "completion/complete"),
+                    )
+                    .await;
+                    self.record(name, status);
+                }
+                None => self.record(
+                    name,
+                    Status::Skip("no prompt arguments to complete".to_string()),
+                ),
+            }
+        } else {
+            self.record(name, Status::Skip("not advertised".to_string()));
+        }
+
+        self.record(
+            "notifications receive no response",
+            no_responses(&client.unexpected[answered..]),
+        );
+
+        Ok(())
+    }
+
+    /// Servers must answer a request for an unsupported version with a
+    /// version they do support, rather than an error
+    async fn check_version_negotiation(&mut self) {
+        let name = "unsupported protocol version is negotiated";
+        let reply = match self.connect().await {
+            Ok(mut client) => client.initialize("1999-01-01").await,
+            Err(e) => Err(e),
+        };
+        let status = match reply {
+            Ok(Reply::Result(result)) => {
+                match result.get("protocolVersion").and_then(Value::as_str) {
+                    Some(version) if PROTOCOL_VERSIONS.contains(&version) => Status::Pass,
+                    Some(version) => {
+                        Status::Fail(format!("Server answered with unknown version {version}"))
+                    }
+                    None => Status::Fail("Result has no protocolVersion".to_string()),
+                }
+            }
+            Ok(Reply::Error { code, message, .. }) => Status::Fail(format!(
+                "Server rejected the request ({code}: {message}) instead of offering a supported version"
+            )),
+            Err(e) => Status::Fail(e.to_string()),
+        };
+        self.record(name, status);
+    }
+}
+
+/// Send a request and judge the reply. Transport errors and timeouts fail
+/// the check, rather than ending the run.
+async fn check(
+    client: &mut RawClient,
+    method: &str,
+    params: Value,
+    judge: impl FnOnce(Reply) -> Status,
+) -> Status {
+    match client.request(method, params).await {
+        Ok(reply) => judge(reply),
+        Err(e) => Status::Fail(e.to_string()),
+    }
+}
+
+/// Pass if the server sent no responses that matched none of our requests
+fn no_responses(unexpected: &[Value]) -> Status {
+    if unexpected.is_empty() {
+        return Status::Pass;
+    }
+    Status::Fail(format!(
+        "Received {} response(s) that matched no request: {}",
+        unexpected.len(),
+        Value::Array(unexpected.to_vec())
+    ))
+}
+
+/// Check that an initialize result is well formed
+fn validate_initialize(result: &Value) -> std::result::Result<InitializeResult, String> {
+    let init_result: InitializeResult = serde_json::from_value(result.clone())
+        .map_err(|e| format!("Malformed initialize result: {e}"))?;
+    if !PROTOCOL_VERSIONS.contains(&init_result.protocol_version.as_str()) {
+        return Err(format!(
+            "Server answered with unknown protocol version {}",
+            init_result.protocol_version
+        ));
+    }
+    if init_result.server_info.name.is_empty() {
+        return Err("serverInfo.name is empty".to_string());
+    }
+    Ok(init_result)
+}
+
+fn expect_error(reply: &Reply, codes: &[i64]) -> Status {
+    match reply {
+        Reply::Error { code, .. } if codes.contains(code) => Status::Pass,
+        Reply::Error { code, message, .. } => Status::Fail(format!(
+            "Expected error {}, got {code}: {message}",
+            codes[0]
+        )),
+        Reply::Result(result) => Status::Fail(format!(
+            "Expected error {}, got a result: {result}",
+            codes[0]
+        )),
+    }
+}
+
+fn expect_result(reply: &Reply, method: &str) -> Status {
+    match reply {
+        Reply::Result(_) => Status::Pass,
+        Reply::Error { code, message, .. } => Status::Fail(format!(
+            "Advertised, but {method} failed with {code}: {message}"
+        )),
+    }
+}
+
+/// Fetch every page of a list, checking that cursors progress and items
+/// aren't repeated across pages. Returns the status and all items fetched.
+async fn fetch_all(client: &mut RawClient, list: &ListMethod) -> (Status, Vec<Value>) {
+    let mut pages = vec![];
+    let mut cursor: Option<String> = None;
+    loop {
+        let params = match &cursor {
+            Some(cursor) => json!({"cursor": cursor}),
+            None => json!({}),
+        };
+        let page = match client.request(list.method, params).await {
+            Ok(Reply::Result(page)) => page,
+            Ok(error) => return (expect_result(&error, list.method), vec![]),
+            Err(e) => return (Status::Fail(e.to_string()), vec![]),
+        };
+        cursor = page
+            .get("nextCursor")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        pages.push(page);
+        if cursor.is_none() || pages.len() >= MAX_PAGES {
+            break;
+        }
+    }
+    check_pages(&pages, list)
+}
+
+fn check_pages(pages: &[Value], list: &ListMethod) -> (Status, Vec<Value>) {
+    let mut items = vec![];
+    let mut keys = HashSet::new();
+    let mut cursors = HashSet::new();
+    for (idx, page) in pages.iter().enumerate() {
+        let Some(page_items) = page.get(list.items).and_then(Value::as_array) else {
+            return (
+                Status::Fail(format!("Page {} has no '{}' array", idx + 1, list.items)),
+                items,
+            );
+        };
+        for item in page_items {
+            let key = item
+                .get(list.key)
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            if !keys.insert(key.to_string()) {
+                return (
+                    Status::Fail(format!(
+                        "'{key}' appears more than once across {} page(s)",
+                        pages.len()
+                    )),
+                    items,
+                );
+            }
+            items.push(item.clone());
+        }
+        if let Some(cursor) = page.get("nextCursor").and_then(Value::as_str)
+            && !cursors.insert(cursor.to_string())
+        {
+            return (
+                Status::Fail(format!("Cursor '{cursor}' was returned more than once")),
+                items,
+            );
+        }
+    }
+    if pages.len() >= MAX_PAGES {
+        return (
+            Status::Fail(format!("Gave up after {MAX_PAGES} pages")),
+            items,
+        );
+    }
+    (Status::Pass, items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_pages() {
+        let pages = vec![
+            json!({"tools": [{"name": "a"}, {"name": "b"}], "nextCursor": "1"}),
+            json!({"tools": [{"name": "c"}]}),
+        ];
+        let (status, items) = check_pages(&pages, &TOOLS);
+        assert_eq!(status, Status::Pass);
+        assert_eq!(items.len(), 3);
+
+        let pages = vec![
+            json!({"tools": [{"name": "a"}], "nextCursor": "1"}),
+            json!({"tools": [{"name": "a"}]}),
+        ];
+        assert!(matches!(check_pages(&pages, &TOOLS).0, Status::Fail(_)));
+
+        let pages = vec![
+            json!({"tools": [{"name": "a"}], "nextCursor": "1"}),
+            json!({"tools": [{"name": "b"}], "nextCursor": "1"}),
+        ];
+        assert!(matches!(check_pages(&pages, &TOOLS).0, Status::Fail(_)));
+
+        let pages = vec![json!({"prompts": []})];
+        assert!(matches!(check_pages(&pages, &TOOLS).0, Status::Fail(_)));
+    }
+
+    #[test]
+    fn test_expect_error() {
+        let not_found = Reply::Error {
+            code: METHOD_NOT_FOUND,
+            message: "Method not found".to_string(),
+            data: None,
+        };
+        assert_eq!(expect_error(&not_found, &[METHOD_NOT_FOUND]), Status::Pass);
+        assert!(matches!(
+            expect_error(&not_found, &[INVALID_PARAMS]),
+            Status::Fail(_)
+        ));
+        assert!(matches!(
+            expect_error(&Reply::Result(json!({})), &[METHOD_NOT_FOUND]),
+            Status::Fail(_)
+        ));
+    }
+
+    #[test]
+    fn test_no_responses() {
+        assert_eq!(no_responses(&[]), Status::Pass);
+        let error = json!({"jsonrpc": "2.0", "id": null, "error": {"code": -32600}});
+        assert!(matches!(no_responses(&[error]), Status::Fail(_)));
+    }
+
+    #[test]
+    fn test_check_result_json() {
+        let result = CheckResult {
+            name: "ping".to_string(),
+            status: Status::Fail("timeout".to_string()),
+        };
+        assert_eq!(
+            serde_json::to_value(&result).unwrap(),
+            json!({"name": "ping", "status": "fail", "detail": "timeout"})
+        );
+        let result = CheckResult {
+            name: "ping".to_string(),
+            status: Status::Pass,
+        };
+        assert_eq!(
+            serde_json::to_value(&result).unwrap(),
+            json!({"name": "ping", "status": "pass"})
+        );
+    }
+}
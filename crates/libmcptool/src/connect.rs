@@ -1,14 +1,28 @@
+mod completer;
+mod script;
+mod session;
+mod watch;
+
+use std::path::{Path, PathBuf};
+
 use clap::Parser;
-use rustyline::DefaultEditor;
+use rustyline::{Editor, history::DefaultHistory};
 use tenx_mcp::{ClientConn, ClientCtx, Result as McpResult, schema::ServerNotification};
 use tokio::sync::mpsc;
 
+pub use completer::McpCompleter;
+use session::Session;
+use watch::SourceWatcher;
+
 use crate::{
-    Result, client,
+    Error, Result,
+    client::ConnectOptions,
     command::{ReplCommandWrapper, execute_mcp_command_with_client, generate_repl_help},
     ctx::Ctx,
     output::initresult,
+    snapshot,
     target::Target,
+    transcript::Transcript,
 };
 
 #[derive(Clone)]
@@ -28,10 +42,42 @@ impl ClientConn for NotificationClientConn {
     }
 }
 
-pub async fn connect_command(ctx: &Ctx, target: String) -> Result<()> {
-    let target = Target::parse(&target)?;
+pub struct ConnectArgs {
+    pub target: String,
+    pub transcript: Option<PathBuf>,
+    pub script: Option<PathBuf>,
+    pub continue_on_error: bool,
+    /// Restart the server whenever this file or directory changes
+    pub watch: Option<PathBuf>,
+}
+
+pub async fn connect_command(ctx: &Ctx, args: ConnectArgs) -> Result<()> {
+    let target = Target::parse(&args.target)?;
+    let transcript = args
+        .transcript
+        .as_deref()
+        .map(Transcript::create)
+        .transpose()?;
+    // Everything sent and received is recorded as it crosses the connection
+    let ctx = &ctx.clone().with_connect_options(ConnectOptions {
+        transcript: transcript.clone(),
+        ..ctx.connect_options.clone()
+    });
+
+    let mut watcher = match &args.watch {
+        Some(path) => {
+            if !matches!(target, Target::Stdio { .. }) {
+                return Err(Error::Other(
+                    "--watch can only be used with cmd:// targets".to_string(),
+                ));
+            }
+            Some(SourceWatcher::new(path)?)
+        }
+        None => None,
+    };
 
-    ctx.output.text(format!("Connecting to {target}..."))?;
+    ctx.output
+        .text(format!("Connecting to {}...", target.redacted()))?;
 
     // Create notification channel
     let (notification_sender, mut notification_receiver) = mpsc::unbounded_channel();
@@ -40,98 +86,101 @@ pub async fn connect_command(ctx: &Ctx, target: String) -> Result<()> {
     let conn = NotificationClientConn {
         notification_sender,
     };
-    let (mut client, init_result) = client::get_client_with_connection(ctx, &target, conn).await?;
+    let mut session = Session::connect(ctx, target.clone(), conn).await?;
 
     ctx.output.trace_success(format!(
         "Connected to: {} v{}",
-        init_result.server_info.name, init_result.server_info.version
+        session.init_result.server_info.name, session.init_result.server_info.version
     ))?;
+
+    if let Some(script) = &args.script {
+        return script::run_script(
+            ctx,
+            script,
+            args.continue_on_error,
+            &mut session,
+            &mut notification_receiver,
+            transcript.as_ref(),
+        )
+        .await;
+    }
+
     ctx.output
         .text("Type 'help' for available commands, 'quit' to exit\n")?;
 
-    let mut rl = DefaultEditor::new()?;
+    let completer = McpCompleter::new();
+    completer
+        .refresh_all(&mut session.client, &session.init_result)
+        .await;
+
+    let history_path = ctx.history_path(&target);
+    if let Some(parent) = history_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut rl = Editor::<McpCompleter, DefaultHistory>::new()?;
+    rl.set_helper(Some(completer.clone()));
+    // A missing history file just means this is the first session for the target
+    let _ = rl.load_history(&history_path);
+    let (mut lines, ready) = spawn_line_reader(rl, history_path);
+    let _ = ready.send(());
+
+    if let Some(watcher) = &watcher {
+        ctx.output
+            .note(format!("Watching {} for changes", watcher.path().display()))?;
+    }
+    let mut poll = tokio::time::interval(watch::POLL_INTERVAL);
 
     loop {
         tokio::select! {
+            // Restart the server when the watched path changes
+            _ = poll.tick(), if watcher.is_some() => {
+                if let Some(watcher) = watcher.as_mut()
+                    && watcher.poll()
+                {
+                    restart(ctx, &mut session, &completer, watcher.path()).await?;
+                }
+            }
             // Handle incoming notifications
             notification = notification_receiver.recv() => {
                 if let Some(notification) = notification {
                     display_notification(&ctx.output, &notification)?;
+                    completer.handle_notification(&mut session.client, &notification).await;
                 }
             }
-            // Handle user input (in a non-blocking way)
-            readline_result = tokio::task::spawn_blocking(|| {
-                let mut rl = DefaultEditor::new().expect("Failed to create readline editor");
-                rl.readline("mcp> ")
-            }) => {
-                match readline_result {
-                    Ok(readline) => match readline {
-                        Ok(line) => {
-                            let line = line.trim();
-                            if line.is_empty() {
-                                continue;
-                            }
-
-                            rl.add_history_entry(line)?;
-
-                            match line {
-                                "quit" | "exit" => {
-                                    ctx.output.text("Goodbye!")?;
-                                    break;
-                                }
-                                "help" => {
-                                    ctx.output.h1("Available commands")?;
-                                    ctx.output.text(generate_repl_help())?;
-                                }
-                                "init" => {
-                                    ctx.output.note("Showing initialization result from initial connection (not re-initializing)")?;
-                                    initresult::init_result(&ctx.output, &init_result)?;
-                                }
-                                _ => {
-                                    // Try to parse as an MCP command using clap
-                                    let parts: Vec<&str> = line.split_whitespace().collect();
-                                    match ReplCommandWrapper::try_parse_from(parts) {
-                                        Ok(wrapper) => {
-                                            match execute_mcp_command_with_client(
-                                                wrapper.command,
-                                                &mut client,
-                                                &init_result,
-                                                ctx,
-                                            )
-                                            .await
-                                            {
-                                                Ok(_) => {}
-                                                Err(e) => {
-                                                    ctx.output.trace_error(format!("Command failed: {e}"))?
-                                                }
-                                            }
-                                        }
-                                        Err(e) => {
-                                            ctx.output.trace_error(format!("Invalid command: {e}"))?;
-                                            ctx.output.text("Type 'help' for available commands.")?;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        Err(rustyline::error::ReadlineError::Interrupted) => {
-                            ctx.output.text("CTRL-C")?;
-                            break;
-                        }
-                        Err(rustyline::error::ReadlineError::Eof) => {
-                            ctx.output.text("CTRL-D")?;
-                            break;
+            // Handle user input from the reader thread
+            readline = lines.recv() => {
+                let Some(readline) = readline else {
+                    break;
+                };
+                match readline {
+                    Ok(line) => {
+                        let line = line.trim();
+                        if let Some(transcript) = &transcript
+                            && !line.is_empty()
+                        {
+                            transcript.command(line)?;
                         }
-                        Err(err) => {
-                            ctx.output.trace_error(format!("Error: {err:?}"))?;
+                        if !execute_line(ctx, line, &mut session, &completer, transcript.as_ref())
+                            .await?
+                        {
                             break;
                         }
                     }
-                    Err(e) => {
-                        ctx.output.trace_error(format!("Failed to read input: {e:?}"))?;
+                    Err(rustyline::error::ReadlineError::Interrupted) => {
+                        ctx.output.text("CTRL-C")?;
+                        break;
+                    }
+                    Err(rustyline::error::ReadlineError::Eof) => {
+                        ctx.output.text("CTRL-D")?;
+                        break;
+                    }
+                    Err(err) => {
+                        ctx.output.trace_error(format!("Error: {err:?}"))?;
                         break;
                     }
                 }
+                let _ = ready.send(());
             }
         }
     }
@@ -139,6 +188,169 @@ pub async fn connect_command(ctx: &Ctx, target: String) -> Result<()> {
     Ok(())
 }
 
+/// Run the editor on a dedicated thread so that it (and its history) persists
+/// across prompts while the async side keeps handling notifications.
+///
+/// The thread prompts once for every message received on the returned sender,
+/// which keeps the prompt from being redrawn while a command is still running.
+///
+/// Each entry is appended to the history file as soon as it is entered, so
+/// history survives the session ending abruptly.
+fn spawn_line_reader(
+    mut rl: Editor<McpCompleter, DefaultHistory>,
+    history_path: PathBuf,
+) -> (
+    mpsc::UnboundedReceiver<rustyline::Result<String>>,
+    std::sync::mpsc::Sender<()>,
+) {
+    let (line_sender, line_receiver) = mpsc::unbounded_channel();
+    let (ready_sender, ready_receiver) = std::sync::mpsc::channel::<()>();
+
+    std::thread::spawn(move || {
+        while ready_receiver.recv().is_ok() {
+            let readline = rl.readline("mcp> ");
+            if let Ok(line) = &readline {
+                let line = line.trim();
+                if !line.is_empty() && rl.add_history_entry(line).unwrap_or(false) {
+                    let _ = rl.append_history(&history_path);
+                }
+            }
+            if line_sender.send(readline).is_err() {
+                break;
+            }
+        }
+    });
+
+    (line_receiver, ready_sender)
+}
+
+/// Execute a single REPL line. Returns false if the REPL should exit.
+async fn execute_line(
+    ctx: &Ctx,
+    line: &str,
+    session: &mut Session,
+    completer: &McpCompleter,
+    transcript: Option<&Transcript>,
+) -> Result<bool> {
+    match line {
+        "" => {}
+        "quit" | "exit" => {
+            ctx.output.text("Goodbye!")?;
+            return Ok(false);
+        }
+        "help" => {
+            ctx.output.h1("Available commands")?;
+            ctx.output.text(generate_repl_help())?;
+        }
+        "init" => {
+            ctx.output.note(
+                "Showing initialization result from the current connection (not re-initializing)",
+            )?;
+            initresult::init_result(&ctx.output, &session.init_result)?;
+        }
+        "reconnect" => reconnect(ctx, session, completer).await?,
+        _ => {
+            // Try to parse as an MCP command using clap
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            match ReplCommandWrapper::try_parse_from(parts) {
+                Ok(wrapper) => {
+                    match execute_mcp_command_with_client(
+                        wrapper.command.clone(),
+                        &mut session.client,
+                        &session.init_result,
+                        ctx,
+                    )
+                    .await
+                    {
+                        Ok(_) => session.record(&wrapper.command),
+                        Err(e) => {
+                            if let Some(transcript) = transcript {
+                                transcript.error(&e.to_string())?;
+                            }
+                            ctx.output.trace_error(format!("Command failed: {e}"))?;
+                            // A failed command may mean the transport is gone
+                            if !session.is_alive().await {
+                                ctx.output.trace_warn(format!(
+                                    "Lost connection to {}; the command was not retried",
+                                    session.target.redacted()
+                                ))?;
+                                reconnect(ctx, session, completer).await?;
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    ctx.output.trace_error(format!("Invalid command: {e}"))?;
+                    ctx.output.text("Type 'help' for available commands.")?;
+                }
+            }
+        }
+    }
+    Ok(true)
+}
+
+/// Restart the server after a change to its source, showing how its
+/// interface changed
+async fn restart(
+    ctx: &Ctx,
+    session: &mut Session,
+    completer: &McpCompleter,
+    path: &Path,
+) -> Result<()> {
+    ctx.output.h1(format!(
+        "Change detected in {}, restarting server",
+        path.display()
+    ))?;
+    // The old server may already be gone, in which case there's nothing to compare
+    let before = session.interface(ctx).await.ok();
+    if let Err(e) = session.reconnect(ctx).await {
+        ctx.output.trace_error(format!(
+            "Could not restart {}: {e}",
+            session.target.redacted()
+        ))?;
+        ctx.output
+            .text("Waiting for the next change. Type 'reconnect' to try again.")?;
+        return Ok(());
+    }
+    completer
+        .refresh_all(&mut session.client, &session.init_result)
+        .await;
+
+    let Some(before) = before else {
+        return Ok(());
+    };
+    let after = session.interface(ctx).await?;
+    let differences = snapshot::diff_values(&before, &after);
+    if differences.is_empty() {
+        ctx.output
+            .note("No changes to tools, prompts or resources")?;
+    } else {
+        ctx.output.h2("Interface changes")?;
+        snapshot::display_differences(&ctx.output, &differences)?;
+    }
+    Ok(())
+}
+
+/// Re-establish the session, reporting rather than propagating failure so the
+/// REPL stays usable
+async fn reconnect(ctx: &Ctx, session: &mut Session, completer: &McpCompleter) -> Result<()> {
+    match session.reconnect(ctx).await {
+        Ok(()) => {
+            completer
+                .refresh_all(&mut session.client, &session.init_result)
+                .await;
+        }
+        Err(e) => {
+            ctx.output.trace_error(format!(
+                "Could not reconnect to {}: {e}",
+                session.target.redacted()
+            ))?;
+            ctx.output.text("Type 'reconnect' to try again.")?;
+        }
+    }
+    Ok(())
+}
+
 fn display_notification(
     output: &crate::output::Output,
     notification: &ServerNotification,
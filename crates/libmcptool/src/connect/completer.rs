@@ -0,0 +1,406 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use clap::CommandFactory;
+use rustyline::{
+    Context, Helper,
+    completion::{Completer, Pair},
+    highlight::Highlighter,
+    hint::Hinter,
+    validate::Validator,
+};
+use tenx_mcp::{
+    Client, ClientConn,
+    schema::{InitializeResult, ServerNotification},
+};
+
+use crate::{command::ReplCommandWrapper, mcp};
+
+/// Commands handled by the REPL itself rather than by clap
+const REPL_COMMANDS: &[&str] = &["help", "quit", "exit", "init", "reconnect"];
+
+/// Server-provided names used to drive completion
+#[derive(Debug, Default, Clone)]
+struct CompletionData {
+    /// Tool names mapped to the property names of their input schema
+    tools: BTreeMap<String, Vec<String>>,
+    /// Prompt names mapped to their argument names
+    prompts: BTreeMap<String, Vec<String>>,
+    /// Resource URIs
+    resources: Vec<String>,
+    /// Resource template URIs mapped to the variables in them
+    resource_templates: BTreeMap<String, Vec<String>>,
+}
+
+/// Context-aware tab completion for the `connect` REPL.
+///
+/// Command names come from `McpCommand`; tool names, prompt names, resource
+/// URIs and argument keys are fetched from the connected server and refreshed
+/// whenever the server announces a list change.
+#[derive(Clone)]
+pub struct McpCompleter {
+    commands: Vec<String>,
+    data: Arc<Mutex<CompletionData>>,
+}
+
+impl Default for McpCompleter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl McpCompleter {
+    pub fn new() -> Self {
+        let mut commands: Vec<String> = ReplCommandWrapper::command()
+            .get_subcommands()
+            .map(|cmd| cmd.get_name().to_string())
+            .chain(REPL_COMMANDS.iter().map(|s| s.to_string()))
+            .collect();
+        commands.sort();
+        commands.dedup();
+
+        Self {
+            commands,
+            data: Arc::new(Mutex::new(CompletionData::default())),
+        }
+    }
+
+    /// Fetch everything the server advertises support for
+    pub async fn refresh_all<C: ClientConn + 'static>(
+        &self,
+        client: &mut Client<C>,
+        init_result: &InitializeResult,
+    ) {
+        if init_result.capabilities.tools.is_some() {
+            self.refresh_tools(client).await;
+        }
+        if init_result.capabilities.prompts.is_some() {
+            self.refresh_prompts(client).await;
+        }
+        if init_result.capabilities.resources.is_some() {
+            self.refresh_resources(client).await;
+        }
+    }
+
+    /// Refresh the relevant list if the notification announces a change
+    pub async fn handle_notification<C: ClientConn + 'static>(
+        &self,
+        client: &mut Client<C>,
+        notification: &ServerNotification,
+    ) {
+        match notification {
+            ServerNotification::ToolListChanged => self.refresh_tools(client).await,
+            ServerNotification::PromptListChanged => self.refresh_prompts(client).await,
+            ServerNotification::ResourceListChanged => self.refresh_resources(client).await,
+            _ => {}
+        }
+    }
+
+    async fn refresh_tools<C: ClientConn + 'static>(&self, client: &mut Client<C>) {
+        // Completion is best-effort, so failures leave the previous data in place
+        if let Ok(tools) = mcp::fetch_tools(client).await {
+            let tools = tools
+                .into_iter()
+                .map(|tool| {
+                    let mut args: Vec<String> = tool
+                        .input_schema
+                        .properties
+                        .map(|properties| properties.into_keys().collect())
+                        .unwrap_or_default();
+                    args.sort();
+                    (tool.name, args)
+                })
+                .collect();
+            self.data.lock().unwrap().tools = tools;
+        }
+    }
+
+    async fn refresh_prompts<C: ClientConn + 'static>(&self, client: &mut Client<C>) {
+        if let Ok(prompts) = mcp::fetch_prompts(client).await {
+            let prompts = prompts
+                .into_iter()
+                .map(|prompt| {
+                    let args = prompt
+                        .arguments
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|arg| arg.name)
+                        .collect();
+                    (prompt.name, args)
+                })
+                .collect();
+            self.data.lock().unwrap().prompts = prompts;
+        }
+    }
+
+    async fn refresh_resources<C: ClientConn + 'static>(&self, client: &mut Client<C>) {
+        if let Ok(resources) = mcp::fetch_resources(client).await {
+            let mut resources: Vec<String> =
+                resources.into_iter().map(|resource| resource.uri).collect();
+            resources.sort();
+            self.data.lock().unwrap().resources = resources;
+        }
+        if let Ok(templates) = mcp::fetch_resource_templates(client).await {
+            let templates = templates
+                .into_iter()
+                .map(|template| {
+                    let variables = template_variables(&template.uri_template);
+                    (template.uri_template, variables)
+                })
+                .collect();
+            self.data.lock().unwrap().resource_templates = templates;
+        }
+    }
+
+    /// Compute the start offset of the word being completed and the candidates for it
+    fn candidates(&self, line: &str) -> (usize, Vec<String>) {
+        let start = line
+            .rfind(char::is_whitespace)
+            .map(|idx| idx + 1)
+            .unwrap_or(0);
+        let word = &line[start..];
+        let previous: Vec<&str> = line[..start].split_whitespace().collect();
+
+        let data = self.data.lock().unwrap();
+        let options: Vec<String> = match previous.as_slice() {
+            [] => self.commands.clone(),
+            ["calltool"] => data.tools.keys().cloned().collect(),
+            ["calltool", tool, .., "--arg" | "-a"] => data
+                .tools
+                .get(*tool)
+                .map(|args| args.iter().map(|a| format!("{a}=")).collect())
+                .unwrap_or_default(),
+            ["calltool", _, ..] => vec![
+                "--arg".to_string(),
+                "--interactive".to_string(),
+                "--json".to_string(),
+            ],
+            ["getprompt"] => data.prompts.keys().cloned().collect(),
+            ["getprompt", prompt, .., "--arg" | "-a"] => data
+                .prompts
+                .get(*prompt)
+                .map(|args| args.iter().map(|a| format!("{a}=")).collect())
+                .unwrap_or_default(),
+            ["getprompt", _, ..] => vec!["--arg".to_string()],
+            ["readresource" | "subscriberesource" | "unsubscriberesource"] => {
+                data.resources.clone()
+            }
+            ["complete"] => data
+                .prompts
+                .keys()
+                .map(|name| format!("prompt://{name}"))
+                .chain(
+                    data.resources
+                        .iter()
+                        .chain(data.resource_templates.keys())
+                        .map(|uri| resource_reference(uri)),
+                )
+                .collect(),
+            ["complete", reference] => {
+                if let Some(name) = reference.strip_prefix("prompt://") {
+                    data.prompts.get(name).cloned().unwrap_or_default()
+                } else {
+                    data.resource_templates
+                        .iter()
+                        .find(|(uri, _)| resource_reference(uri) == *reference)
+                        .map(|(_, variables)| variables.clone())
+                        .unwrap_or_default()
+                }
+            }
+            _ => vec![],
+        };
+
+        let mut matches: Vec<String> = options
+            .into_iter()
+            .filter(|option| option.starts_with(word))
+            .collect();
+        matches.sort();
+        matches.dedup();
+        (start, matches)
+    }
+}
+
+/// How `complete` refers to a resource or resource template
+fn resource_reference(uri: &str) -> String {
+    if uri.starts_with("resource://") {
+        uri.to_string()
+    } else {
+        format!("resource://{uri}")
+    }
+}
+
+/// The names of the `{variable}` expressions in a URI template
+fn template_variables(template: &str) -> Vec<String> {
+    let mut variables = vec![];
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        // Operators such as `{+path}` and `{?query}` prefix the names, and
+        // several names can share an expression
+        let expression =
+            rest[start + 1..start + len].trim_start_matches(['+', '#', '.', '/', ';', '?', '&']);
+        for name in expression.split(',') {
+            let name = name.trim_end_matches('*');
+            let name = name.split(':').next().unwrap_or_default();
+            if !name.is_empty() && !variables.iter().any(|v| v == name) {
+                variables.push(name.to_string());
+            }
+        }
+        rest = &rest[start + len + 1..];
+    }
+    variables
+}
+
+impl Completer for McpCompleter {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let (start, candidates) = self.candidates(&line[..pos]);
+        let pairs = candidates
+            .into_iter()
+            .map(|candidate| Pair {
+                display: candidate.clone(),
+                replacement: candidate,
+            })
+            .collect();
+        Ok((start, pairs))
+    }
+}
+
+impl Hinter for McpCompleter {
+    type Hint = String;
+}
+
+impl Highlighter for McpCompleter {}
+
+impl Validator for McpCompleter {}
+
+impl Helper for McpCompleter {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_completer() -> McpCompleter {
+        let completer = McpCompleter::new();
+        {
+            let mut data = completer.data.lock().unwrap();
+            data.tools.insert(
+                "echo".to_string(),
+                vec!["message".to_string(), "repeat".to_string()],
+            );
+            data.tools.insert("add".to_string(), vec![]);
+            data.prompts
+                .insert("greeting".to_string(), vec!["name".to_string()]);
+            data.resources = vec![
+                "file:///config.json".to_string(),
+                "file:///users.json".to_string(),
+            ];
+            data.resource_templates.insert(
+                "file:///logs/{date}/{level}".to_string(),
+                vec!["date".to_string(), "level".to_string()],
+            );
+        }
+        completer
+    }
+
+    #[test]
+    fn test_complete_commands() {
+        let completer = test_completer();
+
+        let (start, candidates) = completer.candidates("list");
+        assert_eq!(start, 0);
+        assert!(candidates.contains(&"listtools".to_string()));
+        assert!(candidates.contains(&"listprompts".to_string()));
+        assert!(!candidates.contains(&"ping".to_string()));
+
+        let (_, candidates) = completer.candidates("qu");
+        assert_eq!(candidates, vec!["quit".to_string()]);
+    }
+
+    #[test]
+    fn test_complete_tools_and_arguments() {
+        let completer = test_completer();
+
+        let (start, candidates) = completer.candidates("calltool e");
+        assert_eq!(start, 9);
+        assert_eq!(candidates, vec!["echo".to_string()]);
+
+        let (_, candidates) = completer.candidates("calltool echo --");
+        assert_eq!(
+            candidates,
+            vec![
+                "--arg".to_string(),
+                "--interactive".to_string(),
+                "--json".to_string()
+            ]
+        );
+
+        let (start, candidates) = completer.candidates("calltool echo --arg m");
+        assert_eq!(start, 20);
+        assert_eq!(candidates, vec!["message=".to_string()]);
+
+        let (_, candidates) = completer.candidates("calltool echo -a message=hi -a ");
+        assert_eq!(
+            candidates,
+            vec!["message=".to_string(), "repeat=".to_string()]
+        );
+
+        let (_, candidates) = completer.candidates("calltool missing --arg ");
+        assert!(candidates.is_empty());
+    }
+
+    #[test]
+    fn test_complete_prompts_and_resources() {
+        let completer = test_completer();
+
+        let (_, candidates) = completer.candidates("getprompt ");
+        assert_eq!(candidates, vec!["greeting".to_string()]);
+
+        let (_, candidates) = completer.candidates("getprompt greeting --arg ");
+        assert_eq!(candidates, vec!["name=".to_string()]);
+
+        let (_, candidates) = completer.candidates("readresource file:///u");
+        assert_eq!(candidates, vec!["file:///users.json".to_string()]);
+
+        let (_, candidates) = completer.candidates("complete p");
+        assert_eq!(candidates, vec!["prompt://greeting".to_string()]);
+
+        let (_, candidates) = completer.candidates("complete prompt://greeting ");
+        assert_eq!(candidates, vec!["name".to_string()]);
+
+        let (_, candidates) = completer.candidates("complete resource://file:///");
+        assert_eq!(
+            candidates,
+            vec![
+                "resource://file:///config.json".to_string(),
+                "resource://file:///logs/{date}/{level}".to_string(),
+                "resource://file:///users.json".to_string(),
+            ]
+        );
+
+        let (_, candidates) =
+            completer.candidates("complete resource://file:///logs/{date}/{level} ");
+        assert_eq!(candidates, vec!["date".to_string(), "level".to_string()]);
+    }
+
+    #[test]
+    fn test_template_variables() {
+        assert_eq!(
+            template_variables("file:///{+path}{?limit,offset}"),
+            vec!["path", "limit", "offset"]
+        );
+        assert_eq!(
+            template_variables("db://{table}/{id:3}/{table}"),
+            vec!["table", "id"]
+        );
+        assert!(template_variables("file:///static").is_empty());
+    }
+}
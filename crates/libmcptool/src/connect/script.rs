@@ -0,0 +1,485 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use clap::Parser;
+use serde_json::Value;
+use tenx_mcp::schema::ServerNotification;
+use tokio::sync::mpsc;
+
+use super::{display_notification, session::Session};
+use crate::{
+    Error, Result,
+    command::{ReplCommandWrapper, execute_mcp_command_with_client, generate_repl_help},
+    ctx::Ctx,
+    transcript::Transcript,
+};
+
+/// Comparison operators supported by `expect`/`assert` lines
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Contains,
+}
+
+impl Op {
+    fn parse(input: &str) -> Result<Self> {
+        match input {
+            "==" => Ok(Op::Eq),
+            "!=" => Ok(Op::Ne),
+            "contains" => Ok(Op::Contains),
+            _ => Err(Error::Format(format!(
+                "Invalid operator: '{input}'. Expected ==, != or contains"
+            ))),
+        }
+    }
+
+    /// Compare two values
+    pub fn compare(&self, lhs: &Value, rhs: &Value) -> bool {
+        match self {
+            Op::Eq => lhs == rhs,
+            Op::Ne => lhs != rhs,
+            Op::Contains => match (lhs, rhs) {
+                (Value::String(haystack), Value::String(needle)) => haystack.contains(needle),
+                (Value::Array(items), needle) => items.contains(needle),
+                (Value::Object(map), Value::String(key)) => map.contains_key(key),
+                _ => false,
+            },
+        }
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Op::Eq => write!(f, "=="),
+            Op::Ne => write!(f, "!="),
+            Op::Contains => write!(f, "contains"),
+        }
+    }
+}
+
+/// A single parsed script line
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptLine {
+    /// An MCP command, split into words, exactly as it would be typed at the prompt
+    Command(Vec<String>),
+    /// `let <name> = <$reference>` captures a value for later lines
+    Let { name: String, expr: String },
+    /// `expect <$reference> <op> <value>` (or `assert ...`) compares a value
+    Expect { lhs: String, op: Op, rhs: String },
+    /// `help` lists the available commands
+    Help,
+    /// `reconnect` re-establishes the session
+    Reconnect,
+    /// `quit` or `exit` stops the script early
+    Quit,
+}
+
+/// Parse a script line. Blank lines and `#` comments yield `None`.
+pub fn parse_line(line: &str) -> Result<Option<ScriptLine>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    let words = shell_words::split(line)
+        .map_err(|e| Error::Format(format!("Failed to parse line: {e}")))?;
+
+    let parsed = match words.first().map(String::as_str) {
+        Some("expect" | "assert") => {
+            if words.len() != 4 {
+                return Err(Error::Format(format!(
+                    "Expected '{} <$variable> <==|!=|contains> <value>'",
+                    words[0]
+                )));
+            }
+            ScriptLine::Expect {
+                lhs: words[1].clone(),
+                op: Op::parse(&words[2])?,
+                rhs: words[3].clone(),
+            }
+        }
+        Some("let") => {
+            if words.len() != 4 || words[2] != "=" {
+                return Err(Error::Format(
+                    "Expected 'let <name> = <$variable>'".to_string(),
+                ));
+            }
+            if ident_len(&words[1]) != words[1].len() || words[1].is_empty() {
+                return Err(Error::Format(format!(
+                    "Invalid variable name: '{}'",
+                    words[1]
+                )));
+            }
+            ScriptLine::Let {
+                name: words[1].clone(),
+                expr: words[3].clone(),
+            }
+        }
+        Some("help") if words.len() == 1 => ScriptLine::Help,
+        Some("reconnect") if words.len() == 1 => ScriptLine::Reconnect,
+        Some("quit" | "exit") => ScriptLine::Quit,
+        _ => ScriptLine::Command(words),
+    };
+    Ok(Some(parsed))
+}
+
+/// One step in a variable path
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Length of the identifier at the start of `input`
+fn ident_len(input: &str) -> usize {
+    input
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(input.len())
+}
+
+/// Parse a reference such as `last.content[0].text` (without the leading `$`).
+/// Returns the variable name, the path into it and the number of bytes consumed.
+fn parse_reference(input: &str) -> Option<(&str, Vec<Segment>, usize)> {
+    let name_len = ident_len(input);
+    if name_len == 0 {
+        return None;
+    }
+
+    let mut pos = name_len;
+    let mut segments = Vec::new();
+    loop {
+        let rest = &input[pos..];
+        if let Some(after) = rest.strip_prefix('.') {
+            let len = ident_len(after);
+            if len == 0 {
+                break;
+            }
+            segments.push(Segment::Key(after[..len].to_string()));
+            pos += 1 + len;
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let Some(end) = after.find(']') else {
+                break;
+            };
+            let Ok(index) = after[..end].parse::<usize>() else {
+                break;
+            };
+            segments.push(Segment::Index(index));
+            pos += end + 2;
+        } else {
+            break;
+        }
+    }
+
+    Some((&input[..name_len], segments, pos))
+}
+
+static NULL: Value = Value::Null;
+
+/// Variables available to a script. `$last` always holds the result of the
+/// most recent command.
+#[derive(Debug, Default)]
+pub struct Variables {
+    values: HashMap<String, Value>,
+}
+
+impl Variables {
+    pub fn set(&mut self, name: &str, value: Value) {
+        self.values.insert(name.to_string(), value);
+    }
+
+    pub fn set_last(&mut self, value: Value) {
+        self.set("last", value);
+    }
+
+    /// Evaluate a `$name.path[0]` expression. Paths that don't exist evaluate to null.
+    pub fn eval(&self, expr: &str) -> Result<Value> {
+        let reference = expr
+            .strip_prefix('$')
+            .ok_or_else(|| Error::Format(format!("Expected a $variable, got '{expr}'")))?;
+        match parse_reference(reference) {
+            Some((name, segments, consumed)) if consumed == reference.len() => {
+                self.lookup(name, &segments).cloned()
+            }
+            _ => Err(Error::Format(format!(
+                "Invalid variable reference: '{expr}'"
+            ))),
+        }
+    }
+
+    /// Evaluate a comparison operand: a `$reference`, a JSON literal, or a bare string
+    pub fn operand(&self, token: &str) -> Result<Value> {
+        if token.starts_with('$') {
+            self.eval(token)
+        } else {
+            Ok(serde_json::from_str(token).unwrap_or_else(|_| Value::String(token.to_string())))
+        }
+    }
+
+    /// Replace every `$reference` in a command word with its value. Strings are
+    /// inserted verbatim, other values as JSON.
+    pub fn substitute(&self, word: &str) -> Result<String> {
+        let mut result = String::new();
+        let mut rest = word;
+        while let Some(idx) = rest.find('$') {
+            result.push_str(&rest[..idx]);
+            let after = &rest[idx + 1..];
+            match parse_reference(after) {
+                Some((name, segments, consumed)) => {
+                    match self.lookup(name, &segments)? {
+                        Value::String(s) => result.push_str(s),
+                        value => result.push_str(&value.to_string()),
+                    }
+                    rest = &after[consumed..];
+                }
+                None => {
+                    result.push('$');
+                    rest = after;
+                }
+            }
+        }
+        result.push_str(rest);
+        Ok(result)
+    }
+
+    fn lookup(&self, name: &str, segments: &[Segment]) -> Result<&Value> {
+        let mut value = self
+            .values
+            .get(name)
+            .ok_or_else(|| Error::Other(format!("Unknown variable: ${name}")))?;
+        for segment in segments {
+            let next = match segment {
+                Segment::Key(key) => value.get(key.as_str()),
+                Segment::Index(index) => value.get(*index),
+            };
+            value = next.unwrap_or(&NULL);
+        }
+        Ok(value)
+    }
+}
+
+/// Run a script of REPL commands over an existing connection.
+///
+/// Execution stops at the first failing command or assertion unless
+/// `continue_on_error` is set. If anything failed, an error is returned so
+/// that mcptool exits with a non-zero status.
+pub async fn run_script(
+    ctx: &Ctx,
+    path: &Path,
+    continue_on_error: bool,
+    session: &mut Session,
+    notifications: &mut mpsc::UnboundedReceiver<ServerNotification>,
+    transcript: Option<&Transcript>,
+) -> Result<()> {
+    let contents = std::fs::read_to_string(path)?;
+    let mut variables = Variables::default();
+    let mut failures = 0;
+    let mut assertions = 0;
+
+    for (idx, line) in contents.lines().enumerate() {
+        let lineno = idx + 1;
+        let outcome = match parse_line(line) {
+            Ok(None) => continue,
+            Ok(Some(ScriptLine::Quit)) => break,
+            Ok(Some(parsed)) => {
+                ctx.output.h2(format!("{lineno}: {}", line.trim()))?;
+                if let Some(transcript) = transcript {
+                    transcript.command(line.trim())?;
+                }
+                run_line(
+                    ctx,
+                    parsed,
+                    &mut variables,
+                    &mut assertions,
+                    session,
+                    transcript,
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        };
+
+        while let Ok(notification) = notifications.try_recv() {
+            display_notification(&ctx.output, &notification)?;
+        }
+
+        if let Err(e) = outcome {
+            failures += 1;
+            ctx.output.trace_error(format!("line {lineno}: {e}"))?;
+            if !continue_on_error {
+                break;
+            }
+        }
+    }
+
+    ctx.output.text("")?;
+    if failures > 0 {
+        return Err(Error::Other(format!(
+            "Script failed with {failures} error(s)"
+        )));
+    }
+    ctx.output.trace_success(format!(
+        "Script completed, {assertions} assertion(s) passed"
+    ))?;
+    Ok(())
+}
+
+async fn run_line(
+    ctx: &Ctx,
+    line: ScriptLine,
+    variables: &mut Variables,
+    assertions: &mut usize,
+    session: &mut Session,
+    transcript: Option<&Transcript>,
+) -> Result<()> {
+    match line {
+        ScriptLine::Command(words) => {
+            let words = words
+                .iter()
+                .map(|word| variables.substitute(word))
+                .collect::<Result<Vec<_>>>()?;
+            let wrapper = ReplCommandWrapper::try_parse_from(words)
+                .map_err(|e| Error::Format(format!("Invalid command: {e}")))?;
+            match execute_mcp_command_with_client(
+                wrapper.command.clone(),
+                &mut session.client,
+                &session.init_result,
+                ctx,
+            )
+            .await
+            {
+                Ok(result) => {
+                    session.record(&wrapper.command);
+                    variables.set_last(result);
+                }
+                Err(e) => {
+                    if let Some(transcript) = transcript {
+                        transcript.error(&e.to_string())?;
+                    }
+                    // Don't let later assertions pass against a stale result
+                    variables.set_last(Value::Null);
+                    return Err(e);
+                }
+            }
+        }
+        ScriptLine::Let { name, expr } => {
+            let value = variables.eval(&expr)?;
+            variables.set(&name, value);
+        }
+        ScriptLine::Expect { lhs, op, rhs } => {
+            let actual = variables.eval(&lhs)?;
+            let expected = variables.operand(&rhs)?;
+            if !op.compare(&actual, &expected) {
+                return Err(Error::Other(format!(
+                    "Assertion failed: {lhs} {op} {expected} (got {actual})"
+                )));
+            }
+            *assertions += 1;
+            ctx.output.trace_success(format!("{lhs} {op} {expected}"))?;
+        }
+        ScriptLine::Help => {
+            ctx.output.h1("Available commands")?;
+            ctx.output.text(generate_repl_help())?;
+        }
+        ScriptLine::Reconnect => session.reconnect(ctx).await?,
+        ScriptLine::Quit => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(parse_line("").unwrap(), None);
+        assert_eq!(parse_line("   # a comment").unwrap(), None);
+        assert_eq!(
+            parse_line(r#"calltool echo --arg message="hello world""#).unwrap(),
+            Some(ScriptLine::Command(vec![
+                "calltool".to_string(),
+                "echo".to_string(),
+                "--arg".to_string(),
+                "message=hello world".to_string(),
+            ]))
+        );
+        assert_eq!(
+            parse_line(r#"expect $last.content[0].text == "hello""#).unwrap(),
+            Some(ScriptLine::Expect {
+                lhs: "$last.content[0].text".to_string(),
+                op: Op::Eq,
+                rhs: "hello".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_line("let text = $last.content[0].text").unwrap(),
+            Some(ScriptLine::Let {
+                name: "text".to_string(),
+                expr: "$last.content[0].text".to_string(),
+            })
+        );
+        assert_eq!(parse_line("quit").unwrap(), Some(ScriptLine::Quit));
+        assert_eq!(parse_line("help").unwrap(), Some(ScriptLine::Help));
+        assert_eq!(
+            parse_line("reconnect").unwrap(),
+            Some(ScriptLine::Reconnect)
+        );
+
+        assert!(parse_line("expect $last ==").is_err());
+        assert!(parse_line("assert $last ~= 1").is_err());
+        assert!(parse_line("let a-b = $last").is_err());
+        assert!(parse_line("let x $last").is_err());
+    }
+
+    #[test]
+    fn test_variables() {
+        let mut variables = Variables::default();
+        variables.set_last(json!({
+            "content": [{ "type": "text", "text": "hello" }],
+            "isError": false,
+        }));
+
+        assert_eq!(
+            variables.eval("$last.content[0].text").unwrap(),
+            json!("hello")
+        );
+        assert_eq!(variables.eval("$last.isError").unwrap(), json!(false));
+        assert_eq!(variables.eval("$last.missing[3]").unwrap(), Value::Null);
+        assert!(variables.eval("$unknown").is_err());
+        assert!(variables.eval("last").is_err());
+        assert!(variables.eval("$last.content[x]").is_err());
+
+        variables.set("greeting", json!("hi there"));
+        assert_eq!(
+            variables
+                .substitute("message=$greeting, $last.content[0].text!")
+                .unwrap(),
+            "message=hi there, hello!"
+        );
+        assert_eq!(
+            variables.substitute("count=$last.isError").unwrap(),
+            "count=false"
+        );
+        assert_eq!(variables.substitute("price=5$").unwrap(), "price=5$");
+    }
+
+    #[test]
+    fn test_compare() {
+        let variables = Variables::default();
+        assert_eq!(variables.operand("42").unwrap(), json!(42));
+        assert_eq!(variables.operand("hello").unwrap(), json!("hello"));
+        assert_eq!(variables.operand("true").unwrap(), json!(true));
+
+        assert!(Op::Eq.compare(&json!("a"), &json!("a")));
+        assert!(Op::Ne.compare(&json!(1), &json!(2)));
+        assert!(Op::Contains.compare(&json!("hello world"), &json!("world")));
+        assert!(Op::Contains.compare(&json!([1, 2, 3]), &json!(2)));
+        assert!(Op::Contains.compare(&json!({ "a": 1 }), &json!("a")));
+        assert!(!Op::Contains.compare(&json!(12), &json!(1)));
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use serde_json::Value;
+use tenx_mcp::{Client, ServerAPI, schema::InitializeResult};
+
+use super::NotificationClientConn;
+use crate::{
+    Result,
+    client::{self, ConnectOptions, ServerProcesses},
+    command::McpCommand,
+    ctx::Ctx,
+    snapshot::{self, Snapshot},
+    target::Target,
+};
+
+/// Delays before each reconnection attempt
+const RECONNECT_BACKOFF: &[Duration] = &[
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_secs(2),
+    Duration::from_secs(4),
+    Duration::from_secs(8),
+];
+
+/// Parts of a normalized snapshot compared when the server restarts
+const INTERFACE_KEYS: &[&str] = &["tools", "prompts", "resources", "resource_templates"];
+
+/// How long to wait for a ping when checking whether the connection is alive
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A REPL connection to a server that can be re-established on demand.
+///
+/// Resource subscriptions made through the session are remembered so that
+/// they can be restored after reconnecting.
+pub struct Session {
+    pub target: Target,
+    pub client: Client<NotificationClientConn>,
+    pub init_result: InitializeResult,
+    conn: NotificationClientConn,
+    subscriptions: BTreeSet<String>,
+    /// The server process, for cmd:// targets, stopped before reconnecting
+    processes: ServerProcesses,
+}
+
+impl Session {
+    pub async fn connect(ctx: &Ctx, target: Target, conn: NotificationClientConn) -> Result<Self> {
+        let processes = ServerProcesses::default();
+        let (client, init_result) =
+            client::get_client_with_connection(&tracking(ctx, &processes), &target, conn.clone())
+                .await?;
+        Ok(Session {
+            target,
+            client,
+            init_result,
+            conn,
+            subscriptions: BTreeSet::new(),
+            processes,
+        })
+    }
+
+    /// Note the effect of a command that completed successfully
+    pub fn record(&mut self, command: &McpCommand) {
+        match command {
+            McpCommand::Subscriberesource { uri } => {
+                self.subscriptions.insert(uri.clone());
+            }
+            McpCommand::Unsubscriberesource { uri } => {
+                self.subscriptions.remove(uri);
+            }
+            _ => {}
+        }
+    }
+
+    /// Check whether the server still answers a ping
+    pub async fn is_alive(&mut self) -> bool {
+        matches!(
+            tokio::time::timeout(PING_TIMEOUT, self.client.ping()).await,
+            Ok(Ok(_))
+        )
+    }
+
+    /// Connect and initialize afresh, retrying with backoff, then restore
+    /// resource subscriptions. The old server process, if any, is killed
+    /// first so that it can't linger alongside the new one.
+    pub async fn reconnect(&mut self, ctx: &Ctx) -> Result<()> {
+        let quiet_ctx = tracking(
+            &Ctx {
+                output: ctx.output.clone().with_quiet(true),
+                ..ctx.clone()
+            },
+            &self.processes,
+        );
+
+        let mut last_error = None;
+        for (attempt, delay) in RECONNECT_BACKOFF.iter().enumerate() {
+            ctx.output.note(format!(
+                "Reconnecting to {} in {:.1}s (attempt {}/{})",
+                self.target.redacted(),
+                delay.as_secs_f64(),
+                attempt + 1,
+                RECONNECT_BACKOFF.len()
+            ))?;
+            tokio::time::sleep(*delay).await;
+            // Also stops any process left by a failed attempt
+            self.processes.kill_all().await;
+            match client::get_client_with_connection(&quiet_ctx, &self.target, self.conn.clone())
+                .await
+            {
+                Ok((client, init_result)) => {
+                    self.client = client;
+                    self.init_result = init_result;
+                    last_error = None;
+                    break;
+                }
+                Err(e) => {
+                    ctx.output.trace_warn(format!("Reconnect failed: {e}"))?;
+                    last_error = Some(e);
+                }
+            }
+        }
+        if let Some(e) = last_error {
+            return Err(e);
+        }
+
+        ctx.output.trace_success(format!(
+            "Reconnected to: {} v{}",
+            self.init_result.server_info.name, self.init_result.server_info.version
+        ))?;
+        self.resubscribe(ctx).await
+    }
+
+    /// The server's tools, prompts, resources and resource templates, keyed
+    /// by name or URI so that two interfaces can be compared with
+    /// [`snapshot::diff_values`]
+    pub async fn interface(&mut self, ctx: &Ctx) -> Result<Value> {
+        let quiet_ctx = Ctx {
+            output: ctx.output.clone().with_quiet(true),
+            ..ctx.clone()
+        };
+        let snapshot = Snapshot::capture(
+            &quiet_ctx,
+            &self.target,
+            &mut self.client,
+            &self.init_result,
+            &[],
+        )
+        .await?;
+        let mut interface = snapshot.normalized()?;
+        if let Value::Object(map) = &mut interface {
+            map.retain(|key, _| INTERFACE_KEYS.contains(&key.as_str()));
+        }
+        Ok(interface)
+    }
+
+    async fn resubscribe(&mut self, ctx: &Ctx) -> Result<()> {
+        let mut restored = BTreeSet::new();
+        for uri in &self.subscriptions {
+            match self.client.resources_subscribe(uri).await {
+                Ok(_) => {
+                    ctx.output
+                        .trace_success(format!("Resubscribed to resource: {uri}"))?;
+                    restored.insert(uri.clone());
+                }
+                Err(e) => ctx
+                    .output
+                    .trace_error(format!("Failed to resubscribe to {uri}: {e}"))?,
+            }
+        }
+        self.subscriptions = restored;
+        Ok(())
+    }
+}
+
+/// A context whose connections record the server processes they spawn
+fn tracking(ctx: &Ctx, processes: &ServerProcesses) -> Ctx {
+    ctx.clone().with_connect_options(ConnectOptions {
+        processes: Some(processes.clone()),
+        ..ctx.connect_options.clone()
+    })
+}
@@ -0,0 +1,155 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::{Error, Result};
+
+/// How often the watched path is checked for modifications
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Directories that hold build output or dependencies rather than sources
+const SKIPPED: &[&str] = &["target", "node_modules"];
+
+/// Polls a file or directory tree for modifications, and for files being
+/// added or removed.
+///
+/// A change is only reported once the tree has stopped changing for a full
+/// poll interval, so that a rebuild which writes many files (or rewrites the
+/// server binary in several steps) triggers a single restart.
+pub struct SourceWatcher {
+    path: PathBuf,
+    /// The files the server was started against
+    seen: Snapshot,
+    /// A changed set of files waiting to settle
+    pending: Option<Snapshot>,
+}
+
+impl SourceWatcher {
+    pub fn new(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Err(Error::Other(format!(
+                "Watch path does not exist: {}",
+                path.display()
+            )));
+        }
+        Ok(SourceWatcher {
+            path: path.to_path_buf(),
+            seen: snapshot(path),
+            pending: None,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Check for modifications, returning true once a change has settled
+    pub fn poll(&mut self) -> bool {
+        let current = snapshot(&self.path);
+        if current == self.seen {
+            self.pending = None;
+            return false;
+        }
+        if self.pending.as_ref() == Some(&current) {
+            self.seen = current;
+            self.pending = None;
+            return true;
+        }
+        self.pending = Some(current);
+        false
+    }
+}
+
+/// Modification times of a file, or of every file beneath a directory, in
+/// path order
+type Snapshot = Vec<(PathBuf, SystemTime)>;
+
+/// Take a snapshot of a tree. Hidden entries such as `.git`, and build output
+/// such as `target`, are skipped.
+fn snapshot(path: &Path) -> Snapshot {
+    fn collect(path: &Path, files: &mut Snapshot) {
+        let Ok(metadata) = std::fs::metadata(path) else {
+            return;
+        };
+        if !metadata.is_dir() {
+            if let Ok(modified) = metadata.modified() {
+                files.push((path.to_path_buf(), modified));
+            }
+            return;
+        }
+        let Ok(entries) = std::fs::read_dir(path) else {
+            return;
+        };
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if !name.starts_with('.') && !SKIPPED.contains(&name.as_ref()) {
+                collect(&entry.path(), files);
+            }
+        }
+    }
+
+    let mut files = vec![];
+    collect(path, &mut files);
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(path: &Path, secs: u64) {
+        let file = std::fs::File::options()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_change_reported_once_settled() {
+        let dir = tempfile::tempdir().unwrap();
+        touch(&dir.path().join("main.rs"), 1000);
+        touch(&dir.path().join(".hidden"), 5000);
+        let mut watcher = SourceWatcher::new(dir.path()).unwrap();
+        assert!(!watcher.poll());
+
+        touch(&dir.path().join("lib.rs"), 2000);
+        assert!(!watcher.poll(), "change must settle first");
+        touch(&dir.path().join("lib.rs"), 3000);
+        assert!(!watcher.poll(), "still changing");
+        assert!(watcher.poll());
+        assert!(!watcher.poll(), "reported only once");
+
+        // Hidden entries don't count
+        touch(&dir.path().join(".hidden"), 9000);
+        assert!(!watcher.poll());
+        assert!(!watcher.poll());
+    }
+
+    #[test]
+    fn test_removal_and_skipped_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        touch(&dir.path().join("main.rs"), 1000);
+        touch(&dir.path().join("old.rs"), 1000);
+        std::fs::create_dir(dir.path().join("target")).unwrap();
+        let mut watcher = SourceWatcher::new(dir.path()).unwrap();
+
+        // Build output doesn't count
+        touch(&dir.path().join("target").join("server"), 9000);
+        assert!(!watcher.poll());
+        assert!(!watcher.poll());
+
+        // Removing a file does, though no modification time went up
+        std::fs::remove_file(dir.path().join("old.rs")).unwrap();
+        assert!(!watcher.poll());
+        assert!(watcher.poll());
+    }
+
+    #[test]
+    fn test_missing_path() {
+        assert!(SourceWatcher::new(Path::new("/nonexistent/server")).is_err());
+    }
+}
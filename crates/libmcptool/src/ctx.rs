@@ -2,8 +2,10 @@ use std::path::PathBuf;
 
 use crate::{
     Result,
+    client::ConnectOptions,
     output::{LogLevel, Output},
     storage::TokenStorage,
+    target::Target,
 };
 
 pub const VERSION: &str = concat!(
@@ -22,6 +24,8 @@ pub struct Ctx {
     pub config_path: PathBuf,
     /// Output instance for consistent formatting
     pub output: Output,
+    /// Options applied when connecting to servers
+    pub connect_options: ConnectOptions,
 }
 
 impl Ctx {
@@ -42,9 +46,33 @@ impl Ctx {
         Ok(Self {
             config_path,
             output,
+            connect_options: ConnectOptions::default(),
         })
     }
 
+    /// Set the options used when connecting to servers
+    pub fn with_connect_options(mut self, connect_options: ConnectOptions) -> Self {
+        self.connect_options = connect_options;
+        self
+    }
+
+    /// Path to the REPL history file for a target. Each target gets its own
+    /// history, keyed by a hash of the target so that nothing it carries ends
+    /// up in a file name.
+    pub fn history_path(&self, target: &Target) -> PathBuf {
+        // FNV-1a, which unlike the standard library's hasher is stable
+        // between releases
+        let hash = target
+            .to_string()
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            });
+        self.config_path
+            .join("history")
+            .join(format!("{hash:016x}"))
+    }
+
     /// Create a TokenStorage instance using the configured path
     pub fn storage(&self) -> Result<TokenStorage> {
         Ok(TokenStorage::new(self.config_path.clone())?)
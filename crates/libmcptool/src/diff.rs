@@ -0,0 +1,747 @@
+//! Compare what two servers, or a server and a saved snapshot, advertise, and
+//! classify each difference by whether it can break existing clients.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::{
+    Error, Result, client,
+    ctx::Ctx,
+    output::listtools::schema_type,
+    snapshot::{self, Snapshot},
+    target::Target,
+};
+
+/// How a difference affects clients written against the old server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Impact {
+    /// Removes or restricts something clients may rely on
+    Breaking,
+    /// Adds something new without affecting existing use
+    Additive,
+    /// Neither, such as a new description or server version
+    Neutral,
+}
+
+/// One classified difference between the old and the new server
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Delta {
+    pub impact: Impact,
+    /// Dotted path to the difference, e.g. `tools.echo.inputSchema.message`
+    pub path: String,
+    pub message: String,
+}
+
+/// Schemas are read by the server for tool input, and by clients for tool
+/// output, so narrowing one is breaking for input and widening one is
+/// breaking for output.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Direction {
+    Input,
+    Output,
+}
+
+impl Direction {
+    fn narrowed(self) -> Impact {
+        match self {
+            Direction::Input => Impact::Breaking,
+            Direction::Output => Impact::Neutral,
+        }
+    }
+
+    fn widened(self) -> Impact {
+        match self {
+            Direction::Input => Impact::Additive,
+            Direction::Output => Impact::Breaking,
+        }
+    }
+}
+
+/// Classify every difference between two snapshots
+pub fn compare(old: &Snapshot, new: &Snapshot) -> Result<Vec<Delta>> {
+    Ok(compare_normalized(&old.normalized()?, &new.normalized()?))
+}
+
+/// Classify every difference between two normalized snapshots, breaking
+/// changes first. Recorded calls are not compared.
+fn compare_normalized(old: &Value, new: &Value) -> Vec<Delta> {
+    let mut out = vec![];
+
+    let (mut old_init, mut new_init) = (old["init"].clone(), new["init"].clone());
+    let old_capabilities = take(&mut old_init, "capabilities");
+    let new_capabilities = take(&mut new_init, "capabilities");
+    // Server info, protocol version and instructions
+    neutral("", &old_init, &new_init, &mut out);
+    compare_capabilities(&old_capabilities, &new_capabilities, &mut out);
+
+    compare_items(
+        "tools",
+        "tool",
+        &old["tools"],
+        &new["tools"],
+        compare_tool,
+        &mut out,
+    );
+    compare_items(
+        "prompts",
+        "prompt",
+        &old["prompts"],
+        &new["prompts"],
+        compare_prompt,
+        &mut out,
+    );
+    compare_items(
+        "resources",
+        "resource",
+        &old["resources"],
+        &new["resources"],
+        neutral,
+        &mut out,
+    );
+    compare_items(
+        "resource_templates",
+        "resource template",
+        &old["resource_templates"],
+        &new["resource_templates"],
+        neutral,
+        &mut out,
+    );
+
+    out.sort_by_key(|delta| delta.impact);
+    out
+}
+
+fn push(out: &mut Vec<Delta>, impact: Impact, path: &str, message: impl Into<String>) {
+    out.push(Delta {
+        impact,
+        path: path.to_string(),
+        message: message.into(),
+    });
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else if key.is_empty() || key.starts_with('[') {
+        format!("{path}{key}")
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+/// Remove a key from an object, returning its value or null
+fn take(value: &mut Value, key: &str) -> Value {
+    value
+        .as_object_mut()
+        .and_then(|object| object.remove(key))
+        .unwrap_or(Value::Null)
+}
+
+fn object(value: Value) -> Map<String, Value> {
+    match value {
+        Value::Object(object) => object,
+        _ => Map::new(),
+    }
+}
+
+/// Report every remaining difference as neutral
+fn neutral(path: &str, old: &Value, new: &Value, out: &mut Vec<Delta>) {
+    for difference in snapshot::diff_values(old, new) {
+        let message = match difference.change {
+            snapshot::Change::Added { actual } => format!("added {actual}"),
+            snapshot::Change::Removed { expected } => format!("removed {expected}"),
+            snapshot::Change::Changed { expected, actual } => format!("{expected} -> {actual}"),
+        };
+        push(out, Impact::Neutral, &join(path, &difference.path), message);
+    }
+}
+
+fn compare_capabilities(old: &Value, new: &Value, out: &mut Vec<Delta>) {
+    for difference in snapshot::diff_values(old, new) {
+        let path = join("capabilities", &difference.path);
+        match difference.change {
+            snapshot::Change::Added { .. } => {
+                push(out, Impact::Additive, &path, "capability added")
+            }
+            snapshot::Change::Removed { .. } => {
+                push(out, Impact::Breaking, &path, "capability removed")
+            }
+            snapshot::Change::Changed {
+                expected: Value::Bool(true),
+                actual: Value::Bool(false),
+            } => push(out, Impact::Breaking, &path, "capability disabled"),
+            snapshot::Change::Changed {
+                expected: Value::Bool(false),
+                actual: Value::Bool(true),
+            } => push(out, Impact::Additive, &path, "capability enabled"),
+            snapshot::Change::Changed { expected, actual } => push(
+                out,
+                Impact::Neutral,
+                &path,
+                format!("{expected} -> {actual}"),
+            ),
+        }
+    }
+}
+
+/// Compare two lists keyed by name or URI. Removing an item is breaking and
+/// adding one is additive; items in both are compared with `compare`.
+fn compare_items(
+    path: &str,
+    noun: &str,
+    old: &Value,
+    new: &Value,
+    compare: fn(&str, &Value, &Value, &mut Vec<Delta>),
+    out: &mut Vec<Delta>,
+) {
+    let (old, new) = (object(old.clone()), object(new.clone()));
+    for (key, old_item) in &old {
+        let path = join(path, key);
+        match new.get(key) {
+            Some(new_item) => compare(&path, old_item, new_item, out),
+            None => push(out, Impact::Breaking, &path, format!("{noun} removed")),
+        }
+    }
+    for key in new.keys().filter(|key| !old.contains_key(*key)) {
+        push(
+            out,
+            Impact::Additive,
+            &join(path, key),
+            format!("{noun} added"),
+        );
+    }
+}
+
+fn compare_tool(path: &str, old: &Value, new: &Value, out: &mut Vec<Delta>) {
+    let (mut old, mut new) = (old.clone(), new.clone());
+
+    let (old_input, new_input) = (take(&mut old, "inputSchema"), take(&mut new, "inputSchema"));
+    compare_schema(
+        &join(path, "inputSchema"),
+        &old_input,
+        &new_input,
+        Direction::Input,
+        out,
+    );
+
+    let output_path = join(path, "outputSchema");
+    match (
+        take(&mut old, "outputSchema"),
+        take(&mut new, "outputSchema"),
+    ) {
+        (Value::Null, Value::Null) => {}
+        (Value::Null, _) => push(out, Impact::Additive, &output_path, "output schema added"),
+        (_, Value::Null) => push(out, Impact::Breaking, &output_path, "output schema removed"),
+        (old_output, new_output) => compare_schema(
+            &output_path,
+            &old_output,
+            &new_output,
+            Direction::Output,
+            out,
+        ),
+    }
+
+    // Description, annotations and the like
+    neutral(path, &old, &new, out);
+}
+
+fn compare_prompt(path: &str, old: &Value, new: &Value, out: &mut Vec<Delta>) {
+    let (mut old, mut new) = (old.clone(), new.clone());
+    let arguments = |prompt: &mut Value| -> Map<String, Value> {
+        let arguments = take(prompt, "arguments");
+        arguments
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|argument| Some((argument["name"].as_str()?.to_string(), argument.clone())))
+            .collect()
+    };
+    let required = |argument: &Value| argument["required"].as_bool().unwrap_or(false);
+    let (old_arguments, new_arguments) = (arguments(&mut old), arguments(&mut new));
+
+    for (name, old_argument) in &old_arguments {
+        let path = join(path, &format!("arguments.{name}"));
+        let Some(new_argument) = new_arguments.get(name) else {
+            push(out, Impact::Breaking, &path, "argument removed");
+            continue;
+        };
+        match (required(old_argument), required(new_argument)) {
+            (false, true) => push(out, Impact::Breaking, &path, "argument is now required"),
+            (true, false) => push(out, Impact::Additive, &path, "argument is now optional"),
+            _ => {}
+        }
+        let (mut old_argument, mut new_argument) = (old_argument.clone(), new_argument.clone());
+        take(&mut old_argument, "required");
+        take(&mut new_argument, "required");
+        neutral(&path, &old_argument, &new_argument, out);
+    }
+    for (name, new_argument) in &new_arguments {
+        if old_arguments.contains_key(name) {
+            continue;
+        }
+        let path = join(path, &format!("arguments.{name}"));
+        if required(new_argument) {
+            push(out, Impact::Breaking, &path, "new required argument");
+        } else {
+            push(out, Impact::Additive, &path, "new optional argument");
+        }
+    }
+
+    neutral(path, &old, &new, out);
+}
+
+/// The JSON types a schema's `type` allows; empty if it allows any
+fn types(schema_type: &Value) -> BTreeSet<&str> {
+    match schema_type {
+        Value::String(t) => BTreeSet::from([t.as_str()]),
+        Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+        _ => BTreeSet::new(),
+    }
+}
+
+/// Whether a schema allowing the `wide` types accepts every value of the
+/// `narrow` ones
+fn covers(wide: &BTreeSet<&str>, narrow: &BTreeSet<&str>) -> bool {
+    wide.is_empty()
+        || (!narrow.is_empty()
+            && narrow
+                .iter()
+                .all(|t| wide.contains(t) || (*t == "integer" && wide.contains("number"))))
+}
+
+fn compare_schema(
+    path: &str,
+    old: &Value,
+    new: &Value,
+    direction: Direction,
+    out: &mut Vec<Delta>,
+) {
+    let (mut old, mut new) = (old.clone(), new.clone());
+
+    if old["type"] != new["type"] {
+        let (old_types, new_types) = (types(&old["type"]), types(&new["type"]));
+        let type_name = |schema: &Value| schema_type(schema).unwrap_or_else(|| "any".to_string());
+        let (from, to) = (type_name(&old), type_name(&new));
+        if covers(&old_types, &new_types) {
+            push(
+                out,
+                direction.narrowed(),
+                path,
+                format!("type narrowed from {from} to {to}"),
+            );
+        } else if covers(&new_types, &old_types) {
+            push(
+                out,
+                direction.widened(),
+                path,
+                format!("type widened from {from} to {to}"),
+            );
+        } else {
+            push(
+                out,
+                Impact::Breaking,
+                path,
+                format!("type changed from {from} to {to}"),
+            );
+        }
+    }
+    take(&mut old, "type");
+    take(&mut new, "type");
+
+    let (old_enum, new_enum) = (take(&mut old, "enum"), take(&mut new, "enum"));
+    match (old_enum.as_array(), new_enum.as_array()) {
+        (None, Some(_)) => push(
+            out,
+            direction.narrowed(),
+            path,
+            format!("restricted to {new_enum}"),
+        ),
+        (Some(_), None) => push(
+            out,
+            direction.widened(),
+            path,
+            "no longer restricted to a list of values",
+        ),
+        (Some(old_values), Some(new_values)) => {
+            let list = |values: Vec<&Value>| {
+                values
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            let removed: Vec<&Value> = old_values
+                .iter()
+                .filter(|v| !new_values.contains(v))
+                .collect();
+            let added: Vec<&Value> = new_values
+                .iter()
+                .filter(|v| !old_values.contains(v))
+                .collect();
+            if !removed.is_empty() {
+                push(
+                    out,
+                    direction.narrowed(),
+                    path,
+                    format!("values removed: {}", list(removed)),
+                );
+            }
+            if !added.is_empty() {
+                push(
+                    out,
+                    direction.widened(),
+                    path,
+                    format!("values added: {}", list(added)),
+                );
+            }
+        }
+        (None, None) => {}
+    }
+
+    let (old_properties, new_properties) = (
+        object(take(&mut old, "properties")),
+        object(take(&mut new, "properties")),
+    );
+    let required = |schema: &mut Value| -> BTreeSet<String> {
+        take(schema, "required")
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|name| name.as_str().map(String::from))
+            .collect()
+    };
+    let (old_required, new_required) = (required(&mut old), required(&mut new));
+    let noun = match direction {
+        Direction::Input => "parameter",
+        Direction::Output => "field",
+    };
+
+    for (name, old_property) in &old_properties {
+        let path = join(path, name);
+        let Some(new_property) = new_properties.get(name) else {
+            push(out, Impact::Breaking, &path, format!("{noun} removed"));
+            continue;
+        };
+        compare_schema(&path, old_property, new_property, direction, out);
+        match (
+            old_required.contains(name),
+            new_required.contains(name),
+            direction,
+        ) {
+            (false, true, Direction::Input) => {
+                push(out, Impact::Breaking, &path, "parameter is now required")
+            }
+            (true, false, Direction::Input) => {
+                push(out, Impact::Additive, &path, "parameter is now optional")
+            }
+            (false, true, Direction::Output) => {
+                push(out, Impact::Additive, &path, "field is now always present")
+            }
+            (true, false, Direction::Output) => push(
+                out,
+                Impact::Breaking,
+                &path,
+                "field is no longer always present",
+            ),
+            _ => {}
+        }
+    }
+    for (name, _) in new_properties
+        .iter()
+        .filter(|(name, _)| !old_properties.contains_key(*name))
+    {
+        let path = join(path, name);
+        if direction == Direction::Input && new_required.contains(name) {
+            push(out, Impact::Breaking, &path, "new required parameter");
+        } else {
+            push(out, Impact::Additive, &path, format!("new optional {noun}"));
+        }
+    }
+
+    // Array items are compared like properties
+    if old["items"].is_object() && new["items"].is_object() {
+        let (old_items, new_items) = (take(&mut old, "items"), take(&mut new, "items"));
+        compare_schema(&join(path, "[]"), &old_items, &new_items, direction, out);
+    }
+
+    // Descriptions, formats, bounds and the like
+    neutral(path, &old, &new, out);
+}
+
+/// A snapshot file written by `mcptool snapshot`, or a fresh capture of a
+/// target
+async fn load(ctx: &Ctx, source: &str) -> Result<Snapshot> {
+    let path = Path::new(source);
+    if path.is_file() {
+        return Snapshot::load(path);
+    }
+    let target = Target::parse(source)?;
+    let (mut client, init_result) = client::get_client(ctx, &target).await?;
+    Snapshot::capture(ctx, &target, &mut client, &init_result, &[]).await
+}
+
+pub struct DiffCommandArgs {
+    /// The server or snapshot to compare against
+    pub old: String,
+    /// The server or snapshot being checked
+    pub new: String,
+}
+
+pub async fn diff_command(ctx: &Ctx, args: DiffCommandArgs) -> Result<()> {
+    let old = load(ctx, &args.old).await?;
+    let new = load(ctx, &args.new).await?;
+    let deltas = compare(&old, &new)?;
+
+    let count = |impact: Impact| deltas.iter().filter(|d| d.impact == impact).count();
+    let breaking = count(Impact::Breaking);
+
+    if ctx.output.json {
+        ctx.output.json_value(&deltas)?;
+    } else if deltas.is_empty() {
+        ctx.output.trace_success(format!(
+            "No differences between {} and {}",
+            args.old, args.new
+        ))?;
+    } else {
+        ctx.output.h1(format!(
+            "{} difference(s) between {} and {}: {breaking} breaking, {} additive",
+            deltas.len(),
+            args.old,
+            args.new,
+            count(Impact::Additive)
+        ))?;
+        for delta in &deltas {
+            let symbol = match delta.impact {
+                Impact::Breaking => "!",
+                Impact::Additive => "+",
+                Impact::Neutral => "~",
+            };
+            ctx.output
+                .text(format!("{symbol} {}: {}", delta.path, delta.message))?;
+        }
+    }
+
+    if breaking > 0 {
+        return Err(Error::Other(format!(
+            "{breaking} breaking change(s) from {} to {}",
+            args.old, args.new
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn find<'a>(deltas: &'a [Delta], path: &str) -> Vec<(Impact, &'a str)> {
+        deltas
+            .iter()
+            .filter(|d| d.path == path)
+            .map(|d| (d.impact, d.message.as_str()))
+            .collect()
+    }
+
+    fn server(version: &str, tools: Value, prompts: Value, capabilities: Value) -> Value {
+        json!({
+            "init": {
+                "protocolVersion": "2025-06-18",
+                "capabilities": capabilities,
+                "serverInfo": {"name": "test", "version": version}
+            },
+            "tools": tools,
+            "prompts": prompts,
+            "resources": {"file:///a": {"uri": "file:///a", "name": "a"}},
+            "resource_templates": {},
+            "calls": {}
+        })
+    }
+
+    #[test]
+    fn test_compare_tools() {
+        let old = server(
+            "1.0",
+            json!({
+                "echo": {
+                    "name": "echo",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "message": {"type": "string"},
+                            "count": {"type": ["integer", "null"]},
+                            "mode": {"type": "string", "enum": ["a", "b"]},
+                            "loud": {"type": "boolean"}
+                        },
+                        "required": ["message"]
+                    },
+                    "outputSchema": {
+                        "type": "object",
+                        "properties": {"text": {"type": "string"}},
+                        "required": ["text"]
+                    }
+                },
+                "gone": {"name": "gone", "inputSchema": {"type": "object"}}
+            }),
+            json!({}),
+            json!({"tools": {"listChanged": true}, "logging": {}}),
+        );
+        let new = server(
+            "1.1",
+            json!({
+                "echo": {
+                    "name": "echo",
+                    "description": "Echo a message",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "message": {"type": "string"},
+                            "count": {"type": "integer"},
+                            "mode": {"type": "string", "enum": ["a", "c"]},
+                            "prefix": {"type": "string"},
+                            "lang": {"type": "string"}
+                        },
+                        "required": ["message", "lang"]
+                    },
+                    "outputSchema": {
+                        "type": "object",
+                        "properties": {"text": {"type": ["string", "null"]}}
+                    }
+                },
+                "new": {"name": "new", "inputSchema": {"type": "object"}}
+            }),
+            json!({}),
+            json!({"tools": {"listChanged": false}, "prompts": {}}),
+        );
+        let deltas = compare_normalized(&old, &new);
+
+        let input = "tools.echo.inputSchema";
+        assert_eq!(
+            find(&deltas, &format!("{input}.count")),
+            vec![(
+                Impact::Breaking,
+                "type narrowed from integer | null to integer"
+            )]
+        );
+        assert_eq!(
+            find(&deltas, &format!("{input}.mode")),
+            vec![
+                (Impact::Breaking, "values removed: \"b\""),
+                (Impact::Additive, "values added: \"c\"")
+            ]
+        );
+        assert_eq!(
+            find(&deltas, &format!("{input}.loud")),
+            vec![(Impact::Breaking, "parameter removed")]
+        );
+        assert_eq!(
+            find(&deltas, &format!("{input}.lang")),
+            vec![(Impact::Breaking, "new required parameter")]
+        );
+        assert_eq!(
+            find(&deltas, &format!("{input}.prefix")),
+            vec![(Impact::Additive, "new optional parameter")]
+        );
+        // Output that may now be null, or missing, breaks clients reading it
+        assert_eq!(
+            find(&deltas, "tools.echo.outputSchema.text"),
+            vec![
+                (
+                    Impact::Breaking,
+                    "type widened from string to string | null"
+                ),
+                (Impact::Breaking, "field is no longer always present")
+            ]
+        );
+        assert_eq!(
+            find(&deltas, "tools.gone"),
+            vec![(Impact::Breaking, "tool removed")]
+        );
+        assert_eq!(
+            find(&deltas, "tools.new"),
+            vec![(Impact::Additive, "tool added")]
+        );
+        assert_eq!(
+            find(&deltas, "tools.echo.description"),
+            vec![(Impact::Neutral, "added \"Echo a message\"")]
+        );
+        assert_eq!(
+            find(&deltas, "serverInfo.version"),
+            vec![(Impact::Neutral, "\"1.0\" -> \"1.1\"")]
+        );
+        assert_eq!(
+            find(&deltas, "capabilities.tools.listChanged"),
+            vec![(Impact::Breaking, "capability disabled")]
+        );
+        assert_eq!(
+            find(&deltas, "capabilities.logging"),
+            vec![(Impact::Breaking, "capability removed")]
+        );
+        assert_eq!(
+            find(&deltas, "capabilities.prompts"),
+            vec![(Impact::Additive, "capability added")]
+        );
+
+        // Breaking changes are listed first
+        let impacts: Vec<Impact> = deltas.iter().map(|d| d.impact).collect();
+        assert!(impacts.is_sorted());
+        assert!(compare_normalized(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn test_compare_prompts() {
+        let old = server(
+            "1.0",
+            json!({}),
+            json!({"greet": {"name": "greet", "arguments": [
+                {"name": "name", "required": true},
+                {"name": "style"},
+                {"name": "tone", "required": false}
+            ]}}),
+            json!({"prompts": {}}),
+        );
+        let new = server(
+            "1.0",
+            json!({}),
+            json!({"greet": {"name": "greet", "arguments": [
+                {"name": "name"},
+                {"name": "style", "required": true},
+                {"name": "language", "required": true}
+            ]}}),
+            json!({"prompts": {}}),
+        );
+        let deltas = compare_normalized(&old, &new);
+        assert_eq!(
+            deltas
+                .iter()
+                .map(|d| (d.impact, d.path.as_str(), d.message.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    Impact::Breaking,
+                    "prompts.greet.arguments.style",
+                    "argument is now required"
+                ),
+                (
+                    Impact::Breaking,
+                    "prompts.greet.arguments.tone",
+                    "argument removed"
+                ),
+                (
+                    Impact::Breaking,
+                    "prompts.greet.arguments.language",
+                    "new required argument"
+                ),
+                (
+                    Impact::Additive,
+                    "prompts.greet.arguments.name",
+                    "argument is now optional"
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,330 @@
+//! Run one MCP command against many targets at once, e.g. to take an
+//! inventory of the tools offered by a fleet of servers.
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde_json::{Value, json};
+use tokio::{sync::Semaphore, task::JoinSet};
+
+use crate::{
+    Error, Result, client,
+    command::{McpCommand, execute_mcp_command_with_client},
+    ctx::Ctx,
+    profile::Config,
+    target::Target,
+};
+
+pub struct FanoutCommandArgs {
+    pub targets: Vec<String>,
+    /// Maximum number of targets to run against at once
+    pub jobs: usize,
+    /// How long to wait for each target before giving up on it
+    pub timeout: Duration,
+}
+
+/// The outcome of the command on one target
+struct TargetResult {
+    target: String,
+    duration: Duration,
+    outcome: std::result::Result<Value, String>,
+}
+
+/// Targets listed in a file, one per line. Blank lines and `#` comments are
+/// skipped.
+pub fn load_targets(path: &Path) -> Result<Vec<String>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| Error::Other(format!("Failed to read {}: {e}", path.display())))?;
+    let targets: Vec<String> = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect();
+    if targets.is_empty() {
+        return Err(Error::Format(format!(
+            "No targets found in {}",
+            path.display()
+        )));
+    }
+    Ok(targets)
+}
+
+/// Every profile in the configuration, as `@name` targets
+pub fn profile_targets(ctx: &Ctx) -> Result<Vec<String>> {
+    let config = Config::load(&ctx.config_path)?;
+    if config.profiles.is_empty() {
+        return Err(Error::Other(
+            "No profiles found. Use 'mcptool profile add <name> <target>' to add one".to_string(),
+        ));
+    }
+    Ok(config
+        .profiles
+        .keys()
+        .map(|name| format!("@{name}"))
+        .collect())
+}
+
+pub async fn fanout_command(ctx: &Ctx, command: McpCommand, args: FanoutCommandArgs) -> Result<()> {
+    if let McpCommand::Calltool {
+        interactive, json, ..
+    } = &command
+        && (*interactive || *json)
+    {
+        return Err(Error::Other(
+            "calltool --interactive and --json read arguments once, so they can't be used with several targets"
+                .to_string(),
+        ));
+    }
+
+    // Each target runs silently; results are shown together at the end. The
+    // stderr of cmd:// servers is dropped too, unless --server-stderr asks
+    // for it, so that it doesn't break up the table.
+    let quiet_output = ctx.output.clone().with_quiet(true);
+    let mut quiet_ctx = Ctx {
+        output: quiet_output.clone(),
+        ..ctx.clone()
+    };
+    quiet_ctx.connect_options.server_output = Some(quiet_output);
+    let semaphore = Arc::new(Semaphore::new(args.jobs.max(1)));
+    let mut tasks = JoinSet::new();
+    for (idx, target) in args.targets.into_iter().enumerate() {
+        let ctx = quiet_ctx.clone();
+        let command = command.clone();
+        let semaphore = semaphore.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            (idx, run_target(&ctx, command, target, args.timeout).await)
+        });
+    }
+
+    let mut results = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        results.push(joined.map_err(|e| Error::Internal(e.to_string()))?);
+    }
+    results.sort_by_key(|(idx, _)| *idx);
+    let results: Vec<TargetResult> = results.into_iter().map(|(_, result)| result).collect();
+
+    if ctx.output.json {
+        let values: Vec<Value> = results
+            .iter()
+            .map(|result| {
+                let mut value = json!({
+                    "target": result.target,
+                    "duration_ms": result.duration.as_millis() as u64,
+                });
+                match &result.outcome {
+                    Ok(response) => value["result"] = response.clone(),
+                    Err(error) => value["error"] = json!(error),
+                }
+                value
+            })
+            .collect();
+        ctx.output.json_value(&values)?;
+    } else {
+        display_results(ctx, &command, &results)?;
+    }
+
+    let failed = results.iter().filter(|r| r.outcome.is_err()).count();
+    if failed > 0 {
+        return Err(Error::Other(format!(
+            "{failed} of {} target(s) failed",
+            results.len()
+        )));
+    }
+    Ok(())
+}
+
+async fn run_target(
+    ctx: &Ctx,
+    command: McpCommand,
+    target: String,
+    timeout: Duration,
+) -> TargetResult {
+    let start = Instant::now();
+    let run = async {
+        let (mut client, init_result) = client::get_client(ctx, &Target::parse(&target)?).await?;
+        execute_mcp_command_with_client(command, &mut client, &init_result, ctx).await
+    };
+    let outcome = match tokio::time::timeout(timeout, run).await {
+        Ok(outcome) => outcome.map_err(|e| e.to_string()),
+        Err(_) => Err(format!("Timed out after {}s", timeout.as_secs_f64())),
+    };
+    // Shown by its redacted form, when it parses
+    let target = Target::parse(&target)
+        .map(|parsed| parsed.redacted().to_string())
+        .unwrap_or(target);
+    TargetResult {
+        target,
+        duration: start.elapsed(),
+        outcome,
+    }
+}
+
+fn display_results(ctx: &Ctx, command: &McpCommand, results: &[TargetResult]) -> Result<()> {
+    let rows: Vec<(&str, &str, String, String)> = results
+        .iter()
+        .map(|result| {
+            let (status, detail) = match &result.outcome {
+                Ok(response) => ("ok", summarize(command, response)),
+                Err(error) => ("error", error.clone()),
+            };
+            let time = format!("{}ms", result.duration.as_millis());
+            (result.target.as_str(), status, time, detail)
+        })
+        .collect();
+
+    let max_target_len = rows.iter().map(|row| row.0.len()).max().unwrap_or(6).max(6);
+    let max_time_len = rows.iter().map(|row| row.2.len()).max().unwrap_or(4).max(4);
+
+    ctx.output.text(format!(
+        "{:<width_target$}  {:<6}  {:>width_time$}  {}",
+        "Target",
+        "Status",
+        "Time",
+        "Result",
+        width_target = max_target_len,
+        width_time = max_time_len,
+    ))?;
+    ctx.output.text(format!(
+        "{:-<width_target$}  {:-<6}  {:-<width_time$}  {:-<10}",
+        "",
+        "",
+        "",
+        "",
+        width_target = max_target_len,
+        width_time = max_time_len,
+    ))?;
+    for (target, status, time, detail) in rows {
+        ctx.output.text(format!(
+            "{target:<width_target$}  {status:<6}  {time:>width_time$}  {detail}",
+            width_target = max_target_len,
+            width_time = max_time_len,
+        ))?;
+    }
+    Ok(())
+}
+
+/// A one-line summary of a command's response
+fn summarize(command: &McpCommand, response: &Value) -> String {
+    // e.g. "2 tools: echo, add"
+    let names = |list: &str, key: &str, noun: &str| {
+        let items = response[list].as_array().cloned().unwrap_or_default();
+        let names: Vec<&str> = items.iter().filter_map(|item| item[key].as_str()).collect();
+        if names.is_empty() {
+            format!("0 {noun}")
+        } else {
+            format!("{} {noun}: {}", names.len(), names.join(", "))
+        }
+    };
+    match command {
+        McpCommand::Ping => "pong".to_string(),
+        McpCommand::Init => format!(
+            "{} {} (protocol {})",
+            response["serverInfo"]["name"].as_str().unwrap_or("?"),
+            response["serverInfo"]["version"].as_str().unwrap_or("?"),
+            response["protocolVersion"].as_str().unwrap_or("?"),
+        ),
+        McpCommand::Listtools => names("tools", "name", "tools"),
+        McpCommand::Listprompts => names("prompts", "name", "prompts"),
+        McpCommand::Listresources => names("resources", "uri", "resources"),
+        McpCommand::Listresourcetemplates => {
+            names("resourceTemplates", "uriTemplate", "resource templates")
+        }
+        McpCommand::Calltool { .. } => {
+            let text = response["content"]
+                .as_array()
+                .and_then(|content| content.iter().find_map(|item| item["text"].as_str()))
+                .unwrap_or_default();
+            let text: String = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            let text = match text.char_indices().nth(60) {
+                Some((end, _)) => format!("{}...", &text[..end]),
+                None => text,
+            };
+            if response["isError"].as_bool() == Some(true) {
+                format!("tool error: {text}")
+            } else {
+                text
+            }
+        }
+        McpCommand::Readresource { .. } => format!(
+            "{} content item(s)",
+            response["contents"].as_array().map_or(0, Vec::len)
+        ),
+        McpCommand::Getprompt { .. } => format!(
+            "{} message(s)",
+            response["messages"].as_array().map_or(0, Vec::len)
+        ),
+        McpCommand::Complete { .. } => response["completion"]["values"]
+            .as_array()
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .unwrap_or_default(),
+        McpCommand::Setlevel { .. }
+        | McpCommand::Subscriberesource { .. }
+        | McpCommand::Unsubscriberesource { .. } => "ok".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_targets() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("targets.txt");
+        std::fs::write(
+            &path,
+            "# production\nhttps://mcp.example.com/v1\n\n  @fs  \ncmd://./server --verbose\n",
+        )
+        .unwrap();
+        assert_eq!(
+            load_targets(&path).unwrap(),
+            vec![
+                "https://mcp.example.com/v1",
+                "@fs",
+                "cmd://./server --verbose"
+            ]
+        );
+
+        std::fs::write(&path, "# nothing here\n").unwrap();
+        assert!(load_targets(&path).is_err());
+    }
+
+    #[test]
+    fn test_summarize() {
+        let tools = json!({"tools": [{"name": "echo"}, {"name": "add"}]});
+        assert_eq!(
+            summarize(&McpCommand::Listtools, &tools),
+            "2 tools: echo, add"
+        );
+        assert_eq!(
+            summarize(
+                &McpCommand::Listresourcetemplates,
+                &json!({"resourceTemplates": []})
+            ),
+            "0 resource templates"
+        );
+        let call = McpCommand::Calltool {
+            tool_name: "echo".to_string(),
+            args: vec![],
+            interactive: false,
+            json: false,
+            arguments: None,
+        };
+        assert_eq!(
+            summarize(
+                &call,
+                &json!({"content": [{"type": "text", "text": "boom\nfailed"}], "isError": true})
+            ),
+            "tool error: boom failed"
+        );
+    }
+}
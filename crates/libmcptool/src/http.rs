@@ -0,0 +1,604 @@
+//! HTTP plumbing shared by the MCP clients and the proxy.
+//!
+//! The MCP client library builds its own HTTP client, so options it has no
+//! setting for, such as extra headers or TLS settings, are applied by
+//! speaking streamable HTTP ourselves and handing the library a stream of
+//! newline-delimited JSON-RPC, as for the transports in [`crate::transport`].
+
+use std::sync::{Arc, Mutex};
+
+use serde_json::Value;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, DuplexStream},
+    sync::mpsc,
+};
+
+use crate::{Error, Result, egress::ProxyOptions, tls::TlsOptions};
+
+/// Extra request options for HTTP targets
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HttpOptions {
+    /// Headers added to every request
+    pub headers: Vec<(String, String)>,
+    /// Sent as an `Authorization: Bearer` header
+    pub bearer: Option<String>,
+    /// Certificate verification and client certificates
+    pub tls: TlsOptions,
+    /// Outbound HTTP or SOCKS proxy
+    pub proxy: ProxyOptions,
+}
+
+impl HttpOptions {
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+            && self.bearer.is_none()
+            && self.tls.is_empty()
+            && self.proxy.is_empty()
+    }
+
+    /// Combine with another set of options, which take precedence
+    pub fn merged(&self, overrides: &HttpOptions) -> HttpOptions {
+        HttpOptions {
+            headers: self
+                .headers
+                .iter()
+                .chain(&overrides.headers)
+                .cloned()
+                .collect(),
+            bearer: overrides.bearer.clone().or_else(|| self.bearer.clone()),
+            tls: if overrides.tls.is_empty() {
+                self.tls.clone()
+            } else {
+                overrides.tls.clone()
+            },
+            proxy: if overrides.proxy.is_empty() {
+                self.proxy.clone()
+            } else {
+                overrides.proxy.clone()
+            },
+        }
+    }
+
+    /// Only the settings for reaching a server, without the headers and
+    /// bearer token meant for an MCP endpoint
+    pub fn without_credentials(&self) -> HttpOptions {
+        HttpOptions {
+            tls: self.tls.clone(),
+            proxy: self.proxy.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Build an HTTP client for these options
+    pub fn client(&self) -> Result<reqwest::Client> {
+        let builder = self.tls.configure(reqwest::Client::builder())?;
+        self.proxy
+            .configure(builder)?
+            .build()
+            .map_err(|e| Error::Other(format!("Failed to create HTTP client: {e}")))
+    }
+
+    /// The headers to send, including the bearer token
+    pub fn header_pairs(&self) -> Vec<(String, String)> {
+        let mut headers = self.headers.clone();
+        if let Some(token) = &self.bearer {
+            headers.push(("Authorization".to_string(), format!("Bearer {token}")));
+        }
+        headers
+    }
+
+    /// Add the headers and bearer token to a request
+    pub fn apply(&self, mut request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        for (name, value) in self.header_pairs() {
+            request = request.header(name, value);
+        }
+        request
+    }
+}
+
+/// Parse a `Name: value` header
+pub fn parse_header(input: &str) -> Result<(String, String)> {
+    let (name, value) = input
+        .split_once(':')
+        .ok_or_else(|| Error::Format(format!("Expected 'Name: value', got '{input}'")))?;
+    let name = name.trim();
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c))
+    {
+        return Err(Error::Format(format!("Invalid header name '{name}'")));
+    }
+    Ok((name.to_string(), value.trim().to_string()))
+}
+
+/// Resolve a secret given as a literal value, `@file` or `env:VAR`
+pub fn resolve_secret(spec: &str) -> Result<String> {
+    if let Some(path) = spec.strip_prefix('@') {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| Error::Other(format!("Failed to read secret from {path}: {e}")))?;
+        Ok(contents.trim().to_string())
+    } else if let Some(var) = spec.strip_prefix("env:") {
+        std::env::var(var)
+            .map_err(|_| Error::Other(format!("Environment variable {var} is not set")))
+    } else {
+        Ok(spec.to_string())
+    }
+}
+
+/// A server-sent event
+#[derive(Debug, Clone, PartialEq)]
+pub struct SseEvent {
+    /// The event type, if one was given
+    pub event: Option<String>,
+    /// The data lines, joined with newlines
+    pub data: String,
+}
+
+/// Remove the first complete event from a buffer of server-sent event bytes.
+/// Returns None if the buffer doesn't hold a complete event yet. Events are
+/// only decoded once complete, so characters split across network reads
+/// survive.
+pub fn next_sse_event(buffer: &mut Vec<u8>) -> Option<SseEvent> {
+    if buffer.contains(&b'\r') {
+        let mut normalized = Vec::with_capacity(buffer.len());
+        for (idx, byte) in buffer.iter().enumerate() {
+            if *byte != b'\r' || buffer.get(idx + 1) != Some(&b'\n') {
+                normalized.push(*byte);
+            }
+        }
+        *buffer = normalized;
+    }
+    let end = buffer.windows(2).position(|pair| pair == b"\n\n")?;
+    let block: Vec<u8> = buffer.drain(..end + 2).collect();
+    let block = String::from_utf8_lossy(&block);
+    let mut event = None;
+    let mut data = vec![];
+    for line in block.lines() {
+        if let Some(value) = line.strip_prefix("data:") {
+            data.push(value.strip_prefix(' ').unwrap_or(value));
+        } else if let Some(value) = line.strip_prefix("event:") {
+            event = Some(value.trim().to_string());
+        }
+    }
+    Some(SseEvent {
+        event,
+        data: data.join("\n"),
+    })
+}
+
+/// A JSON-RPC error answering `message` if it is a request, so that the client
+/// isn't left waiting when forwarding it fails
+pub fn error_response(message: &str, error: &Error) -> Option<String> {
+    let message = serde_json::from_str::<serde_json::Value>(message).ok()?;
+    let id = message.get("id")?;
+    message.get("method")?;
+    let response = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {"code": -32603, "message": format!("Proxy error: {error}")},
+    });
+    Some(response.to_string())
+}
+
+/// Whether a JSON-RPC message is a request, and so expects a response
+pub fn is_request(message: &str) -> bool {
+    serde_json::from_str::<Value>(message)
+        .is_ok_and(|message| message.get("id").is_some() && message.get("method").is_some())
+}
+
+/// Buffer size of the in-memory stream handed back to the caller
+const STREAM_BUFFER: usize = 64 * 1024;
+
+/// Open a streamable HTTP connection to `url`. Returns a stream that carries
+/// one JSON-RPC message per line in each direction, so that the options are
+/// applied by a client we configure ourselves.
+///
+/// Messages are POSTed in the order they are written. Notifications and
+/// responses are sent before the next message is read; requests are sent
+/// concurrently, so long-running requests don't hold up others. Messages in
+/// the responses, whether plain JSON or an event stream, are written back as
+/// they arrive. Once the session is initialized, a GET event stream carries
+/// messages the server sends on its own. When the caller drops its end the
+/// session is ended with a DELETE request.
+pub async fn connect(url: &str, options: &HttpOptions) -> Result<DuplexStream> {
+    url::Url::parse(url).map_err(|e| Error::Format(format!("Invalid URL {url}: {e}")))?;
+    let (local, remote) = tokio::io::duplex(STREAM_BUFFER);
+    let (reader, mut writer) = tokio::io::split(remote);
+    let (sender, mut receiver) = mpsc::unbounded_channel::<String>();
+    let connection = Connection {
+        client: options.client()?,
+        url: url.to_string(),
+        options: options.clone(),
+        session: Arc::new(Mutex::new(Session::default())),
+        sender,
+    };
+
+    // Messages from the caller
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(reader).lines();
+        let mut listener = None;
+        while let Ok(Some(line)) = lines.next_line().await {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if is_request(&line) {
+                let connection = connection.clone();
+                tokio::spawn(async move { connection.send(&line).await });
+                continue;
+            }
+            let initialized = serde_json::from_str::<Value>(&line)
+                .is_ok_and(|message| message["method"] == "notifications/initialized");
+            connection.send(&line).await;
+            if initialized && listener.is_none() {
+                listener = Some(tokio::spawn(connection.clone().listen()));
+            }
+        }
+        if let Some(listener) = listener {
+            listener.abort();
+        }
+        connection.close().await;
+    });
+
+    // Messages to the caller, until every sender is gone
+    tokio::spawn(async move {
+        while let Some(message) = receiver.recv().await {
+            if writer
+                .write_all(format!("{message}\n").as_bytes())
+                .await
+                .is_err()
+            {
+                return;
+            }
+        }
+    });
+
+    Ok(local)
+}
+
+/// State the server hands out, to be sent back on later requests
+#[derive(Default)]
+struct Session {
+    id: Option<String>,
+    protocol_version: Option<String>,
+}
+
+/// Everything needed to talk to the server over HTTP
+#[derive(Clone)]
+struct Connection {
+    client: reqwest::Client,
+    url: String,
+    options: HttpOptions,
+    session: Arc<Mutex<Session>>,
+    sender: mpsc::UnboundedSender<String>,
+}
+
+impl Connection {
+    async fn send(&self, line: &str) {
+        if let Err(e) = self.forward(line).await
+            && let Some(response) = error_response(line, &e)
+        {
+            let _ = self.sender.send(response);
+        }
+    }
+
+    /// Add our headers and the session state to a request
+    fn prepare(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        let mut request = self.options.apply(request);
+        let session = self.session.lock().unwrap();
+        if let Some(id) = &session.id {
+            request = request.header("Mcp-Session-Id", id);
+        }
+        if let Some(version) = &session.protocol_version {
+            request = request.header("MCP-Protocol-Version", version);
+        }
+        request
+    }
+
+    async fn forward(&self, line: &str) -> Result<()> {
+        let response = self
+            .prepare(
+                self.client
+                    .post(&self.url)
+                    .header("Accept", "application/json, text/event-stream")
+                    .header("Content-Type", "application/json")
+                    .body(line.to_string()),
+            )
+            .send()
+            .await
+            .map_err(|e| Error::Other(format!("HTTP request failed: {e}")))?;
+
+        if let Some(new_session) = response
+            .headers()
+            .get("Mcp-Session-Id")
+            .and_then(|v| v.to_str().ok())
+        {
+            self.session.lock().unwrap().id = Some(new_session.to_string());
+        }
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(Error::Other(format!("HTTP {status}: {body}")));
+        }
+
+        let is_stream = response
+            .headers()
+            .get("Content-Type")
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/event-stream"));
+        if is_stream {
+            self.read_events(response).await
+        } else {
+            let body = response
+                .text()
+                .await
+                .map_err(|e| Error::Other(format!("Failed to read HTTP response: {e}")))?;
+            if !body.trim().is_empty() {
+                self.emit(&body)?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Receive the messages the server sends outside of any request. Servers
+    /// that don't offer this stream answer 405, which leaves nothing to do.
+    async fn listen(self) {
+        let request = self.prepare(
+            self.client
+                .get(&self.url)
+                .header("Accept", "text/event-stream"),
+        );
+        if let Ok(response) = request.send().await
+            && response.status().is_success()
+        {
+            let _ = self.read_events(response).await;
+        }
+    }
+
+    /// End the session, if the server gave us one. Servers may refuse to let
+    /// clients end sessions, so the response doesn't matter.
+    async fn close(&self) {
+        if self.session.lock().unwrap().id.is_none() {
+            return;
+        }
+        let _ = self.prepare(self.client.delete(&self.url)).send().await;
+    }
+
+    async fn read_events(&self, mut response: reqwest::Response) -> Result<()> {
+        let mut buffer = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| Error::Other(format!("Failed to read event stream: {e}")))?
+        {
+            buffer.extend_from_slice(&chunk);
+            while let Some(event) = next_sse_event(&mut buffer) {
+                if !event.data.is_empty() {
+                    self.emit(&event.data)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Send a JSON body to the caller, one message per line. The protocol
+    /// version the server agrees to is remembered for later requests.
+    fn emit(&self, body: &str) -> Result<()> {
+        let messages = match serde_json::from_str::<Value>(body)? {
+            Value::Array(messages) => messages,
+            message => vec![message],
+        };
+        for message in messages {
+            if let Some(version) = message
+                .pointer("/result/protocolVersion")
+                .and_then(Value::as_str)
+            {
+                self.session.lock().unwrap().protocol_version = Some(version.to_string());
+            }
+            let _ = self.sender.send(message.to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_header() {
+        assert_eq!(
+            parse_header("X-Api-Key:  abc123 ").unwrap(),
+            ("X-Api-Key".to_string(), "abc123".to_string())
+        );
+        assert_eq!(
+            parse_header("Accept: a: b").unwrap(),
+            ("Accept".to_string(), "a: b".to_string())
+        );
+        assert!(parse_header("no colon").is_err());
+        assert!(parse_header("Bad Name: x").is_err());
+        assert!(parse_header(": x").is_err());
+    }
+
+    #[test]
+    fn test_resolve_secret() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("token");
+        std::fs::write(&path, "from-file\n").unwrap();
+
+        assert_eq!(resolve_secret("literal").unwrap(), "literal");
+        assert_eq!(
+            resolve_secret(&format!("@{}", path.display())).unwrap(),
+            "from-file"
+        );
+        assert!(resolve_secret("env:MCPTOOL_TEST_UNSET_VARIABLE").is_err());
+    }
+
+    #[test]
+    fn test_merged() {
+        let base = HttpOptions {
+            headers: vec![("A".to_string(), "1".to_string())],
+            bearer: Some("stored".to_string()),
+            ..Default::default()
+        };
+        let overrides = HttpOptions {
+            headers: vec![("B".to_string(), "2".to_string())],
+            bearer: None,
+            ..Default::default()
+        };
+        let merged = base.merged(&overrides);
+        assert_eq!(merged.headers.len(), 2);
+        assert_eq!(merged.bearer.as_deref(), Some("stored"));
+        assert_eq!(
+            merged.header_pairs().last().unwrap(),
+            &("Authorization".to_string(), "Bearer stored".to_string())
+        );
+    }
+
+    #[test]
+    fn test_error_response() {
+        let error = Error::Other("connection refused".to_string());
+        let response = error_response(r#"{"jsonrpc":"2.0","id":7,"method":"ping"}"#, &error)
+            .expect("requests get an error response");
+        let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(response["id"], 7);
+        assert_eq!(response["error"]["code"], -32603);
+        assert!(
+            error_response(
+                r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+                &error
+            )
+            .is_none()
+        );
+        assert!(error_response(r#"{"jsonrpc":"2.0","id":7,"result":{}}"#, &error).is_none());
+        assert!(error_response("not json", &error).is_none());
+    }
+
+    #[test]
+    fn test_is_request() {
+        assert!(is_request(r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#));
+        assert!(!is_request(
+            r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#
+        ));
+        assert!(!is_request(r#"{"jsonrpc":"2.0","id":1,"result":{}}"#));
+        assert!(!is_request("not json"));
+    }
+
+    #[test]
+    fn test_next_sse_event() {
+        let mut buffer = b": keepalive\n\nevent: endpoint\r\ndata: /messages?id=1\r\n\r\ndata: {\"a\":\ndata: 1}\n\ndata: partial".to_vec();
+
+        let comment = next_sse_event(&mut buffer).unwrap();
+        assert_eq!(comment.data, "");
+
+        let endpoint = next_sse_event(&mut buffer).unwrap();
+        assert_eq!(endpoint.event.as_deref(), Some("endpoint"));
+        assert_eq!(endpoint.data, "/messages?id=1");
+
+        let message = next_sse_event(&mut buffer).unwrap();
+        assert_eq!(message.event, None);
+        assert_eq!(message.data, "{\"a\":\n1}");
+
+        assert_eq!(next_sse_event(&mut buffer), None);
+        assert_eq!(buffer, b"data: partial");
+
+        // A character split between reads is decoded whole
+        let text = "data: caf\u{e9}\n\n".as_bytes();
+        let mut buffer = text[..10].to_vec();
+        assert_eq!(next_sse_event(&mut buffer), None);
+        buffer.extend_from_slice(&text[10..]);
+        assert_eq!(next_sse_event(&mut buffer).unwrap().data, "caf\u{e9}");
+    }
+
+    #[tokio::test]
+    async fn test_connect() {
+        use tokio::io::AsyncReadExt;
+        use tokio::net::TcpListener;
+
+        // An endpoint that answers the first request with JSON and the second
+        // with an event stream, echoing back the headers it was sent
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            for idx in 0.. {
+                let (stream, _) = listener.accept().await.unwrap();
+                let (reader, mut writer) = stream.into_split();
+                let mut reader = BufReader::new(reader);
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).await.unwrap();
+                assert!(request_line.starts_with("POST /mcp?x=1 "), "{request_line}");
+                let mut head = String::new();
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).await.unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    let line = line.to_lowercase();
+                    if let Some(value) = line.strip_prefix("content-length:") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                    head.push_str(&line);
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).await.unwrap();
+                let request: Value = serde_json::from_slice(&body).unwrap();
+                let result = serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "result": {"protocolVersion": "2025-06-18", "head": head},
+                })
+                .to_string();
+                let response = if idx == 0 {
+                    format!(
+                        "HTTP/1.1 200 OK\r\nMcp-Session-Id: abc\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{result}",
+                        result.len()
+                    )
+                } else {
+                    let event = format!("event: message\ndata: {result}\n\n");
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nContent-Length: {}\r\n\r\n{event}",
+                        event.len()
+                    )
+                };
+                writer.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let options = HttpOptions {
+            headers: vec![("X-Api-Key".to_string(), "secret".to_string())],
+            bearer: Some("token".to_string()),
+            ..Default::default()
+        };
+        let stream = connect(&format!("http://127.0.0.1:{port}/mcp?x=1"), &options)
+            .await
+            .unwrap();
+        let (reader, mut writer) = tokio::io::split(stream);
+        let mut lines = BufReader::new(reader).lines();
+
+        writer
+            .write_all(b"{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"initialize\"}\n")
+            .await
+            .unwrap();
+        let first: Value =
+            serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(first["id"], 1);
+        let head = first["result"]["head"].as_str().unwrap();
+        assert!(head.contains("x-api-key: secret"), "{head}");
+        assert!(head.contains("authorization: bearer token"), "{head}");
+        assert!(!head.contains("mcp-session-id"), "{head}");
+
+        // The session and protocol version are sent back
+        writer
+            .write_all(b"{\"jsonrpc\":\"2.0\",\"id\":2,\"method\":\"ping\"}\n")
+            .await
+            .unwrap();
+        let second: Value =
+            serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(second["id"], 2);
+        let head = second["result"]["head"].as_str().unwrap();
+        assert!(head.contains("mcp-session-id: abc"), "{head}");
+        assert!(head.contains("mcp-protocol-version: 2025-06-18"), "{head}");
+    }
+}
@@ -1,18 +1,36 @@
 pub mod args;
 pub mod auth;
+pub mod bench;
 pub mod calltool;
 pub mod client;
+pub mod clientconfig;
 pub mod command;
+pub mod conformance;
 pub mod connect;
 pub mod ctx;
+pub mod diff;
+pub mod egress;
 pub mod error;
+pub mod fanout;
+pub mod http;
 pub mod mcp;
 pub mod output;
+pub mod probe;
+pub mod profile;
 pub mod proxy;
+pub mod rawclient;
+pub mod snapshot;
+pub mod sse;
 pub mod storage;
 pub mod target;
 pub mod testserver;
+pub mod testsuite;
+pub mod tls;
+pub mod transcript;
+pub mod transport;
 pub mod utils;
+pub mod watch;
+pub mod ws;
 
 // Re-export commonly used error types
 pub use error::{Error, Result};
@@ -1,6 +1,11 @@
+use serde_json::{Map, Value};
 use tenx_mcp::{
     Client, ClientConn, ServerAPI,
-    schema::{InitializeResult, LoggingLevel},
+    schema::{
+        CallToolResult, CompleteResult, Cursor, GetPromptResult, InitializeResult,
+        ListPromptsResult, ListResourceTemplatesResult, ListResourcesResult, ListToolsResult,
+        LoggingLevel, Prompt, ReadResourceResult, Resource, ResourceTemplate, Tool,
+    },
 };
 
 use crate::{Result, args::ArgumentParser, calltool, output, utils::TimedFuture};
@@ -18,14 +23,86 @@ pub async fn ping<C: ClientConn + 'static>(
 pub async fn listtools<C: ClientConn + 'static>(
     client: &mut Client<C>,
     output: &crate::output::Output,
-) -> Result<()> {
+) -> Result<ListToolsResult> {
     output.text("Listing tools")?;
     let tools_result = client
         .list_tools(None)
         .timed("    response", output)
         .await?;
     output::listtools::list_tools_result(output, &tools_result)?;
-    Ok(())
+    Ok(tools_result)
+}
+
+/// Follow a pagination cursor. Returns None when there are no more pages, and
+/// fails if the server hands back the cursor it was just given, which would
+/// otherwise loop forever.
+fn next_page(current: &Option<Cursor>, next: Option<Cursor>) -> Result<Option<Cursor>> {
+    match (current, next) {
+        (Some(current), Some(next)) if current.to_string() == next.to_string() => Err(
+            crate::Error::Other(format!("Server repeated pagination cursor: {next}")),
+        ),
+        (_, next) => Ok(next),
+    }
+}
+
+/// Fetch all tools, following pagination cursors
+pub async fn fetch_tools<C: ClientConn + 'static>(client: &mut Client<C>) -> Result<Vec<Tool>> {
+    let mut tools = vec![];
+    let mut cursor = None;
+    loop {
+        let page = client.list_tools(cursor.clone()).await?;
+        tools.extend(page.tools);
+        cursor = next_page(&cursor, page.next_cursor)?;
+        if cursor.is_none() {
+            return Ok(tools);
+        }
+    }
+}
+
+/// Fetch all prompts, following pagination cursors
+pub async fn fetch_prompts<C: ClientConn + 'static>(client: &mut Client<C>) -> Result<Vec<Prompt>> {
+    let mut prompts = vec![];
+    let mut cursor = None;
+    loop {
+        let page = client.list_prompts(cursor.clone()).await?;
+        prompts.extend(page.prompts);
+        cursor = next_page(&cursor, page.next_cursor)?;
+        if cursor.is_none() {
+            return Ok(prompts);
+        }
+    }
+}
+
+/// Fetch all resources, following pagination cursors
+pub async fn fetch_resources<C: ClientConn + 'static>(
+    client: &mut Client<C>,
+) -> Result<Vec<Resource>> {
+    let mut resources = vec![];
+    let mut cursor = None;
+    loop {
+        let page = client.list_resources(cursor.clone()).await?;
+        resources.extend(page.resources);
+        cursor = next_page(&cursor, page.next_cursor)?;
+        if cursor.is_none() {
+            return Ok(resources);
+        }
+    }
+}
+
+/// Fetch all resource templates, following pagination cursors
+pub async fn fetch_resource_templates<C: ClientConn + 'static>(
+    client: &mut Client<C>,
+) -> Result<Vec<ResourceTemplate>> {
+    let mut templates = vec![];
+    let mut cursor = None;
+    loop {
+        let page = client.list_resource_templates(cursor.clone()).await?;
+        templates.extend(page.resource_templates);
+        cursor = next_page(&cursor, page.next_cursor)?;
+        if cursor.is_none() {
+            return Ok(templates);
+        }
+    }
 }
 
 pub fn init(init_result: &InitializeResult, output: &crate::output::Output) -> Result<()> {
@@ -36,40 +113,40 @@ pub fn init(init_result: &InitializeResult, output: &crate::output::Output) -> R
 pub async fn listresources<C: ClientConn + 'static>(
     client: &mut Client<C>,
     output: &crate::output::Output,
-) -> Result<()> {
+) -> Result<ListResourcesResult> {
     output.text("Listing resources")?;
     let resources_result = client
         .list_resources(None)
         .timed("    response", output)
         .await?;
     output::listresources::list_resources_result(output, &resources_result)?;
-    Ok(())
+    Ok(resources_result)
 }
 
 pub async fn listprompts<C: ClientConn + 'static>(
     client: &mut Client<C>,
     output: &crate::output::Output,
-) -> Result<()> {
+) -> Result<ListPromptsResult> {
     output.text("Listing prompts")?;
     let prompts_result = client
         .list_prompts(None)
         .timed("    response", output)
         .await?;
     output::listprompts::list_prompts_result(output, &prompts_result)?;
-    Ok(())
+    Ok(prompts_result)
 }
 
 pub async fn listresourcetemplates<C: ClientConn + 'static>(
     client: &mut Client<C>,
     output: &crate::output::Output,
-) -> Result<()> {
+) -> Result<ListResourceTemplatesResult> {
     output.text("Listing resource templates")?;
     let templates_result = client
         .list_resource_templates(None)
         .timed("    response", output)
         .await?;
     output::listresourcetemplates::list_resource_templates_result(output, &templates_result)?;
-    Ok(())
+    Ok(templates_result)
 }
 
 pub async fn set_level<C: ClientConn + 'static>(
@@ -114,9 +191,10 @@ pub async fn calltool<C: ClientConn + 'static>(
     args: Vec<String>,
     interactive: bool,
     json: bool,
-) -> Result<()> {
+    arguments: Option<Map<String, Value>>,
+) -> Result<CallToolResult> {
     // Validate input modes
-    let mode_count = [!args.is_empty(), interactive, json]
+    let mode_count = [!args.is_empty(), interactive, json, arguments.is_some()]
         .iter()
         .filter(|&&x| x)
         .count();
@@ -146,7 +224,9 @@ pub async fn calltool<C: ClientConn + 'static>(
         .ok_or_else(|| crate::Error::Other(format!("Tool '{tool_name}' not found")))?;
 
     // Parse arguments based on mode
-    let arguments = if json {
+    let arguments = if let Some(arguments) = arguments {
+        ArgumentParser::from_map(arguments)
+    } else if json {
         calltool::json::parse_json_arguments(output)?
     } else if interactive {
         calltool::interactive::parse_interactive_arguments(tool, output)?
@@ -160,21 +240,22 @@ pub async fn calltool<C: ClientConn + 'static>(
         .timed("   response", output)
         .await?;
 
-    output::calltool::call_tool_result(output, &result)
+    output::calltool::call_tool_result(output, &result)?;
+    Ok(result)
 }
 
 pub async fn read_resource<C: ClientConn + 'static>(
     client: &mut Client<C>,
     output: &crate::output::Output,
     uri: &str,
-) -> Result<()> {
+) -> Result<ReadResourceResult> {
     output.text(format!("Reading resource: {uri}"))?;
     let result = client
         .resources_read(uri)
         .timed("    response", output)
         .await?;
     output::readresource::read_resource_result(output, &result)?;
-    Ok(())
+    Ok(result)
 }
 
 pub async fn get_prompt<C: ClientConn + 'static>(
@@ -182,18 +263,27 @@ pub async fn get_prompt<C: ClientConn + 'static>(
     output: &crate::output::Output,
     name: &str,
     args: Vec<String>,
-) -> Result<()> {
+    arguments: Option<Map<String, Value>>,
+) -> Result<GetPromptResult> {
     output.text(format!("Getting prompt: {name}"))?;
 
-    // Parse arguments from key=value format
-    let arguments = ArgumentParser::parse_key_value_args(args)?;
+    let arguments = match arguments {
+        Some(_) if !args.is_empty() => {
+            return Err(crate::Error::Other(
+                "Cannot combine JSON arguments with --arg".to_string(),
+            ));
+        }
+        Some(arguments) => ArgumentParser::from_map(arguments),
+        // Parse arguments from key=value format
+        None => ArgumentParser::parse_key_value_args(args)?,
+    };
 
     let result = client
         .get_prompt(name, arguments)
         .timed("    response", output)
         .await?;
     output::getprompt::get_prompt_result(output, &result)?;
-    Ok(())
+    Ok(result)
 }
 
 pub async fn subscribe_resource<C: ClientConn + 'static>(
@@ -229,7 +319,7 @@ pub async fn complete<C: ClientConn + 'static>(
     output: &crate::output::Output,
     reference: &str,
     argument: &str,
-) -> Result<()> {
+) -> Result<CompleteResult> {
     output.text(format!("Getting completions for: {reference}/{argument}"))?;
 
     // Parse the reference into Reference
@@ -262,5 +352,5 @@ pub async fn complete<C: ClientConn + 'static>(
         .timed("    response", output)
         .await?;
     output::complete::complete_result(output, &result)?;
-    Ok(())
+    Ok(result)
 }
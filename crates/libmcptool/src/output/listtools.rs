@@ -1,6 +1,22 @@
 use crate::Result;
 use crate::output::Output;
 
+/// The type a property schema declares, with union types like
+/// `["string", "null"]` shown as `string | null`
+pub fn schema_type(schema: &serde_json::Value) -> Option<String> {
+    match schema.get("type") {
+        Some(serde_json::Value::String(t)) => Some(t.to_string()),
+        Some(serde_json::Value::Array(types)) => Some(
+            types
+                .iter()
+                .filter_map(|v| v.as_str())
+                .collect::<Vec<_>>()
+                .join(" | "),
+        ),
+        _ => None,
+    }
+}
+
 fn toolschema(output: &Output, schema: &tenx_mcp::schema::ToolSchema) -> Result<()> {
     if let Some(properties) = &schema.properties {
         if !properties.is_empty() {
@@ -17,19 +33,7 @@ fn toolschema(output: &Output, schema: &tenx_mcp::schema::ToolSchema) -> Result<
             for (name, prop_schema) in sorted_props {
                 let is_required = schema.is_required(name);
 
-                // Extract type from schema
-                let type_str = if let Some(serde_json::Value::String(t)) = prop_schema.get("type") {
-                    t.to_string()
-                } else if let Some(serde_json::Value::Array(types)) = prop_schema.get("type") {
-                    // Handle union types like ["string", "null"]
-                    types
-                        .iter()
-                        .filter_map(|v| v.as_str())
-                        .collect::<Vec<_>>()
-                        .join(" | ")
-                } else {
-                    "unknown".to_string()
-                };
+                let type_str = schema_type(prop_schema).unwrap_or_else(|| "unknown".to_string());
 
                 // Use kv() to display property name and type
                 output.kv(name, &type_str)?;
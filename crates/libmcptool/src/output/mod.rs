@@ -364,6 +364,11 @@ impl Output {
         self.status(message, "[INFO]", SolarizedDark::BLUE, false)
     }
 
+    /// A line from the stderr of a server process
+    pub fn server_stderr(&self, line: impl Into<String>) -> io::Result<()> {
+        self.status(line, "[server]", SolarizedDark::VIOLET, false)
+    }
+
     pub fn success(&self, message: impl Into<String>) -> io::Result<()> {
         if self.json || self.quiet {
             return Ok(());
@@ -0,0 +1,166 @@
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    Result,
+    ctx::Ctx,
+    rawclient::{PROTOCOL_VERSIONS, RawClient, Reply},
+    target::Target,
+};
+
+/// How a server answered an initialize request for one protocol version
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VersionProbe {
+    pub requested: String,
+    /// True if the server agreed to the requested version
+    pub accepted: bool,
+    /// The version the server answered with, if it answered at all
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub answered: Option<String>,
+    /// Server name and version from the initialize result
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl VersionProbe {
+    fn from_reply(requested: &str, reply: &Reply) -> Self {
+        let mut probe = VersionProbe {
+            requested: requested.to_string(),
+            accepted: false,
+            answered: None,
+            server: None,
+            error: None,
+        };
+        match reply {
+            Reply::Result(result) => {
+                probe.answered = result
+                    .get("protocolVersion")
+                    .and_then(Value::as_str)
+                    .map(str::to_string);
+                probe.accepted = probe.answered.as_deref() == Some(requested);
+                let info = &result["serverInfo"];
+                if let (Some(name), Some(version)) =
+                    (info["name"].as_str(), info["version"].as_str())
+                {
+                    probe.server = Some(format!("{name} v{version}"));
+                }
+                if probe.answered.is_none() {
+                    probe.error = Some("Result has no protocolVersion".to_string());
+                }
+            }
+            Reply::Error { code, message, .. } => {
+                probe.error = Some(format!("JSON-RPC error {code}: {message}"));
+            }
+        }
+        probe
+    }
+}
+
+pub struct ProbeVersionsCommandArgs {
+    pub target: String,
+    /// Versions to try. Empty means every known revision.
+    pub versions: Vec<String>,
+    pub timeout: Duration,
+}
+
+pub async fn probe_versions_command(ctx: &Ctx, args: ProbeVersionsCommandArgs) -> Result<()> {
+    let target = Target::parse(&args.target)?;
+    let versions = if args.versions.is_empty() {
+        PROTOCOL_VERSIONS.iter().map(|v| v.to_string()).collect()
+    } else {
+        args.versions
+    };
+
+    ctx.output.text(format!(
+        "Probing {} with {} protocol version(s)",
+        target.redacted(),
+        versions.len()
+    ))?;
+
+    let mut probes = vec![];
+    for version in &versions {
+        // Each version gets a fresh connection, since a server may only be
+        // initialized once
+        let probe = match RawClient::connect(ctx, &target, args.timeout).await {
+            Ok(mut client) => match client.initialize(version).await {
+                Ok(reply) => VersionProbe::from_reply(version, &reply),
+                Err(e) => failed_probe(version, e.to_string()),
+            },
+            Err(e) => failed_probe(version, e.to_string()),
+        };
+        probes.push(probe);
+    }
+
+    if ctx.output.json {
+        return ctx.output.json_value(&probes);
+    }
+
+    ctx.output.h1("Protocol versions")?;
+    let out = ctx.output.indent();
+    for probe in &probes {
+        match (&probe.error, probe.accepted, &probe.answered) {
+            (Some(error), _, _) => out.trace_error(format!("{}: {error}", probe.requested))?,
+            (None, true, _) => out.success(format!(
+                "{}: accepted ({})",
+                probe.requested,
+                probe.server.as_deref().unwrap_or("unknown server")
+            ))?,
+            (None, false, answered) => out.trace_warn(format!(
+                "{}: answered with {}",
+                probe.requested,
+                answered.as_deref().unwrap_or("nothing")
+            ))?,
+        }
+    }
+    Ok(())
+}
+
+fn failed_probe(requested: &str, error: String) -> VersionProbe {
+    VersionProbe {
+        requested: requested.to_string(),
+        accepted: false,
+        answered: None,
+        server: None,
+        error: Some(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_probe_from_reply() {
+        let result = Reply::Result(json!({
+            "protocolVersion": "2025-03-26",
+            "serverInfo": {"name": "test", "version": "1.0"},
+            "capabilities": {}
+        }));
+
+        let probe = VersionProbe::from_reply("2025-03-26", &result);
+        assert!(probe.accepted);
+        assert_eq!(probe.server.as_deref(), Some("test v1.0"));
+        assert_eq!(probe.error, None);
+
+        let probe = VersionProbe::from_reply("2024-11-05", &result);
+        assert!(!probe.accepted);
+        assert_eq!(probe.answered.as_deref(), Some("2025-03-26"));
+
+        let error = Reply::Error {
+            code: -32602,
+            message: "Unsupported protocol version".to_string(),
+            data: None,
+        };
+        let probe = VersionProbe::from_reply("2024-11-05", &error);
+        assert!(!probe.accepted);
+        assert_eq!(
+            probe.error.as_deref(),
+            Some("JSON-RPC error -32602: Unsupported protocol version")
+        );
+    }
+}
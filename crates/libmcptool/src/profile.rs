@@ -0,0 +1,281 @@
+//! Named targets and their connection settings, kept in `config.toml` in the
+//! configuration directory and used as `profile://name` or `@name`.
+
+mod add;
+mod list;
+mod remove;
+mod show;
+
+pub use add::{AddCommandArgs, add_command};
+pub use list::list_command;
+pub use remove::remove_command;
+pub use show::show_command;
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Error, Result,
+    ctx::Ctx,
+    http::HttpOptions,
+    storage::{AuthKind, StoredAuth},
+    target::{ProcessEnv, Target},
+};
+
+/// Name of the configuration file in the configuration directory
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// How command results are shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputMode {
+    Text,
+    Json,
+}
+
+/// A named target and the settings used to connect to it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    /// The target, in any form except another profile
+    pub target: String,
+    /// Environment variables for cmd:// servers
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Headers sent to HTTP targets
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// Static authentication entry whose token and headers are sent to the
+    /// target
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<String>,
+    /// Seconds to wait for the server, in place of the default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// Output mode for commands run against this profile, unless --json is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<OutputMode>,
+}
+
+impl Profile {
+    /// The profile's target, and a copy of the context with its settings
+    /// applied. Options given on the command line take precedence.
+    pub fn resolve(&self, ctx: &Ctx) -> Result<(Ctx, Target)> {
+        let target = Target::parse(&self.target)?;
+        if let Target::Profile { name } = &target {
+            return Err(Error::Format(format!(
+                "A profile can't refer to another profile (profile://{name})"
+            )));
+        }
+
+        let mut http = HttpOptions {
+            headers: self.headers.clone().into_iter().collect(),
+            ..Default::default()
+        };
+        if let Some(auth) = &self.auth {
+            http = static_auth(ctx, auth)?.http_options().merged(&http);
+        }
+        let env = ProcessEnv {
+            vars: self.env.clone().into_iter().collect(),
+            ..Default::default()
+        };
+
+        let mut ctx = ctx.clone();
+        ctx.connect_options.http = http.merged(&ctx.connect_options.http);
+        ctx.connect_options.process_env = env.merged(&ctx.connect_options.process_env);
+        Ok((ctx, target))
+    }
+}
+
+/// Look up an authentication entry for a profile. Only static entries can be
+/// used: OAuth tokens expire, and are refreshed only when connecting to the
+/// entry itself as an `auth://` target.
+pub fn static_auth(ctx: &Ctx, name: &str) -> Result<StoredAuth> {
+    let auth = ctx.storage()?.get_auth(name)?;
+    if auth.kind != AuthKind::Static {
+        return Err(Error::Other(format!(
+            "Auth entry '{name}' uses OAuth, so it can't be attached to a profile. \
+             Use auth://{name} as the profile's target instead"
+        )));
+    }
+    Ok(auth)
+}
+
+/// The contents of `config.toml`
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Config {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Profile>,
+    /// Any other settings, kept as they are
+    #[serde(flatten)]
+    pub other: toml::Table,
+}
+
+impl Config {
+    /// Load the configuration, which is empty if the file doesn't exist
+    pub fn load(config_dir: &Path) -> Result<Self> {
+        let path = config_dir.join(CONFIG_FILE);
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        let contents = std::fs::read_to_string(&path)?;
+        toml::from_str(&contents)
+            .map_err(|e| Error::Format(format!("Invalid {}: {e}", path.display())))
+    }
+
+    pub fn save(&self, config_dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(config_dir)?;
+        let contents = toml::to_string_pretty(self)
+            .map_err(|e| Error::Internal(format!("Failed to write configuration: {e}")))?;
+        std::fs::write(config_dir.join(CONFIG_FILE), contents)?;
+        Ok(())
+    }
+
+    pub fn profile(&self, name: &str) -> Result<&Profile> {
+        self.profiles
+            .get(name)
+            .ok_or_else(|| Error::Other(format!("Profile '{name}' not found")))
+    }
+}
+
+/// Validates that a profile name contains only alphanumeric characters and underscores
+pub fn validate_profile_name(name: &str) -> Result<()> {
+    if name.is_empty() {
+        return Err(Error::Format("Profile name cannot be empty".to_string()));
+    }
+
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(Error::Format(format!(
+            "Profile name '{name}' is invalid. Names can only contain letters, numbers, and underscores (a-zA-Z0-9_)"
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_ctx(dir: &Path) -> Ctx {
+        Ctx::new(dir.to_path_buf(), None, false, false, false, 80).unwrap()
+    }
+
+    #[test]
+    fn test_config_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(CONFIG_FILE),
+            r#"
+theme = "dark"
+
+[profiles.fs]
+target = "cmd://mcp-server-fs /tmp"
+env = { DEBUG = "1" }
+timeout = 30
+output = "json"
+"#,
+        )
+        .unwrap();
+
+        let mut config = Config::load(dir.path()).unwrap();
+        let fs = config.profile("fs").unwrap();
+        assert_eq!(fs.env.get("DEBUG").map(String::as_str), Some("1"));
+        assert_eq!(fs.timeout, Some(30));
+        assert_eq!(fs.output, Some(OutputMode::Json));
+        assert!(config.profile("missing").is_err());
+
+        config.profiles.insert(
+            "api".to_string(),
+            Profile {
+                target: "https://api.example.com/mcp".to_string(),
+                ..Default::default()
+            },
+        );
+        config.save(dir.path()).unwrap();
+        let config = Config::load(dir.path()).unwrap();
+        assert_eq!(config.profiles.len(), 2);
+        assert_eq!(config.other.get("theme").unwrap().as_str(), Some("dark"));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut ctx = test_ctx(dir.path());
+        ctx.connect_options.http.headers = vec![("X-Team".to_string(), "cli".to_string())];
+        ctx.connect_options.process_env.vars = vec![("DEBUG".to_string(), "0".to_string())];
+
+        let profile = Profile {
+            target: "cmd://server".to_string(),
+            env: BTreeMap::from([("DEBUG".to_string(), "1".to_string())]),
+            headers: BTreeMap::from([("X-Team".to_string(), "profile".to_string())]),
+            ..Default::default()
+        };
+        let (resolved, target) = profile.resolve(&ctx).unwrap();
+        assert!(matches!(target, Target::Stdio { .. }));
+        // The command line's values are applied last, so they win
+        assert_eq!(
+            resolved.connect_options.process_env.vars,
+            vec![
+                ("DEBUG".to_string(), "1".to_string()),
+                ("DEBUG".to_string(), "0".to_string()),
+            ]
+        );
+        assert_eq!(
+            resolved.connect_options.http.headers.last().unwrap().1,
+            "cli"
+        );
+
+        let nested = Profile {
+            target: "@other".to_string(),
+            ..Default::default()
+        };
+        assert!(nested.resolve(&ctx).is_err());
+    }
+
+    #[test]
+    fn test_resolve_auth() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = test_ctx(dir.path());
+        let storage = ctx.storage().unwrap();
+        let mut auth = StoredAuth {
+            name: "key".to_string(),
+            server_url: "https://api.example.com/mcp".to_string(),
+            client_id: String::new(),
+            client_secret: None,
+            access_token: Some("secret".to_string()),
+            refresh_token: None,
+            expires_at: None,
+            auth_url: String::new(),
+            token_url: String::new(),
+            redirect_url: None,
+            scopes: vec![],
+            kind: AuthKind::Static,
+            headers: vec![],
+            resource: None,
+        };
+        storage.store_auth(&auth).unwrap();
+        auth.name = "oauth".to_string();
+        auth.kind = AuthKind::OAuth;
+        storage.store_auth(&auth).unwrap();
+
+        let profile = Profile {
+            target: "https://api.example.com/mcp".to_string(),
+            auth: Some("key".to_string()),
+            ..Default::default()
+        };
+        let (resolved, _) = profile.resolve(&ctx).unwrap();
+        assert_eq!(
+            resolved.connect_options.http.bearer.as_deref(),
+            Some("secret")
+        );
+
+        let profile = Profile {
+            auth: Some("oauth".to_string()),
+            ..profile
+        };
+        let error = profile.resolve(&ctx).unwrap_err().to_string();
+        assert!(error.contains("auth://oauth"));
+    }
+}
@@ -0,0 +1,85 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    Error, Result,
+    ctx::Ctx,
+    profile::{Config, OutputMode, Profile, static_auth, validate_profile_name},
+    target::Target,
+};
+
+pub struct AddCommandArgs {
+    pub name: String,
+    pub target: String,
+    pub auth: Option<String>,
+    pub timeout: Option<u64>,
+    pub output: Option<OutputMode>,
+    /// Environment variables for cmd:// servers, from --env
+    pub env: Vec<(String, String)>,
+    /// Headers for HTTP targets, from --header
+    pub headers: Vec<(String, String)>,
+}
+
+pub async fn add_command(ctx: &Ctx, args: AddCommandArgs) -> Result<()> {
+    let name = args.name;
+    validate_profile_name(&name)?;
+
+    let mut config = Config::load(&ctx.config_path)?;
+    if config.profiles.contains_key(&name) {
+        return Err(Error::Other(format!("Profile '{name}' already exists")));
+    }
+
+    if let Target::Profile { .. } = Target::parse(&args.target)? {
+        return Err(Error::Format(
+            "A profile can't refer to another profile".to_string(),
+        ));
+    }
+    if let Some(auth) = &args.auth {
+        static_auth(ctx, auth)?;
+    }
+    let unstored = unstored_options(ctx);
+    if !unstored.is_empty() {
+        return Err(Error::Other(format!(
+            "Profiles can't store {}. Give them each time the profile is used, or use --auth for a bearer token",
+            unstored.join(", ")
+        )));
+    }
+
+    let profile = Profile {
+        target: args.target,
+        env: args.env.into_iter().collect::<BTreeMap<_, _>>(),
+        headers: args.headers.into_iter().collect::<BTreeMap<_, _>>(),
+        auth: args.auth,
+        timeout: args.timeout,
+        output: args.output,
+    };
+    config.profiles.insert(name.clone(), profile);
+    config.save(&ctx.config_path)?;
+
+    ctx.output.trace_success(format!(
+        "Profile '{name}' added. Use it as @{name} or profile://{name}"
+    ))?;
+    Ok(())
+}
+
+/// Global options given with `profile add` that a profile has no place for
+fn unstored_options(ctx: &Ctx) -> Vec<&'static str> {
+    let options = &ctx.connect_options;
+    let tls = &options.http.tls;
+    let process_env = &options.process_env;
+    [
+        ("--bearer", options.http.bearer.is_some()),
+        ("--cacert", tls.cacert.is_some()),
+        ("--cert", tls.cert.is_some()),
+        ("--key", tls.key.is_some()),
+        ("--insecure", tls.insecure),
+        ("--http-proxy", options.http.proxy.http.is_some()),
+        ("--socks-proxy", options.http.proxy.socks.is_some()),
+        ("--env-file", !process_env.env_files.is_empty()),
+        ("--cwd", process_env.cwd.is_some()),
+        ("--clear-env", process_env.clear),
+        ("--protocol-version", options.protocol_version.is_some()),
+    ]
+    .into_iter()
+    .filter_map(|(flag, given)| given.then_some(flag))
+    .collect()
+}
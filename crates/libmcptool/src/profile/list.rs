@@ -0,0 +1,73 @@
+use serde_json::json;
+
+use crate::{Result, ctx::Ctx, profile::Config};
+
+pub async fn list_command(ctx: &Ctx) -> Result<()> {
+    let config = Config::load(&ctx.config_path)?;
+
+    if ctx.output.json {
+        let profiles: Vec<_> = config
+            .profiles
+            .iter()
+            .map(|(name, profile)| json!({"name": name, "target": profile.target, "auth": profile.auth}))
+            .collect();
+        return ctx.output.json_value(&profiles);
+    }
+
+    if config.profiles.is_empty() {
+        ctx.output.text("No profiles found.")?;
+        ctx.output.text("")?;
+        ctx.output
+            .text("Use 'mcptool profile add <name> <target>' to add a new profile.")?;
+        return Ok(());
+    }
+
+    ctx.output
+        .h1(format!("Profiles ({}):", config.profiles.len()))?;
+    ctx.output.text("")?;
+
+    let max_name_len = config
+        .profiles
+        .keys()
+        .map(|name| name.len())
+        .max()
+        .unwrap_or(4)
+        .max(4);
+    let max_target_len = config
+        .profiles
+        .values()
+        .map(|profile| profile.target.len())
+        .max()
+        .unwrap_or(6)
+        .max(6);
+
+    ctx.output.text(format!(
+        "{:<width_name$}  {:<width_target$}  {}",
+        "Name",
+        "Target",
+        "Auth",
+        width_name = max_name_len,
+        width_target = max_target_len,
+    ))?;
+    ctx.output.text(format!(
+        "{:-<width_name$}  {:-<width_target$}  {:-<10}",
+        "",
+        "",
+        "",
+        width_name = max_name_len,
+        width_target = max_target_len,
+    ))?;
+
+    for (name, profile) in &config.profiles {
+        ctx.output.text(format!(
+            "{:<width_name$}  {:<width_target$}  {}",
+            name,
+            profile.target,
+            profile.auth.as_deref().unwrap_or("-"),
+            width_name = max_name_len,
+            width_target = max_target_len,
+        ))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use rustyline::DefaultEditor;
+
+use crate::{Result, ctx::Ctx, profile::Config};
+
+pub async fn remove_command(ctx: &Ctx, name: String) -> Result<()> {
+    let mut config = Config::load(&ctx.config_path)?;
+    let profile = config.profile(&name)?;
+
+    // Confirm removal
+    ctx.output
+        .trace_warn(format!("About to remove profile '{name}'"))?;
+    ctx.output.text(format!("  Target: {}", profile.target))?;
+    ctx.output.text("")?;
+
+    let mut rl = DefaultEditor::new()?;
+    let confirmation = rl.readline("Are you sure you want to remove this profile? (y/N): ")?;
+
+    if confirmation.trim().to_lowercase() != "y" {
+        ctx.output.text("Removal cancelled.")?;
+        return Ok(());
+    }
+
+    config.profiles.remove(&name);
+    config.save(&ctx.config_path)?;
+
+    ctx.output
+        .trace_success(format!("Profile '{name}' removed successfully."))?;
+
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use serde_json::json;
+
+use crate::{Result, ctx::Ctx, profile::Config};
+
+pub async fn show_command(ctx: &Ctx, name: String) -> Result<()> {
+    let config = Config::load(&ctx.config_path)?;
+    let profile = config.profile(&name)?;
+
+    if ctx.output.json {
+        let mut value = serde_json::to_value(profile)?;
+        value["name"] = json!(name);
+        return ctx.output.json_value(&value);
+    }
+
+    ctx.output.h1(format!("Profile: {name}"))?;
+    ctx.output.kv("Target", &profile.target)?;
+    if let Some(auth) = &profile.auth {
+        ctx.output.kv("Auth", auth)?;
+    }
+    if let Some(timeout) = profile.timeout {
+        ctx.output.kv("Timeout", format!("{timeout}s"))?;
+    }
+    if let Some(output) = profile.output {
+        ctx.output
+            .kv("Output", format!("{output:?}").to_lowercase())?;
+    }
+    if !profile.env.is_empty() {
+        ctx.output.text("Environment:")?;
+        for (key, value) in &profile.env {
+            ctx.output.indent().kv(key, value)?;
+        }
+    }
+    if !profile.headers.is_empty() {
+        ctx.output.text("Headers:")?;
+        for (name, value) in &profile.headers {
+            ctx.output.indent().kv(name, value)?;
+        }
+    }
+
+    Ok(())
+}
@@ -4,11 +4,15 @@ use chrono::Utc;
 use tokio::{
     fs::OpenOptions,
     io::{self, AsyncReadExt, AsyncWriteExt},
-    net::TcpStream,
     process::Command,
 };
 
-use crate::{Error, Result, target::Target};
+use crate::{
+    Error, Result, egress,
+    http::{self, HttpOptions},
+    target::Target,
+    transport,
+};
 
 async fn log_traffic(log_writer: &mut tokio::fs::File, direction: &str, data: &[u8]) -> Result<()> {
     let timestamp = Utc::now().to_rfc3339();
@@ -49,7 +53,7 @@ where
     Ok(())
 }
 
-pub async fn proxy_command(target: Target, log_file: PathBuf) -> Result<()> {
+pub async fn proxy_command(target: Target, log_file: PathBuf, http: &HttpOptions) -> Result<()> {
     let mut log_writer = Some(
         OpenOptions::new()
             .create(true)
@@ -63,13 +67,13 @@ pub async fn proxy_command(target: Target, log_file: PathBuf) -> Result<()> {
 
     match target {
         Target::Tcp { host, port } => {
-            let addr = format!("{host}:{port}");
-            let target_stream = TcpStream::connect(&addr).await?;
+            let target_stream = egress::connect_tcp(&host, port, &http.proxy).await?;
             proxy_streams(stdin, stdout, target_stream, log_writer.as_mut().unwrap()).await?;
         }
-        Target::Stdio { command, args } => {
+        Target::Stdio { command, args, env } => {
             let mut cmd = Command::new(command);
             cmd.args(args);
+            env.apply(&mut cmd)?;
             cmd.stdin(std::process::Stdio::piped());
             cmd.stdout(std::process::Stdio::piped());
 
@@ -87,13 +91,21 @@ pub async fn proxy_command(target: Target, log_file: PathBuf) -> Result<()> {
             .await?;
         }
         Target::Http { .. } | Target::Https { .. } => {
-            return Err(Error::Other(
-                "HTTP/HTTPS connections are not yet supported for proxy".to_string(),
-            ));
+            let stream = http::connect(&target.to_string(), http).await?;
+            proxy_streams(stdin, stdout, stream, log_writer.as_mut().unwrap()).await?;
+        }
+        Target::Tls { .. }
+        | Target::Sse { .. }
+        | Target::Sses { .. }
+        | Target::Ws { .. }
+        | Target::Wss { .. }
+        | Target::Unix { .. } => {
+            let stream = transport::connect(&target, http).await?;
+            proxy_streams(stdin, stdout, stream, log_writer.as_mut().unwrap()).await?;
         }
-        Target::Auth { .. } => {
+        Target::Auth { .. } | Target::Profile { .. } => {
             return Err(Error::Other(
-                "Auth targets should be resolved to actual targets before calling proxy_command"
+                "Auth and profile targets should be resolved to actual targets before calling proxy_command"
                     .to_string(),
             ));
         }
@@ -0,0 +1,433 @@
+//! A minimal JSON-RPC client that speaks MCP without any protocol logic of its
+//! own. Unlike [`tenx_mcp::Client`], it sends exactly what it is told to, in the
+//! order it is told to, which lets us probe how servers handle unusual or
+//! invalid traffic.
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use serde_json::{Value, json};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    process::{Child, Command},
+};
+
+use crate::{
+    Error, Result,
+    ctx::Ctx,
+    egress,
+    http::{HttpOptions, next_sse_event},
+    profile::Config,
+    target::Target,
+    transport,
+};
+
+/// Protocol revisions known to mcptool, oldest first
+pub const PROTOCOL_VERSIONS: &[&str] = &["2024-11-05", "2025-03-26", "2025-06-18"];
+
+/// The server's answer to a request
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reply {
+    Result(Value),
+    Error {
+        code: i64,
+        message: String,
+        data: Option<Value>,
+    },
+}
+
+impl Reply {
+    fn from_message(message: &Value) -> Reply {
+        match message.get("error") {
+            Some(error) => Reply::Error {
+                code: error.get("code").and_then(Value::as_i64).unwrap_or(0),
+                message: error
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+                data: error.get("data").cloned(),
+            },
+            None => Reply::Result(message.get("result").cloned().unwrap_or(Value::Null)),
+        }
+    }
+
+    /// The result, or an error describing the JSON-RPC error
+    pub fn into_result(self) -> Result<Value> {
+        match self {
+            Reply::Result(value) => Ok(value),
+            Reply::Error { code, message, .. } => {
+                Err(Error::Other(format!("JSON-RPC error {code}: {message}")))
+            }
+        }
+    }
+}
+
+type BoxedReader = BufReader<Box<dyn AsyncRead + Unpin + Send>>;
+type BoxedWriter = Box<dyn AsyncWrite + Unpin + Send>;
+
+enum Transport {
+    /// Newline-delimited JSON over a byte stream (stdio, TCP and the
+    /// transports adapted in [`crate::transport`])
+    Lines {
+        reader: BoxedReader,
+        writer: BoxedWriter,
+        _child: Option<Child>,
+    },
+    /// Streamable HTTP
+    Http {
+        client: reqwest::Client,
+        url: String,
+        options: HttpOptions,
+        session_id: Option<String>,
+    },
+}
+
+pub struct RawClient {
+    transport: Transport,
+    next_id: u64,
+    timeout: Duration,
+    /// Protocol version negotiated by the last successful initialize
+    protocol_version: Option<String>,
+    /// Notifications received from the server, in order
+    pub notifications: Vec<Value>,
+    /// Responses that didn't match any outstanding request
+    pub unexpected: Vec<Value>,
+    /// Requests we stopped waiting for, whose responses may still arrive
+    abandoned: HashSet<u64>,
+}
+
+impl RawClient {
+    /// Open a connection to the target without initializing it
+    pub async fn connect(ctx: &Ctx, target: &Target, timeout: Duration) -> Result<Self> {
+        let transport = match target {
+            Target::Tcp { host, port } => {
+                let stream =
+                    egress::connect_tcp(host, *port, &ctx.connect_options.http.proxy).await?;
+                let (reader, writer) = stream.into_split();
+                Transport::Lines {
+                    reader: BufReader::new(Box::new(reader)),
+                    writer: Box::new(writer),
+                    _child: None,
+                }
+            }
+            Target::Stdio { command, args, env } => {
+                let mut cmd = Command::new(command);
+                cmd.args(args)
+                    .stdin(std::process::Stdio::piped())
+                    .stdout(std::process::Stdio::piped())
+                    .stderr(std::process::Stdio::null())
+                    .kill_on_drop(true);
+                env.merged(&ctx.connect_options.process_env)
+                    .apply(&mut cmd)?;
+                let mut child = cmd.spawn().map_err(|e| {
+                    Error::Other(format!("Failed to spawn MCP server process: {e}"))
+                })?;
+                let stdin = child.stdin.take().unwrap();
+                let stdout = child.stdout.take().unwrap();
+                Transport::Lines {
+                    reader: BufReader::new(Box::new(stdout)),
+                    writer: Box::new(stdin),
+                    _child: Some(child),
+                }
+            }
+            Target::Http { .. } | Target::Https { .. } => Transport::Http {
+                client: ctx.connect_options.http.client()?,
+                url: target.to_string(),
+                options: ctx.connect_options.http.clone(),
+                session_id: None,
+            },
+            Target::Tls { .. }
+            | Target::Sse { .. }
+            | Target::Sses { .. }
+            | Target::Ws { .. }
+            | Target::Wss { .. }
+            | Target::Unix { .. } => {
+                let stream = transport::connect(target, &ctx.connect_options.http).await?;
+                let (reader, writer) = tokio::io::split(stream);
+                Transport::Lines {
+                    reader: BufReader::new(Box::new(reader)),
+                    writer: Box::new(writer),
+                    _child: None,
+                }
+            }
+            Target::Profile { name } => {
+                let profile = Config::load(&ctx.config_path)?.profile(name)?.clone();
+                let (ctx, target) = profile.resolve(ctx)?;
+                let timeout = profile.timeout.map(Duration::from_secs).unwrap_or(timeout);
+                return Box::pin(Self::connect(&ctx, &target, timeout)).await;
+            }
+            Target::Auth { name } => {
+                let auth = ctx.storage()?.get_auth(name)?;
+                let mut client = Box::pin(Self::connect(
+                    ctx,
+                    &Target::parse(&auth.server_url)?,
+                    timeout,
+                ))
+                .await?;
+                match &mut client.transport {
+                    Transport::Http { options, .. } => {
+                        *options = auth.http_options().merged(options);
+                    }
+                    Transport::Lines { .. } => {
+                        return Err(Error::Other(
+                            "Authentication is only supported for HTTP/HTTPS targets".to_string(),
+                        ));
+                    }
+                }
+                return Ok(client);
+            }
+        };
+
+        Ok(RawClient {
+            transport,
+            next_id: 1,
+            timeout,
+            protocol_version: None,
+            notifications: vec![],
+            unexpected: vec![],
+            abandoned: HashSet::new(),
+        })
+    }
+
+    /// Send an initialize request for the given protocol version. On success
+    /// the negotiated version is used for subsequent HTTP requests. The
+    /// `notifications/initialized` notification is not sent.
+    pub async fn initialize(&mut self, protocol_version: &str) -> Result<Reply> {
+        let reply = self
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": protocol_version,
+                    "capabilities": {},
+                    "clientInfo": {"name": "mcptool", "version": crate::ctx::VERSION},
+                }),
+            )
+            .await?;
+        if let Reply::Result(result) = &reply {
+            self.protocol_version = result
+                .get("protocolVersion")
+                .and_then(Value::as_str)
+                .map(str::to_string);
+        }
+        Ok(reply)
+    }
+
+    /// Initialize and send `notifications/initialized`, failing if the server
+    /// rejects the initialize request. Returns the initialize result.
+    pub async fn handshake(&mut self, protocol_version: &str) -> Result<Value> {
+        let result = self.initialize(protocol_version).await?.into_result()?;
+        self.notify("notifications/initialized", json!({})).await?;
+        Ok(result)
+    }
+
+    /// Send a request and wait for its response. Messages that arrive in the
+    /// meantime are recorded in `notifications` and `unexpected`. A response
+    /// that arrives after the request timed out is discarded.
+    pub async fn request(&mut self, method: &str, params: Value) -> Result<Reply> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let message = json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params});
+        match tokio::time::timeout(self.timeout, self.exchange(message, Some(id))).await {
+            Ok(reply) => reply.map(|reply| reply.unwrap_or(Reply::Result(Value::Null))),
+            Err(_) => {
+                self.abandoned.insert(id);
+                Err(Error::Other(format!(
+                    "Timed out after {}s waiting for a response to {method}",
+                    self.timeout.as_secs_f64()
+                )))
+            }
+        }
+    }
+
+    /// Send a notification
+    pub async fn notify(&mut self, method: &str, params: Value) -> Result<()> {
+        let message = json!({"jsonrpc": "2.0", "method": method, "params": params});
+        tokio::time::timeout(self.timeout, self.exchange(message, None))
+            .await
+            .map_err(|_| Error::Other(format!("Timed out sending {method}")))??;
+        Ok(())
+    }
+
+    /// Send a message, and if it has an id, read until its response arrives
+    async fn exchange(&mut self, message: Value, id: Option<u64>) -> Result<Option<Reply>> {
+        match &mut self.transport {
+            Transport::Lines { writer, .. } => {
+                let mut line = serde_json::to_string(&message)?;
+                line.push('\n');
+                writer.write_all(line.as_bytes()).await?;
+                writer.flush().await?;
+                let Some(id) = id else {
+                    return Ok(None);
+                };
+                loop {
+                    let message = self.read_line().await?;
+                    if let Some(reply) = self.dispatch(message, id).await? {
+                        return Ok(Some(reply));
+                    }
+                }
+            }
+            Transport::Http { .. } => self.post(message, id).await,
+        }
+    }
+
+    async fn read_line(&mut self) -> Result<Value> {
+        let Transport::Lines { reader, .. } = &mut self.transport else {
+            unreachable!()
+        };
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).await? == 0 {
+                return Err(Error::Other("Server closed the connection".to_string()));
+            }
+            let line = line.trim();
+            if !line.is_empty() {
+                return serde_json::from_str(line)
+                    .map_err(|e| Error::Other(format!("Server sent invalid JSON: {e}: {line}")));
+            }
+        }
+    }
+
+    async fn post(&mut self, message: Value, id: Option<u64>) -> Result<Option<Reply>> {
+        let response = {
+            let Transport::Http {
+                client,
+                url,
+                options,
+                session_id,
+            } = &self.transport
+            else {
+                unreachable!()
+            };
+            let mut request = options.apply(
+                client
+                    .post(url.as_str())
+                    .header("Accept", "application/json, text/event-stream")
+                    .json(&message),
+            );
+            if let Some(session_id) = session_id {
+                request = request.header("Mcp-Session-Id", session_id);
+            }
+            if let Some(version) = &self.protocol_version {
+                request = request.header("MCP-Protocol-Version", version);
+            }
+            request
+                .send()
+                .await
+                .map_err(|e| Error::Other(format!("HTTP request failed: {e}")))?
+        };
+
+        if let Some(new_session) = response
+            .headers()
+            .get("Mcp-Session-Id")
+            .and_then(|v| v.to_str().ok())
+            && let Transport::Http { session_id, .. } = &mut self.transport
+        {
+            *session_id = Some(new_session.to_string());
+        }
+
+        let Some(id) = id else {
+            return self.acknowledged(response).await;
+        };
+        let status = response.status();
+        let is_stream = response
+            .headers()
+            .get("Content-Type")
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/event-stream"));
+
+        if is_stream {
+            let mut response = response;
+            let mut buffer = Vec::new();
+            while let Some(chunk) = response
+                .chunk()
+                .await
+                .map_err(|e| Error::Other(format!("Failed to read event stream: {e}")))?
+            {
+                buffer.extend_from_slice(&chunk);
+                while let Some(event) = next_sse_event(&mut buffer) {
+                    if event.data.is_empty() {
+                        continue;
+                    }
+                    let message: Value = serde_json::from_str(&event.data)?;
+                    if let Some(reply) = self.dispatch(message, id).await? {
+                        return Ok(Some(reply));
+                    }
+                }
+            }
+            return Err(Error::Other(
+                "Event stream ended without a response".to_string(),
+            ));
+        }
+
+        let body = response
+            .text()
+            .await
+            .map_err(|e| Error::Other(format!("Failed to read HTTP response: {e}")))?;
+        let messages = match serde_json::from_str::<Value>(&body) {
+            Ok(Value::Array(messages)) => messages,
+            Ok(message) => vec![message],
+            Err(_) => {
+                return Err(Error::Other(format!(
+                    "HTTP {status} without a JSON-RPC response: {body}"
+                )));
+            }
+        };
+        for message in messages {
+            if let Some(reply) = self.dispatch(message, id).await? {
+                return Ok(Some(reply));
+            }
+        }
+        Err(Error::Other(format!("HTTP {status} without a response")))
+    }
+
+    /// Check the answer to a message that expects no response. Servers reply
+    /// 202 Accepted with no body, so an error status fails, and any JSON-RPC
+    /// they send back is recorded in `unexpected`.
+    async fn acknowledged(&mut self, response: reqwest::Response) -> Result<Option<Reply>> {
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| Error::Other(format!("Failed to read HTTP response: {e}")))?;
+        if !status.is_success() {
+            return Err(Error::Other(format!("HTTP {status}: {body}")));
+        }
+        match serde_json::from_str::<Value>(&body) {
+            Ok(Value::Array(messages)) => self.unexpected.extend(messages),
+            Ok(message) => self.unexpected.push(message),
+            Err(_) => {}
+        }
+        Ok(None)
+    }
+
+    /// Handle one incoming message. Returns the reply if it answers request `id`.
+    async fn dispatch(&mut self, message: Value, id: u64) -> Result<Option<Reply>> {
+        let is_response = message.get("result").is_some() || message.get("error").is_some();
+        match (message.get("id"), message.get("method")) {
+            (Some(message_id), _) if is_response => {
+                if message_id.as_u64() == Some(id) {
+                    return Ok(Some(Reply::from_message(&message)));
+                }
+                if let Some(late) = message_id.as_u64()
+                    && self.abandoned.remove(&late)
+                {
+                    return Ok(None);
+                }
+                self.unexpected.push(message);
+            }
+            (Some(request_id), Some(_)) => {
+                // We advertise no client capabilities, so decline server requests
+                let response = json!({
+                    "jsonrpc": "2.0",
+                    "id": request_id,
+                    "error": {"code": -32601, "message": "Method not found"},
+                });
+                Box::pin(self.exchange(response, None)).await?;
+            }
+            (None, Some(_)) => self.notifications.push(message),
+            _ => self.unexpected.push(message),
+        }
+        Ok(None)
+    }
+}
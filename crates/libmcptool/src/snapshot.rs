@@ -0,0 +1,391 @@
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tenx_mcp::{
+    Client, ClientConn,
+    schema::{InitializeResult, Prompt, Resource, ResourceTemplate, Tool},
+};
+
+use crate::{
+    Error, Result, client,
+    command::{ReplCommandWrapper, execute_mcp_command_with_client},
+    ctx::Ctx,
+    mcp,
+    output::Output,
+    target::Target,
+};
+
+/// A point-in-time capture of everything a server advertises
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// The target the snapshot was taken from
+    pub target: String,
+    pub init: InitializeResult,
+    #[serde(default)]
+    pub tools: Vec<Tool>,
+    #[serde(default)]
+    pub prompts: Vec<Prompt>,
+    #[serde(default)]
+    pub resources: Vec<Resource>,
+    #[serde(default)]
+    pub resource_templates: Vec<ResourceTemplate>,
+    /// Results of the configured calls, replayed when checking
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub calls: Vec<RecordedCall>,
+}
+
+/// A REPL command and the server's response to it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedCall {
+    pub command: String,
+    pub result: Value,
+}
+
+impl Snapshot {
+    /// Capture a snapshot over an existing connection. Lists are only fetched
+    /// for capabilities the server advertises, and always include every page.
+    /// Each entry in `calls` is a REPL command line, such as
+    /// `calltool echo --arg message=hi`.
+    pub async fn capture<C: ClientConn + 'static>(
+        ctx: &Ctx,
+        target: &Target,
+        client: &mut Client<C>,
+        init_result: &InitializeResult,
+        calls: &[String],
+    ) -> Result<Self> {
+        let capabilities = &init_result.capabilities;
+        let mut snapshot = Snapshot {
+            target: target.redacted().to_string(),
+            init: init_result.clone(),
+            tools: vec![],
+            prompts: vec![],
+            resources: vec![],
+            resource_templates: vec![],
+            calls: vec![],
+        };
+
+        if capabilities.tools.is_some() {
+            snapshot.tools = mcp::fetch_tools(client).await?;
+        }
+        if capabilities.prompts.is_some() {
+            snapshot.prompts = mcp::fetch_prompts(client).await?;
+        }
+        if capabilities.resources.is_some() {
+            snapshot.resources = mcp::fetch_resources(client).await?;
+            snapshot.resource_templates = mcp::fetch_resource_templates(client).await?;
+        }
+
+        // Calls run silently; their results end up in the snapshot
+        let quiet_ctx = Ctx {
+            output: ctx.output.clone().with_quiet(true),
+            ..ctx.clone()
+        };
+        for command in calls {
+            let words = shell_words::split(command)
+                .map_err(|e| Error::Format(format!("Failed to parse call '{command}': {e}")))?;
+            let wrapper = ReplCommandWrapper::try_parse_from(words)
+                .map_err(|e| Error::Format(format!("Invalid call '{command}': {e}")))?;
+            let result =
+                execute_mcp_command_with_client(wrapper.command, client, init_result, &quiet_ctx)
+                    .await
+                    .map_err(|e| Error::Other(format!("Call '{command}' failed: {e}")))?;
+            snapshot.calls.push(RecordedCall {
+                command: command.clone(),
+                result,
+            });
+        }
+
+        Ok(snapshot)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        serde_json::from_str(&contents)
+            .map_err(|e| Error::Format(format!("Invalid snapshot {}: {e}", path.display())))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(())
+    }
+
+    /// The snapshot as JSON, with lists keyed by name or URI so that
+    /// reordering isn't reported as a difference and additions or removals
+    /// are attributed to the right item. The source target is omitted.
+    pub fn normalized(&self) -> Result<Value> {
+        let mut value = Map::new();
+        value.insert("init".to_string(), serde_json::to_value(&self.init)?);
+        value.insert(
+            "tools".to_string(),
+            keyed(&self.tools, |tool| tool.name.clone())?,
+        );
+        value.insert(
+            "prompts".to_string(),
+            keyed(&self.prompts, |prompt| prompt.name.clone())?,
+        );
+        value.insert(
+            "resources".to_string(),
+            keyed(&self.resources, |resource| resource.uri.clone())?,
+        );
+        value.insert(
+            "resource_templates".to_string(),
+            keyed(&self.resource_templates, |template| {
+                template.uri_template.clone()
+            })?,
+        );
+        let calls = self
+            .calls
+            .iter()
+            .map(|call| (call.command.clone(), call.result.clone()))
+            .collect();
+        value.insert("calls".to_string(), Value::Object(calls));
+        Ok(Value::Object(value))
+    }
+}
+
+fn keyed<T: Serialize>(items: &[T], key: impl Fn(&T) -> String) -> Result<Value> {
+    let mut map = Map::new();
+    for item in items {
+        map.insert(key(item), serde_json::to_value(item)?);
+    }
+    Ok(Value::Object(map))
+}
+
+/// A single difference between two JSON documents
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Difference {
+    /// Dotted path to the differing value, e.g. `tools.echo.inputSchema`
+    pub path: String,
+    #[serde(flatten)]
+    pub change: Change,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Change {
+    Added { actual: Value },
+    Removed { expected: Value },
+    Changed { expected: Value, actual: Value },
+}
+
+/// Compare two JSON documents, returning every difference in document order.
+/// Objects are compared by key and arrays by index.
+pub fn diff_values(expected: &Value, actual: &Value) -> Vec<Difference> {
+    let mut differences = vec![];
+    diff_at("", expected, actual, &mut differences);
+    differences
+}
+
+fn diff_at(path: &str, expected: &Value, actual: &Value, out: &mut Vec<Difference>) {
+    let child = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{path}.{key}")
+        }
+    };
+
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            for (key, expected) in expected {
+                match actual.get(key) {
+                    Some(actual) => diff_at(&child(key), expected, actual, out),
+                    None => out.push(Difference {
+                        path: child(key),
+                        change: Change::Removed {
+                            expected: expected.clone(),
+                        },
+                    }),
+                }
+            }
+            for (key, actual) in actual {
+                if !expected.contains_key(key) {
+                    out.push(Difference {
+                        path: child(key),
+                        change: Change::Added {
+                            actual: actual.clone(),
+                        },
+                    });
+                }
+            }
+        }
+        (Value::Array(expected), Value::Array(actual)) => {
+            for idx in 0..expected.len().max(actual.len()) {
+                let path = format!("{path}[{idx}]");
+                match (expected.get(idx), actual.get(idx)) {
+                    (Some(expected), Some(actual)) => diff_at(&path, expected, actual, out),
+                    (Some(expected), None) => out.push(Difference {
+                        path,
+                        change: Change::Removed {
+                            expected: expected.clone(),
+                        },
+                    }),
+                    (None, Some(actual)) => out.push(Difference {
+                        path,
+                        change: Change::Added {
+                            actual: actual.clone(),
+                        },
+                    }),
+                    (None, None) => unreachable!(),
+                }
+            }
+        }
+        (expected, actual) if expected != actual => out.push(Difference {
+            path: path.to_string(),
+            change: Change::Changed {
+                expected: expected.clone(),
+                actual: actual.clone(),
+            },
+        }),
+        _ => {}
+    }
+}
+
+/// Display differences in either JSON or formatted text
+pub fn display_differences(output: &Output, differences: &[Difference]) -> Result<()> {
+    if output.json {
+        return output.json_value(&differences);
+    }
+    for difference in differences {
+        let path = &difference.path;
+        match &difference.change {
+            Change::Added { actual } => output.text(format!("+ {path}: {actual}"))?,
+            Change::Removed { expected } => output.text(format!("- {path}: {expected}"))?,
+            Change::Changed { expected, actual } => {
+                output.text(format!("~ {path}: {expected} -> {actual}"))?
+            }
+        }
+    }
+    Ok(())
+}
+
+pub struct SnapshotCommandArgs {
+    pub target: String,
+    pub out: Option<PathBuf>,
+    pub check: Option<PathBuf>,
+    pub calls: Vec<String>,
+}
+
+pub async fn snapshot_command(ctx: &Ctx, args: SnapshotCommandArgs) -> Result<()> {
+    let target = Target::parse(&args.target)?;
+    let saved = args.check.as_deref().map(Snapshot::load).transpose()?;
+
+    // When checking, replay the calls recorded in the saved snapshot
+    let calls: Vec<String> = match &saved {
+        Some(saved) => saved.calls.iter().map(|c| c.command.clone()).collect(),
+        None => args.calls,
+    };
+
+    let (mut client, init_result) = client::get_client(ctx, &target).await?;
+    let snapshot = Snapshot::capture(ctx, &target, &mut client, &init_result, &calls).await?;
+
+    match (saved, &args.out) {
+        (Some(saved), _) => {
+            let differences = diff_values(&saved.normalized()?, &snapshot.normalized()?);
+            if differences.is_empty() {
+                if ctx.output.json {
+                    ctx.output.json_value(&differences)?;
+                } else {
+                    ctx.output.trace_success(format!(
+                        "{} matches snapshot {}",
+                        target.redacted(),
+                        saved.target
+                    ))?;
+                }
+                return Ok(());
+            }
+            ctx.output.h1(format!(
+                "{} difference(s) between snapshot and {}",
+                differences.len(),
+                target.redacted()
+            ))?;
+            display_differences(&ctx.output, &differences)?;
+            Err(Error::Other(format!(
+                "{} differs from snapshot in {} place(s)",
+                target.redacted(),
+                differences.len()
+            )))
+        }
+        (None, Some(path)) => {
+            snapshot.save(path)?;
+            ctx.output.trace_success(format!(
+                "Wrote snapshot of {} to {}",
+                target.redacted(),
+                path.display()
+            ))?;
+            Ok(())
+        }
+        (None, None) => ctx.output.json_value(&snapshot),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_diff_values() {
+        let expected = json!({
+            "tools": {
+                "echo": {"inputSchema": {"required": ["message"]}},
+                "gone": {}
+            },
+            "version": "1.0"
+        });
+        let actual = json!({
+            "tools": {
+                "echo": {"inputSchema": {"required": ["message", "count"]}},
+                "new": {}
+            },
+            "version": "1.1"
+        });
+
+        assert_eq!(
+            diff_values(&expected, &actual),
+            vec![
+                Difference {
+                    path: "tools.echo.inputSchema.required[1]".to_string(),
+                    change: Change::Added {
+                        actual: json!("count")
+                    },
+                },
+                Difference {
+                    path: "tools.gone".to_string(),
+                    change: Change::Removed {
+                        expected: json!({})
+                    },
+                },
+                Difference {
+                    path: "tools.new".to_string(),
+                    change: Change::Added { actual: json!({}) },
+                },
+                Difference {
+                    path: "version".to_string(),
+                    change: Change::Changed {
+                        expected: json!("1.0"),
+                        actual: json!("1.1")
+                    },
+                },
+            ]
+        );
+        assert!(diff_values(&expected, &expected).is_empty());
+    }
+
+    #[test]
+    fn test_difference_json() {
+        let difference = Difference {
+            path: "tools.echo".to_string(),
+            change: Change::Changed {
+                expected: json!(1),
+                actual: json!(2),
+            },
+        };
+        assert_eq!(
+            serde_json::to_value(&difference).unwrap(),
+            json!({"path": "tools.echo", "kind": "changed", "expected": 1, "actual": 2})
+        );
+    }
+}
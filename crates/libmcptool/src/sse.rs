@@ -0,0 +1,285 @@
+//! Client side of the legacy HTTP+SSE transport from protocol revision
+//! 2024-11-05.
+//!
+//! The client opens an event stream with a GET request. The server's first
+//! event, `endpoint`, gives the URL that messages are POSTed to, and its
+//! responses and notifications arrive as `message` events on the stream. This
+//! module adapts that to a newline-delimited JSON-RPC stream.
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, DuplexStream},
+    sync::mpsc,
+};
+
+use crate::{
+    Error, Result,
+    http::{self, HttpOptions, next_sse_event},
+};
+
+/// Path of the event stream when the target doesn't give one
+pub const DEFAULT_PATH: &str = "/sse";
+
+/// Buffer size of the in-memory stream handed back to the caller
+const STREAM_BUFFER: usize = 64 * 1024;
+
+/// The URL of the event stream for an `sse://` (`http`) or `sses://` (`https`)
+/// target
+pub fn stream_url(scheme: &str, host: &str, port: u16, path: &str) -> String {
+    let host = if host.contains(':') && !host.starts_with('[') {
+        format!("[{host}]")
+    } else {
+        host.to_string()
+    };
+    let path = if path.is_empty() { DEFAULT_PATH } else { path };
+    format!("{scheme}://{host}:{port}{path}")
+}
+
+/// Open an event stream and wait for the server to announce its message
+/// endpoint. Returns a stream that carries one JSON-RPC message per line in
+/// each direction.
+pub async fn connect(url: &str, options: &HttpOptions) -> Result<DuplexStream> {
+    let client = options.client()?;
+    let mut response = options
+        .apply(client.get(url).header("Accept", "text/event-stream"))
+        .send()
+        .await
+        .map_err(|e| Error::Other(format!("Failed to open event stream {url}: {e}")))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(Error::Other(format!(
+            "Failed to open event stream {url}: HTTP {status}: {body}"
+        )));
+    }
+
+    // Bytes rather than text, since a chunk can end partway into a character
+    let mut buffer = Vec::new();
+    let endpoint = loop {
+        if let Some(event) = next_sse_event(&mut buffer) {
+            if event.event.as_deref() == Some("endpoint") {
+                break endpoint_url(url, &event.data)?;
+            }
+            continue;
+        }
+        let chunk = response
+            .chunk()
+            .await
+            .map_err(|e| Error::Other(format!("Failed to read event stream: {e}")))?
+            .ok_or_else(|| {
+                Error::Other(
+                    "Event stream closed before the server announced its endpoint".to_string(),
+                )
+            })?;
+        buffer.extend_from_slice(&chunk);
+    };
+
+    let (local, remote) = tokio::io::duplex(STREAM_BUFFER);
+    let (reader, mut writer) = tokio::io::split(remote);
+    // Errors for requests that couldn't be posted, returned on the stream
+    let (errors, mut error_receiver) = mpsc::unbounded_channel::<String>();
+
+    // Events to the caller. Ends when the server closes the stream or the
+    // caller drops its end.
+    tokio::spawn(async move {
+        loop {
+            while let Some(event) = next_sse_event(&mut buffer) {
+                let is_message = matches!(event.event.as_deref(), None | Some("message"));
+                if is_message
+                    && !event.data.is_empty()
+                    && writer
+                        .write_all(format!("{}\n", event.data).as_bytes())
+                        .await
+                        .is_err()
+                {
+                    return;
+                }
+            }
+            tokio::select! {
+                chunk = response.chunk() => match chunk {
+                    Ok(Some(chunk)) => buffer.extend_from_slice(&chunk),
+                    _ => return,
+                },
+                Some(message) = error_receiver.recv() => {
+                    if writer.write_all(format!("{message}\n").as_bytes()).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    });
+
+    // Messages from the caller, posted in order. The server only
+    // acknowledges them here; responses arrive on the event stream.
+    let options = options.clone();
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Err(e) = post(&client, &endpoint, &options, &line).await
+                && let Some(response) = http::error_response(&line, &e)
+            {
+                let _ = errors.send(response);
+            }
+        }
+    });
+
+    Ok(local)
+}
+
+/// Resolve the endpoint announced by the server, which is usually relative to
+/// the event stream. Endpoints on another origin are refused, so that a server
+/// can't have messages and credentials sent elsewhere.
+fn endpoint_url(stream_url: &str, announced: &str) -> Result<String> {
+    let base = url::Url::parse(stream_url)
+        .map_err(|e| Error::Format(format!("Invalid URL {stream_url}: {e}")))?;
+    let endpoint = base.join(announced.trim()).map_err(|e| {
+        Error::Other(format!(
+            "Server announced an invalid endpoint '{announced}': {e}"
+        ))
+    })?;
+    if endpoint.origin() != base.origin() {
+        return Err(Error::Other(format!(
+            "Server announced an endpoint on another origin: {endpoint}"
+        )));
+    }
+    Ok(endpoint.to_string())
+}
+
+async fn post(
+    client: &reqwest::Client,
+    endpoint: &str,
+    options: &HttpOptions,
+    line: &str,
+) -> Result<()> {
+    let response = options
+        .apply(
+            client
+                .post(endpoint)
+                .header("Content-Type", "application/json")
+                .body(line.to_string()),
+        )
+        .send()
+        .await
+        .map_err(|e| Error::Other(format!("HTTP request failed: {e}")))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(Error::Other(format!("HTTP {status}: {body}")));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_url() {
+        assert_eq!(
+            stream_url("http", "localhost", 3000, ""),
+            "http://localhost:3000/sse"
+        );
+        assert_eq!(
+            stream_url("http", "::1", 80, "/v1/events"),
+            "http://[::1]:80/v1/events"
+        );
+        assert_eq!(
+            stream_url("https", "example.com", 443, "/sse"),
+            "https://example.com:443/sse"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_connect() {
+        use tokio::io::AsyncReadExt;
+        use tokio::net::TcpListener;
+
+        // A server that answers each posted message on the event stream
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (events, received) = mpsc::unbounded_channel::<String>();
+            let mut received = Some(received);
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let (reader, mut writer) = stream.into_split();
+                let mut reader = BufReader::new(reader);
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).await.unwrap();
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).await.unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':')
+                        && name.eq_ignore_ascii_case("content-length")
+                    {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+                if request_line.starts_with("GET /sse ") {
+                    let mut received = received.take().unwrap();
+                    tokio::spawn(async move {
+                        writer
+                            .write_all(
+                                b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\r\n\
+                                  event: endpoint\r\ndata: /messages?session=1\r\n\r\n",
+                            )
+                            .await
+                            .unwrap();
+                        while let Some(message) = received.recv().await {
+                            let event = format!("event: message\ndata: {message}\n\n");
+                            writer.write_all(event.as_bytes()).await.unwrap();
+                        }
+                    });
+                } else {
+                    assert!(request_line.starts_with("POST /messages?session=1 "));
+                    let mut body = vec![0; content_length];
+                    reader.read_exact(&mut body).await.unwrap();
+                    let request: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                    let response =
+                        serde_json::json!({"jsonrpc": "2.0", "id": request["id"], "result": {}});
+                    events.send(response.to_string()).unwrap();
+                    writer
+                        .write_all(b"HTTP/1.1 202 Accepted\r\nContent-Length: 0\r\n\r\n")
+                        .await
+                        .unwrap();
+                }
+            }
+        });
+
+        let url = stream_url("http", "127.0.0.1", port, "");
+        let stream = connect(&url, &HttpOptions::default()).await.unwrap();
+        let (reader, mut writer) = tokio::io::split(stream);
+        writer
+            .write_all(b"{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"ping\"}\n")
+            .await
+            .unwrap();
+        let mut line = String::new();
+        BufReader::new(reader).read_line(&mut line).await.unwrap();
+        assert_eq!(line, "{\"id\":1,\"jsonrpc\":\"2.0\",\"result\":{}}\n");
+    }
+
+    #[test]
+    fn test_endpoint_url() {
+        assert_eq!(
+            endpoint_url("http://localhost:3000/sse", "/messages?sessionId=abc").unwrap(),
+            "http://localhost:3000/messages?sessionId=abc"
+        );
+        assert_eq!(
+            endpoint_url("http://localhost:3000/v1/sse", "messages").unwrap(),
+            "http://localhost:3000/v1/messages"
+        );
+        assert_eq!(
+            endpoint_url("http://localhost:3000/sse", "http://localhost:3000/post").unwrap(),
+            "http://localhost:3000/post"
+        );
+        assert!(endpoint_url("http://localhost:3000/sse", "http://other:1/post").is_err());
+        assert!(endpoint_url("http://localhost:3000/sse", "http://localhost:3001/post").is_err());
+        assert!(endpoint_url("http://localhost:3000/sse", "https://localhost:3000/post").is_err());
+    }
+}
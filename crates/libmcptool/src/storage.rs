@@ -5,6 +5,8 @@ use std::time::SystemTime;
 
 use serde::{Deserialize, Serialize};
 
+use crate::http::HttpOptions;
+
 #[derive(Debug, thiserror::Error)]
 pub enum StorageError {
     #[error("Serialization error: {0}")]
@@ -15,6 +17,17 @@ pub enum StorageError {
     NotFound(String),
 }
 
+/// How an authentication entry authenticates
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthKind {
+    /// Tokens obtained through an OAuth flow
+    #[default]
+    OAuth,
+    /// A pre-issued bearer token and/or fixed headers
+    Static,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StoredAuth {
     pub name: String,
@@ -28,6 +41,26 @@ pub struct StoredAuth {
     pub token_url: String,
     pub redirect_url: Option<String>,
     pub scopes: Vec<String>,
+    #[serde(default)]
+    pub kind: AuthKind,
+    /// Headers sent with every request (static entries only)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<(String, String)>,
+    /// Resource indicator (RFC 8707) sent with token requests (OAuth entries
+    /// only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource: Option<String>,
+}
+
+impl StoredAuth {
+    /// The credentials this entry adds to HTTP requests
+    pub fn http_options(&self) -> HttpOptions {
+        HttpOptions {
+            headers: self.headers.clone(),
+            bearer: self.access_token.clone(),
+            ..Default::default()
+        }
+    }
 }
 
 pub struct TokenStorage {
@@ -131,6 +164,9 @@ mod tests {
             token_url: "https://example.com/token".to_string(),
             redirect_url: Some("http://localhost:8080".to_string()),
             scopes: vec!["read".to_string(), "write".to_string()],
+            kind: AuthKind::OAuth,
+            headers: vec![],
+            resource: None,
         };
 
         // Store
@@ -154,4 +190,18 @@ mod tests {
         let names = storage.list_auth().expect("Failed to list auth");
         assert!(!names.contains(&"test_auth".to_string()));
     }
+
+    #[test]
+    fn test_entries_without_kind_are_oauth() {
+        let json = r#"{
+            "name": "old", "server_url": "https://example.com", "client_id": "id",
+            "client_secret": null, "access_token": "token", "refresh_token": null,
+            "expires_at": null, "auth_url": "https://example.com/auth",
+            "token_url": "https://example.com/token", "redirect_url": null, "scopes": []
+        }"#;
+        let auth: StoredAuth = serde_json::from_str(json).unwrap();
+        assert_eq!(auth.kind, AuthKind::OAuth);
+        assert!(auth.headers.is_empty());
+        assert_eq!(auth.http_options().bearer.as_deref(), Some("token"));
+    }
 }
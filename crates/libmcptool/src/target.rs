@@ -1,28 +1,104 @@
 use std::fmt;
+use std::path::{Path, PathBuf};
 
 use crate::{Error, Result};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Target {
-    Tcp { host: String, port: u16 },
-    Stdio { command: String, args: Vec<String> },
-    Http { host: String, port: u16 },
-    Https { host: String, port: u16 },
-    Auth { name: String },
+    Tcp {
+        host: String,
+        port: u16,
+    },
+    /// Newline-delimited JSON-RPC over TCP wrapped in TLS
+    Tls {
+        host: String,
+        port: u16,
+    },
+    Stdio {
+        command: String,
+        args: Vec<String>,
+        env: ProcessEnv,
+    },
+    Http {
+        host: String,
+        port: u16,
+        /// Path and query string, empty or starting with `/`
+        path: String,
+    },
+    Https {
+        host: String,
+        port: u16,
+        /// Path and query string, empty or starting with `/`
+        path: String,
+    },
+    /// The legacy HTTP+SSE transport from protocol revision 2024-11-05
+    Sse {
+        host: String,
+        port: u16,
+        /// Path and query string of the event stream, empty or starting with `/`
+        path: String,
+    },
+    /// The legacy HTTP+SSE transport over HTTPS
+    Sses {
+        host: String,
+        port: u16,
+        /// Path and query string of the event stream, empty or starting with `/`
+        path: String,
+    },
+    Ws {
+        host: String,
+        port: u16,
+        /// Path and query string, empty or starting with `/`
+        path: String,
+    },
+    Wss {
+        host: String,
+        port: u16,
+        /// Path and query string, empty or starting with `/`
+        path: String,
+    },
+    /// Newline-delimited JSON-RPC over a Unix domain socket
+    Unix {
+        path: PathBuf,
+    },
+    Auth {
+        name: String,
+    },
+    /// A named profile from the configuration file
+    Profile {
+        name: String,
+    },
 }
 
 impl Target {
     pub fn parse(input: &str) -> Result<Self> {
         if let Some(remainder) = input.strip_prefix("tcp://") {
             Self::parse_tcp(remainder)
+        } else if let Some(remainder) = input.strip_prefix("tls://") {
+            Self::parse_tls(remainder)
         } else if let Some(remainder) = input.strip_prefix("cmd://") {
             Self::parse_stdio(remainder)
         } else if let Some(remainder) = input.strip_prefix("https://") {
             Self::parse_https(remainder)
         } else if let Some(remainder) = input.strip_prefix("http://") {
             Self::parse_http(remainder)
+        } else if let Some(remainder) = input.strip_prefix("sse://") {
+            Self::parse_sse(remainder)
+        } else if let Some(remainder) = input.strip_prefix("sses://") {
+            Self::parse_sses(remainder)
+        } else if let Some(remainder) = input.strip_prefix("wss://") {
+            Self::parse_wss(remainder)
+        } else if let Some(remainder) = input.strip_prefix("ws://") {
+            Self::parse_ws(remainder)
+        } else if let Some(remainder) = input.strip_prefix("unix://") {
+            Self::parse_unix(remainder)
         } else if let Some(remainder) = input.strip_prefix("auth://") {
             Self::parse_auth(remainder)
+        } else if let Some(remainder) = input
+            .strip_prefix("profile://")
+            .or_else(|| input.strip_prefix('@'))
+        {
+            Self::parse_profile(remainder)
         } else {
             // Implicit TCP
             Self::parse_tcp(input)
@@ -30,6 +106,17 @@ impl Target {
     }
 
     fn parse_tcp(input: &str) -> Result<Self> {
+        let (host, port) = Self::parse_host_port(input, "TCP")?;
+        Ok(Target::Tcp { host, port })
+    }
+
+    fn parse_tls(input: &str) -> Result<Self> {
+        let (host, port) = Self::parse_host_port(input, "TLS")?;
+        Ok(Target::Tls { host, port })
+    }
+
+    /// Parse the `host:port` of a stream target, where the port is required
+    fn parse_host_port(input: &str, kind: &str) -> Result<(String, u16)> {
         if input.is_empty() {
             return Err(Error::Format("Empty host specification".to_string()));
         }
@@ -44,10 +131,7 @@ impl Target {
             let port = port_str
                 .parse::<u16>()
                 .map_err(|_| Error::Format(format!("Invalid port: {port_str}")))?;
-            return Ok(Target::Tcp {
-                host: "0.0.0.0".to_string(),
-                port,
-            });
+            return Ok(("0.0.0.0".to_string(), port));
         }
 
         // Handle IPv6 addresses in brackets
@@ -57,14 +141,14 @@ impl Target {
                 let remainder = &input[end + 1..];
 
                 if remainder.is_empty() {
-                    return Err(Error::Format(
-                        "Port is required for TCP targets".to_string(),
-                    ));
+                    return Err(Error::Format(format!(
+                        "Port is required for {kind} targets"
+                    )));
                 } else if let Some(port_str) = remainder.strip_prefix(':') {
                     let port = port_str
                         .parse::<u16>()
                         .map_err(|_| Error::Format(format!("Invalid port: {port_str}")))?;
-                    return Ok(Target::Tcp { host, port });
+                    return Ok((host, port));
                 } else {
                     return Err(Error::Format(
                         "Invalid character after IPv6 address".to_string(),
@@ -83,21 +167,21 @@ impl Target {
             // Check if this might be part of an IPv6 address without brackets
             if host.contains(':') {
                 // This is likely an IPv6 address without brackets and no port
-                Err(Error::Format(
-                    "Port is required for TCP targets".to_string(),
-                ))
+                Err(Error::Format(format!(
+                    "Port is required for {kind} targets"
+                )))
             } else if port_str.is_empty() {
                 Err(Error::Format("Empty port specification".to_string()))
             } else {
                 let port = port_str
                     .parse::<u16>()
                     .map_err(|_| Error::Format(format!("Invalid port: {port_str}")))?;
-                Ok(Target::Tcp { host, port })
+                Ok((host, port))
             }
         } else {
-            Err(Error::Format(
-                "Port is required for TCP targets".to_string(),
-            ))
+            Err(Error::Format(format!(
+                "Port is required for {kind} targets"
+            )))
         }
     }
 
@@ -110,28 +194,95 @@ impl Target {
         let parts = shell_words::split(input)
             .map_err(|e| Error::Format(format!("Failed to parse command: {e}")))?;
 
-        if parts.is_empty() {
-            return Err(Error::Format("Empty command after parsing".to_string()));
+        // Leading options and KEY=VALUE assignments set up the environment
+        let mut env = ProcessEnv::default();
+        let mut parts = parts.into_iter().peekable();
+        while let Some(word) = parts.peek() {
+            if let Some(cwd) = word.strip_prefix("--cwd=") {
+                env.cwd = Some(cwd.to_string());
+            } else if let Some(path) = word.strip_prefix("--env-file=") {
+                env.env_files.push(path.to_string());
+            } else if word == "--clear-env" {
+                env.clear = true;
+            } else if let Ok(assignment) = parse_env_assignment(word) {
+                env.vars.push(assignment);
+            } else {
+                break;
+            }
+            parts.next();
         }
 
-        let command = parts[0].clone();
-        let args = parts[1..].to_vec();
+        let Some(command) = parts.next() else {
+            return Err(Error::Format("Empty command after parsing".to_string()));
+        };
+        let args = parts.collect();
 
-        Ok(Target::Stdio { command, args })
+        Ok(Target::Stdio { command, args, env })
     }
 
     fn parse_http(input: &str) -> Result<Self> {
-        Self::parse_http_common(input, 80, |host, port| Target::Http { host, port })
+        Self::parse_http_common(input, 80, |host, port, path| Target::Http {
+            host,
+            port,
+            path,
+        })
     }
 
     fn parse_https(input: &str) -> Result<Self> {
-        Self::parse_http_common(input, 443, |host, port| Target::Https { host, port })
+        Self::parse_http_common(input, 443, |host, port, path| Target::Https {
+            host,
+            port,
+            path,
+        })
+    }
+
+    fn parse_sse(input: &str) -> Result<Self> {
+        Self::parse_http_common(input, 80, |host, port, path| Target::Sse {
+            host,
+            port,
+            path,
+        })
+    }
+
+    fn parse_sses(input: &str) -> Result<Self> {
+        Self::parse_http_common(input, 443, |host, port, path| Target::Sses {
+            host,
+            port,
+            path,
+        })
+    }
+
+    fn parse_ws(input: &str) -> Result<Self> {
+        Self::parse_http_common(input, 80, |host, port, path| Target::Ws {
+            host,
+            port,
+            path,
+        })
+    }
+
+    fn parse_wss(input: &str) -> Result<Self> {
+        Self::parse_http_common(input, 443, |host, port, path| Target::Wss {
+            host,
+            port,
+            path,
+        })
     }
 
     fn parse_http_common<F>(input: &str, default_port: u16, constructor: F) -> Result<Self>
     where
-        F: Fn(String, u16) -> Target,
+        F: Fn(String, u16, String) -> Target,
     {
+        // Split off the path and query string; any fragment is dropped
+        let input = input.split('#').next().unwrap_or_default();
+        let (input, path) = match input.find(['/', '?']) {
+            Some(pos) if input.as_bytes()[pos] == b'?' => {
+                (&input[..pos], format!("/{}", &input[pos..]))
+            }
+            Some(pos) => (&input[..pos], input[pos..].to_string()),
+            None => (input, String::new()),
+        };
+        let constructor = |host, port| constructor(host, port, path.clone());
+
         if input.is_empty() {
             return Err(Error::Format("Empty host specification".to_string()));
         }
@@ -182,6 +333,15 @@ impl Target {
         }
     }
 
+    fn parse_unix(input: &str) -> Result<Self> {
+        if input.is_empty() {
+            return Err(Error::Format("Empty socket path".to_string()));
+        }
+        Ok(Target::Unix {
+            path: PathBuf::from(input),
+        })
+    }
+
     fn parse_auth(input: &str) -> Result<Self> {
         if input.is_empty() {
             return Err(Error::Format("Empty auth name".to_string()));
@@ -194,10 +354,190 @@ impl Target {
             name: input.to_string(),
         })
     }
+
+    fn parse_profile(input: &str) -> Result<Self> {
+        if input.is_empty() {
+            return Err(Error::Format("Empty profile name".to_string()));
+        }
+        crate::profile::validate_profile_name(input)?;
+        Ok(Target::Profile {
+            name: input.to_string(),
+        })
+    }
+}
+
+impl Target {
+    /// Apply process options given on the command line to a stdio target.
+    /// Other targets are returned unchanged.
+    pub fn with_process_env(self, overrides: &ProcessEnv) -> Self {
+        match self {
+            Target::Stdio { command, args, env } => Target::Stdio {
+                command,
+                args,
+                env: env.merged(overrides),
+            },
+            other => other,
+        }
+    }
+}
+
+/// Environment and working directory for a spawned server process
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProcessEnv {
+    /// Variables to set, applied after any env files
+    pub vars: Vec<(String, String)>,
+    /// Files of KEY=VALUE lines to load, in order
+    pub env_files: Vec<String>,
+    pub cwd: Option<String>,
+    /// Start from an empty environment instead of inheriting ours
+    pub clear: bool,
+}
+
+impl ProcessEnv {
+    /// Combine with another set of options, which take precedence
+    pub fn merged(&self, overrides: &ProcessEnv) -> ProcessEnv {
+        ProcessEnv {
+            vars: self.vars.iter().chain(&overrides.vars).cloned().collect(),
+            env_files: self
+                .env_files
+                .iter()
+                .chain(&overrides.env_files)
+                .cloned()
+                .collect(),
+            cwd: overrides.cwd.clone().or_else(|| self.cwd.clone()),
+            clear: self.clear || overrides.clear,
+        }
+    }
+
+    /// Configure a command to run with these options
+    pub fn apply(&self, cmd: &mut tokio::process::Command) -> Result<()> {
+        if self.clear {
+            cmd.env_clear();
+        }
+        for path in &self.env_files {
+            for (key, value) in load_env_file(Path::new(path))? {
+                cmd.env(key, value);
+            }
+        }
+        for (key, value) in &self.vars {
+            cmd.env(key, value);
+        }
+        if let Some(cwd) = &self.cwd {
+            cmd.current_dir(cwd);
+        }
+        Ok(())
+    }
+
+    /// The options as they are written in a cmd:// target, quoted as needed,
+    /// optionally with the values of variables hidden
+    fn words(&self, redact: bool) -> Vec<String> {
+        let quote = |value: &str| shell_words::quote(value).into_owned();
+        let mut words = vec![];
+        if let Some(cwd) = &self.cwd {
+            words.push(format!("--cwd={}", quote(cwd)));
+        }
+        if self.clear {
+            words.push("--clear-env".to_string());
+        }
+        for path in &self.env_files {
+            words.push(format!("--env-file={}", quote(path)));
+        }
+        for (key, value) in &self.vars {
+            if redact {
+                words.push(format!("{key}=***"));
+            } else {
+                words.push(format!("{key}={}", quote(value)));
+            }
+        }
+        words
+    }
+}
+
+/// Parse a `KEY=VALUE` environment assignment
+pub fn parse_env_assignment(input: &str) -> Result<(String, String)> {
+    let (key, value) = input
+        .split_once('=')
+        .ok_or_else(|| Error::Format(format!("Expected KEY=VALUE, got '{input}'")))?;
+    validate_env_name(key)?;
+    Ok((key.to_string(), value.to_string()))
+}
+
+fn validate_env_name(key: &str) -> Result<()> {
+    let mut chars = key.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::Format(format!(
+            "Invalid environment variable name '{key}'"
+        )))
+    }
+}
+
+/// Parse the contents of an env file: `KEY=VALUE` lines, optionally prefixed
+/// with `export`, with `#` comments and optional quotes around values
+pub fn parse_env_file(contents: &str) -> Result<Vec<(String, String)>> {
+    let mut vars = vec![];
+    for (idx, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let lineno = idx + 1;
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| Error::Format(format!("Line {lineno}: expected KEY=VALUE")))?;
+        let key = key.trim();
+        validate_env_name(key).map_err(|e| Error::Format(format!("Line {lineno}: {e}")))?;
+        let value = value.trim();
+        let value = ['"', '\'']
+            .iter()
+            .find_map(|quote| {
+                value
+                    .strip_prefix(*quote)
+                    .and_then(|v| v.strip_suffix(*quote))
+            })
+            .unwrap_or(value);
+        vars.push((key.to_string(), value.to_string()));
+    }
+    Ok(vars)
+}
+
+fn load_env_file(path: &Path) -> Result<Vec<(String, String)>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| Error::Other(format!("Failed to read env file {}: {e}", path.display())))?;
+    parse_env_file(&contents)
+        .map_err(|e| Error::Format(format!("Invalid env file {}: {e}", path.display())))
 }
 
 impl fmt::Display for Target {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, false)
+    }
+}
+
+/// A target as shown to users and stored on disk, with the values of
+/// environment variables and query parameters hidden
+pub struct Redacted<'a>(&'a Target);
+
+impl fmt::Display for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.write(f, true)
+    }
+}
+
+impl Target {
+    /// The target with any secrets it may carry hidden. The full form from
+    /// `Display` is only for parsing back into a target.
+    pub fn redacted(&self) -> Redacted<'_> {
+        Redacted(self)
+    }
+
+    fn write(&self, f: &mut fmt::Formatter<'_>, redact: bool) -> fmt::Result {
         match self {
             Target::Tcp { host, port } => {
                 // Check if host is an IPv6 address (contains colons but not already bracketed)
@@ -207,48 +547,93 @@ impl fmt::Display for Target {
                     write!(f, "tcp://{host}:{port}")
                 }
             }
-            Target::Stdio { command, args } => {
-                if args.is_empty() {
-                    write!(f, "cmd://{command}")
-                } else {
-                    write!(f, "cmd://{} {}", command, shell_words::join(args))
-                }
-            }
-            Target::Http { host, port } => {
-                // Check if host is an IPv6 address
+            Target::Tls { host, port } => {
                 if host.contains(':') && !host.starts_with('[') {
-                    if *port == 80 {
-                        write!(f, "http://[{host}]")
-                    } else {
-                        write!(f, "http://[{host}]:{port}")
-                    }
-                } else if *port == 80 {
-                    write!(f, "http://{host}")
+                    write!(f, "tls://[{host}]:{port}")
                 } else {
-                    write!(f, "http://{host}:{port}")
+                    write!(f, "tls://{host}:{port}")
                 }
             }
-            Target::Https { host, port } => {
-                // Check if host is an IPv6 address
-                if host.contains(':') && !host.starts_with('[') {
-                    if *port == 443 {
-                        write!(f, "https://[{host}]")
-                    } else {
-                        write!(f, "https://[{host}]:{port}")
-                    }
-                } else if *port == 443 {
-                    write!(f, "https://{host}")
+            Target::Stdio { command, args, env } => {
+                write!(f, "cmd://")?;
+                let prefix = env.words(redact);
+                if !prefix.is_empty() {
+                    write!(f, "{} ", prefix.join(" "))?;
+                }
+                if args.is_empty() {
+                    write!(f, "{command}")
                 } else {
-                    write!(f, "https://{host}:{port}")
+                    write!(f, "{} {}", command, shell_words::join(args))
                 }
             }
+            Target::Http { host, port, path } => {
+                write_http(f, "http", host, *port, 80, path, redact)
+            }
+            Target::Https { host, port, path } => {
+                write_http(f, "https", host, *port, 443, path, redact)
+            }
+            Target::Sse { host, port, path } => write_http(f, "sse", host, *port, 80, path, redact),
+            Target::Sses { host, port, path } => {
+                write_http(f, "sses", host, *port, 443, path, redact)
+            }
+            Target::Ws { host, port, path } => write_http(f, "ws", host, *port, 80, path, redact),
+            Target::Wss { host, port, path } => {
+                write_http(f, "wss", host, *port, 443, path, redact)
+            }
+            Target::Unix { path } => {
+                write!(f, "unix://{}", path.display())
+            }
             Target::Auth { name } => {
                 write!(f, "auth://{name}")
             }
+            Target::Profile { name } => {
+                write!(f, "profile://{name}")
+            }
         }
     }
 }
 
+/// Hide the values of the query parameters in a path
+fn redact_query(path: &str) -> String {
+    let Some((path, query)) = path.split_once('?') else {
+        return path.to_string();
+    };
+    let query: Vec<String> = query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((key, _)) => format!("{key}=***"),
+            None => pair.to_string(),
+        })
+        .collect();
+    format!("{path}?{}", query.join("&"))
+}
+
+/// Write a URL-style target, omitting the port if it is the default
+fn write_http(
+    f: &mut fmt::Formatter<'_>,
+    scheme: &str,
+    host: &str,
+    port: u16,
+    default_port: u16,
+    path: &str,
+    redact: bool,
+) -> fmt::Result {
+    // Check if host is an IPv6 address
+    if host.contains(':') && !host.starts_with('[') {
+        write!(f, "{scheme}://[{host}]")?;
+    } else {
+        write!(f, "{scheme}://{host}")?;
+    }
+    if port != default_port {
+        write!(f, ":{port}")?;
+    }
+    if redact {
+        write!(f, "{}", redact_query(path))
+    } else {
+        write!(f, "{path}")
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -357,6 +742,7 @@ mod tests {
                 expected: Ok(Target::Stdio {
                     command: "mcp-server".to_string(),
                     args: vec![],
+                    env: ProcessEnv::default(),
                 }),
                 description: "stdio command without args",
             },
@@ -369,6 +755,7 @@ mod tests {
                         "8080".to_string(),
                         "--verbose".to_string(),
                     ],
+                    env: ProcessEnv::default(),
                 }),
                 description: "stdio command with args",
             },
@@ -382,9 +769,32 @@ mod tests {
                         "--path".to_string(),
                         "/some path/".to_string(),
                     ],
+                    env: ProcessEnv::default(),
                 }),
                 description: "stdio command with quoted args",
             },
+            TestCase {
+                input: "cmd://--cwd=/srv --clear-env API_KEY=secret 'NAME=My Server' ./server --verbose",
+                expected: Ok(Target::Stdio {
+                    command: "./server".to_string(),
+                    args: vec!["--verbose".to_string()],
+                    env: ProcessEnv {
+                        vars: vec![
+                            ("API_KEY".to_string(), "secret".to_string()),
+                            ("NAME".to_string(), "My Server".to_string()),
+                        ],
+                        env_files: vec![],
+                        cwd: Some("/srv".to_string()),
+                        clear: true,
+                    },
+                }),
+                description: "stdio command with environment",
+            },
+            TestCase {
+                input: "cmd://API_KEY=secret",
+                expected: Err(format_err("Empty command after parsing")),
+                description: "stdio environment without command",
+            },
             // Error cases
             TestCase {
                 input: "",
@@ -432,6 +842,7 @@ mod tests {
                 expected: Ok(Target::Http {
                     host: "example.com".to_string(),
                     port: 80,
+                    path: String::new(),
                 }),
                 description: "HTTP with default port",
             },
@@ -440,6 +851,7 @@ mod tests {
                 expected: Ok(Target::Http {
                     host: "example.com".to_string(),
                     port: 8080,
+                    path: String::new(),
                 }),
                 description: "HTTP with custom port",
             },
@@ -448,6 +860,7 @@ mod tests {
                 expected: Ok(Target::Http {
                     host: "::1".to_string(),
                     port: 80,
+                    path: String::new(),
                 }),
                 description: "HTTP with IPv6 default port",
             },
@@ -456,6 +869,7 @@ mod tests {
                 expected: Ok(Target::Http {
                     host: "2001:db8::1".to_string(),
                     port: 8080,
+                    path: String::new(),
                 }),
                 description: "HTTP with IPv6 and custom port",
             },
@@ -464,6 +878,7 @@ mod tests {
                 expected: Ok(Target::Http {
                     host: "::1".to_string(),
                     port: 80,
+                    path: String::new(),
                 }),
                 description: "HTTP with IPv6 no brackets",
             },
@@ -473,6 +888,7 @@ mod tests {
                 expected: Ok(Target::Https {
                     host: "example.com".to_string(),
                     port: 443,
+                    path: String::new(),
                 }),
                 description: "HTTPS with default port",
             },
@@ -481,6 +897,7 @@ mod tests {
                 expected: Ok(Target::Https {
                     host: "example.com".to_string(),
                     port: 8443,
+                    path: String::new(),
                 }),
                 description: "HTTPS with custom port",
             },
@@ -489,6 +906,7 @@ mod tests {
                 expected: Ok(Target::Https {
                     host: "::1".to_string(),
                     port: 443,
+                    path: String::new(),
                 }),
                 description: "HTTPS with IPv6 default port",
             },
@@ -497,9 +915,125 @@ mod tests {
                 expected: Ok(Target::Https {
                     host: "2001:db8::1".to_string(),
                     port: 8443,
+                    path: String::new(),
                 }),
                 description: "HTTPS with IPv6 and custom port",
             },
+            TestCase {
+                input: "https://example.com/v1/mcp?key=abc#frag",
+                expected: Ok(Target::Https {
+                    host: "example.com".to_string(),
+                    port: 443,
+                    path: "/v1/mcp?key=abc".to_string(),
+                }),
+                description: "HTTPS with path and query",
+            },
+            TestCase {
+                input: "http://[::1]:8080/mcp",
+                expected: Ok(Target::Http {
+                    host: "::1".to_string(),
+                    port: 8080,
+                    path: "/mcp".to_string(),
+                }),
+                description: "HTTP with IPv6 and path",
+            },
+            TestCase {
+                input: "http://localhost?debug=1",
+                expected: Ok(Target::Http {
+                    host: "localhost".to_string(),
+                    port: 80,
+                    path: "/?debug=1".to_string(),
+                }),
+                description: "HTTP with query and no path",
+            },
+            TestCase {
+                input: "http:///mcp",
+                expected: Err(format_err("Empty host specification")),
+                description: "HTTP with path and no host",
+            },
+            // SSE tests
+            TestCase {
+                input: "sse://example.com:3000/v1/sse",
+                expected: Ok(Target::Sse {
+                    host: "example.com".to_string(),
+                    port: 3000,
+                    path: "/v1/sse".to_string(),
+                }),
+                description: "SSE with port and path",
+            },
+            TestCase {
+                input: "sse://[::1]",
+                expected: Ok(Target::Sse {
+                    host: "::1".to_string(),
+                    port: 80,
+                    path: String::new(),
+                }),
+                description: "SSE with IPv6 default port",
+            },
+            TestCase {
+                input: "sses://mcp.example.com/sse",
+                expected: Ok(Target::Sses {
+                    host: "mcp.example.com".to_string(),
+                    port: 443,
+                    path: "/sse".to_string(),
+                }),
+                description: "SSE over HTTPS with default port",
+            },
+            // WebSocket tests
+            TestCase {
+                input: "ws://localhost:8080/mcp",
+                expected: Ok(Target::Ws {
+                    host: "localhost".to_string(),
+                    port: 8080,
+                    path: "/mcp".to_string(),
+                }),
+                description: "WebSocket with port and path",
+            },
+            TestCase {
+                input: "wss://example.com",
+                expected: Ok(Target::Wss {
+                    host: "example.com".to_string(),
+                    port: 443,
+                    path: String::new(),
+                }),
+                description: "secure WebSocket with default port",
+            },
+            TestCase {
+                input: "wss://[::1]:8443/ws",
+                expected: Ok(Target::Wss {
+                    host: "::1".to_string(),
+                    port: 8443,
+                    path: "/ws".to_string(),
+                }),
+                description: "secure WebSocket with IPv6 and custom port",
+            },
+            // TLS tests
+            TestCase {
+                input: "tls://example.com:8443",
+                expected: Ok(Target::Tls {
+                    host: "example.com".to_string(),
+                    port: 8443,
+                }),
+                description: "TLS with host and port",
+            },
+            TestCase {
+                input: "tls://example.com",
+                expected: Err(format_err("Port is required for TLS targets")),
+                description: "TLS without port",
+            },
+            // Unix socket tests
+            TestCase {
+                input: "unix:///tmp/mcp.sock",
+                expected: Ok(Target::Unix {
+                    path: PathBuf::from("/tmp/mcp.sock"),
+                }),
+                description: "Unix socket with absolute path",
+            },
+            TestCase {
+                input: "unix://",
+                expected: Err(format_err("Empty socket path")),
+                description: "Unix scheme without path",
+            },
             // Auth tests
             TestCase {
                 input: "auth://myservice",
@@ -548,6 +1082,33 @@ mod tests {
                 expected: Err(format_err("Empty auth name")),
                 description: "Auth scheme without name",
             },
+            // Profile tests
+            TestCase {
+                input: "profile://fs",
+                expected: Ok(Target::Profile {
+                    name: "fs".to_string(),
+                }),
+                description: "Profile with scheme",
+            },
+            TestCase {
+                input: "@github_tools",
+                expected: Ok(Target::Profile {
+                    name: "github_tools".to_string(),
+                }),
+                description: "Profile with @ shorthand",
+            },
+            TestCase {
+                input: "@",
+                expected: Err(format_err("Empty profile name")),
+                description: "Profile shorthand without name",
+            },
+            TestCase {
+                input: "@my-profile",
+                expected: Err(format_err(
+                    "Profile name 'my-profile' is invalid. Names can only contain letters, numbers, and underscores (a-zA-Z0-9_)",
+                )),
+                description: "Profile with hyphenated name (invalid)",
+            },
             // HTTP/HTTPS error cases
             TestCase {
                 input: "http://",
@@ -639,6 +1200,7 @@ mod tests {
                 target: Target::Stdio {
                     command: "./server".to_string(),
                     args: vec![],
+                    env: ProcessEnv::default(),
                 },
                 expected: "cmd://./server",
                 description: "stdio without args",
@@ -647,6 +1209,7 @@ mod tests {
                 target: Target::Stdio {
                     command: "./server".to_string(),
                     args: vec!["--verbose".to_string()],
+                    env: ProcessEnv::default(),
                 },
                 expected: "cmd://./server --verbose",
                 description: "stdio with args",
@@ -655,6 +1218,7 @@ mod tests {
                 target: Target::Stdio {
                     command: "server".to_string(),
                     args: vec!["--path".to_string(), "/some path/".to_string()],
+                    env: ProcessEnv::default(),
                 },
                 expected: r#"cmd://server --path '/some path/'"#,
                 description: "stdio with quoted args",
@@ -664,6 +1228,7 @@ mod tests {
                 target: Target::Http {
                     host: "example.com".to_string(),
                     port: 80,
+                    path: String::new(),
                 },
                 expected: "http://example.com",
                 description: "HTTP with default port",
@@ -672,6 +1237,7 @@ mod tests {
                 target: Target::Http {
                     host: "example.com".to_string(),
                     port: 8080,
+                    path: String::new(),
                 },
                 expected: "http://example.com:8080",
                 description: "HTTP with custom port",
@@ -680,6 +1246,7 @@ mod tests {
                 target: Target::Http {
                     host: "::1".to_string(),
                     port: 80,
+                    path: String::new(),
                 },
                 expected: "http://[::1]",
                 description: "HTTP IPv6 with default port",
@@ -688,6 +1255,7 @@ mod tests {
                 target: Target::Http {
                     host: "2001:db8::1".to_string(),
                     port: 8080,
+                    path: String::new(),
                 },
                 expected: "http://[2001:db8::1]:8080",
                 description: "HTTP IPv6 with custom port",
@@ -697,6 +1265,7 @@ mod tests {
                 target: Target::Https {
                     host: "example.com".to_string(),
                     port: 443,
+                    path: String::new(),
                 },
                 expected: "https://example.com",
                 description: "HTTPS with default port",
@@ -705,6 +1274,7 @@ mod tests {
                 target: Target::Https {
                     host: "example.com".to_string(),
                     port: 8443,
+                    path: String::new(),
                 },
                 expected: "https://example.com:8443",
                 description: "HTTPS with custom port",
@@ -713,6 +1283,7 @@ mod tests {
                 target: Target::Https {
                     host: "::1".to_string(),
                     port: 443,
+                    path: String::new(),
                 },
                 expected: "https://[::1]",
                 description: "HTTPS IPv6 with default port",
@@ -721,6 +1292,7 @@ mod tests {
                 target: Target::Https {
                     host: "2001:db8::1".to_string(),
                     port: 8443,
+                    path: String::new(),
                 },
                 expected: "https://[2001:db8::1]:8443",
                 description: "HTTPS IPv6 with custom port",
@@ -740,6 +1312,14 @@ mod tests {
                 expected: "auth://my_oauth_service",
                 description: "Auth with underscored name",
             },
+            // Profile display tests
+            TestCase {
+                target: Target::Profile {
+                    name: "fs".to_string(),
+                },
+                expected: "profile://fs",
+                description: "Profile",
+            },
         ];
 
         for test_case in test_cases {
@@ -751,4 +1331,96 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_stdio_env_roundtrip() {
+        let input = "cmd://--cwd=/srv --env-file=.env NAME='My Server' ./server --verbose";
+        let target = Target::parse(input).unwrap();
+        assert_eq!(target.to_string(), input);
+        assert_eq!(Target::parse(&target.to_string()).unwrap(), target);
+    }
+
+    #[test]
+    fn test_http_path_roundtrip() {
+        for input in [
+            "http://localhost:8080/mcp",
+            "https://example.com/v1/sse?token=abc",
+            "https://[::1]:8443/",
+            "sse://localhost:3000/sse",
+            "sses://example.com:8443/v1/sse",
+            "ws://localhost:8080/mcp",
+            "wss://[2001:db8::1]",
+            "unix:///run/mcp/server.sock",
+            "tls://localhost:8443",
+            "tls://[::1]:8443",
+        ] {
+            let target = Target::parse(input).unwrap();
+            assert_eq!(target.to_string(), input);
+        }
+    }
+
+    #[test]
+    fn test_redacted_display() {
+        for (input, expected) in [
+            (
+                "cmd://--env-file=.env API_KEY=secret NAME='My Server' ./server --verbose",
+                "cmd://--env-file=.env API_KEY=*** NAME=*** ./server --verbose",
+            ),
+            (
+                "https://example.com/v1/sse?token=abc&debug",
+                "https://example.com/v1/sse?token=***&debug",
+            ),
+            (
+                "wss://example.com/mcp?key=1",
+                "wss://example.com/mcp?key=***",
+            ),
+            ("http://localhost:8080/mcp", "http://localhost:8080/mcp"),
+            ("tcp://localhost:3000", "tcp://localhost:3000"),
+        ] {
+            let target = Target::parse(input).unwrap();
+            assert_eq!(target.redacted().to_string(), expected);
+            assert_eq!(target.to_string(), input);
+        }
+    }
+
+    #[test]
+    fn test_with_process_env() {
+        let target = Target::parse("cmd://--cwd=/a KEY=1 server").unwrap();
+        let overrides = ProcessEnv {
+            vars: vec![("KEY".to_string(), "2".to_string())],
+            cwd: Some("/b".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            target.with_process_env(&overrides).to_string(),
+            "cmd://--cwd=/b KEY=1 KEY=2 server"
+        );
+    }
+
+    #[test]
+    fn test_parse_env_file() {
+        let contents = r#"
+# API credentials
+API_KEY=abc123
+export REGION = "us east"
+QUOTED='single'
+EMPTY=
+"#;
+        assert_eq!(
+            parse_env_file(contents).unwrap(),
+            vec![
+                ("API_KEY".to_string(), "abc123".to_string()),
+                ("REGION".to_string(), "us east".to_string()),
+                ("QUOTED".to_string(), "single".to_string()),
+                ("EMPTY".to_string(), "".to_string()),
+            ]
+        );
+        assert_eq!(
+            parse_env_file("OK=1\nnot an assignment")
+                .unwrap_err()
+                .to_string(),
+            format_err("Line 2: expected KEY=VALUE").to_string()
+        );
+        assert!(parse_env_file("1BAD=x").is_err());
+    }
 }
@@ -1,9 +1,12 @@
 use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 
 use rustyline::DefaultEditor;
 use serde::{Deserialize, Serialize};
+use tempfile::NamedTempFile;
 use tenx_mcp::{
     Error, Result, Server, ServerConn, ServerCtx,
     schema::{
@@ -11,9 +14,11 @@ use tenx_mcp::{
         ListResourcesResult, ListToolsResult, LoggingLevel, ProgressToken, Prompt, PromptArgument,
         ReadResourceResult, Resource, ServerCapabilities, ServerNotification, Tool, ToolSchema,
     },
+    transport::GenericDuplex,
 };
+use tokio::{net::TcpStream, sync::mpsc};
 
-use crate::{ctx::Ctx, output::Output};
+use crate::{ctx::Ctx, output::Output, tls, transport, ws};
 
 /// Sample user data structure for demonstrating JSON resource serving
 #[derive(Serialize, Deserialize)]
@@ -1096,6 +1101,98 @@ async fn handle_tcp_non_interactive(
     server.serve_tcp(addr).await
 }
 
+/// Run a listener that hands over the connections it accepts, serving each
+/// one as it arrives
+async fn serve_connections<F, Fut>(
+    server: Server<impl Fn() -> Box<dyn ServerConn> + Clone + Send + Sync + 'static>,
+    listen: F,
+) -> Result<()>
+where
+    F: FnOnce(transport::Connections) -> Fut,
+    Fut: std::future::Future<Output = crate::Result<()>>,
+{
+    let (connections, mut accepted) = mpsc::channel(16);
+    let listener = listen(connections);
+    tokio::pin!(listener);
+    loop {
+        tokio::select! {
+            result = &mut listener => {
+                return result.map_err(|e| Error::InternalError(e.to_string()));
+            }
+            Some(stream) = accepted.recv() => {
+                let server = server.clone();
+                tokio::spawn(async move {
+                    let (reader, writer) = tokio::io::split(stream);
+                    let _ = server.serve(Box::new(GenericDuplex::new(reader, writer))).await;
+                });
+            }
+        }
+    }
+}
+
+/// Serve HTTP with TLS terminated in front of it. The HTTP server itself
+/// listens on a free loopback port, which is picked again if it gets taken
+/// before the server binds it.
+async fn serve_https(
+    server: Server<impl Fn() -> Box<dyn ServerConn> + Clone + Send + Sync + 'static>,
+    addr: &str,
+    certificate: &tls::SelfSigned,
+) -> Result<()> {
+    let mut attempts = 0;
+    let (handle, internal) = loop {
+        let internal = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .map_err(|e| Error::InternalError(format!("Failed to find a local port: {e}")))?
+            .to_string();
+        match server.clone().serve_http(&internal).await {
+            Ok(handle) => break (handle, internal),
+            Err(_) if attempts < 3 => attempts += 1,
+            Err(e) => return Err(e),
+        }
+    };
+
+    let (connections, mut accepted) = mpsc::channel(16);
+    let forward = async {
+        while let Some(mut stream) = accepted.recv().await {
+            let internal = internal.clone();
+            tokio::spawn(async move {
+                if let Ok(mut upstream) = TcpStream::connect(&internal).await {
+                    let _ = tokio::io::copy_bidirectional(&mut stream, &mut upstream).await;
+                }
+            });
+        }
+    };
+    let result = tokio::select! {
+        result = tls::serve(addr, certificate, connections) => {
+            result.map_err(|e| Error::InternalError(e.to_string()))
+        }
+        _ = forward => Ok(()),
+    };
+    handle.stop().await?;
+    result
+}
+
+/// Generate a self-signed certificate, and write it to a new temporary file
+/// for clients to trust. The file is removed when dropped.
+fn self_signed_certificate(output: &Output) -> Result<(tls::SelfSigned, NamedTempFile)> {
+    let certificate =
+        tls::SelfSigned::generate().map_err(|e| Error::InternalError(e.to_string()))?;
+    let file = tempfile::Builder::new()
+        .prefix("mcptool-testserver-")
+        .suffix(".pem")
+        .tempfile()
+        .and_then(|mut file| {
+            file.write_all(certificate.cert_pem.as_bytes())?;
+            Ok(file)
+        })
+        .map_err(|e| Error::InternalError(format!("Failed to write certificate: {e}")))?;
+    let _ = output.text(format!(
+        "Certificate: {} (trust it with --cacert)",
+        file.path().display()
+    ));
+    Ok((certificate, file))
+}
+
 /// Handle non-interactive mode for HTTP server
 async fn handle_http_non_interactive(
     server: Server<impl Fn() -> Box<dyn ServerConn> + Clone + Send + Sync + 'static>,
@@ -1116,18 +1213,42 @@ async fn handle_http_non_interactive(
     Ok(())
 }
 
-pub async fn run_test_server(
-    ctx: &Ctx,
-    stdio: bool,
-    tcp: bool,
-    port: u16,
-    interactive: bool,
-) -> Result<()> {
+/// How the test server accepts connections
+pub struct TestServerArgs {
+    pub stdio: bool,
+    pub tcp: bool,
+    pub ws: bool,
+    /// Listen on a Unix socket at this path
+    pub unix: Option<PathBuf>,
+    /// Serve TCP wrapped in TLS, with a generated self-signed certificate
+    pub tls: bool,
+    /// Serve HTTP over TLS, with a generated self-signed certificate
+    pub https: bool,
+    pub port: u16,
+    pub interactive: bool,
+}
+
+pub async fn run_test_server(ctx: &Ctx, args: TestServerArgs) -> Result<()> {
+    let TestServerArgs {
+        stdio,
+        tcp,
+        ws,
+        unix,
+        tls,
+        https,
+        port,
+        interactive,
+    } = args;
+
     // Validate that only one transport is specified
-    let transport_count = [stdio, tcp].iter().filter(|&&x| x).count();
+    let transport_count = [stdio, tcp, ws, unix.is_some(), tls, https]
+        .iter()
+        .filter(|&&x| x)
+        .count();
     if transport_count > 1 {
         return Err(Error::InvalidConfiguration(
-            "Only one transport can be specified: --stdio, --tcp, or HTTP (default)".to_string(),
+            "Only one transport can be specified: --stdio, --tcp, --ws, --unix, --tls, --https, or HTTP (default)"
+                .to_string(),
         ));
     }
 
@@ -1158,6 +1279,71 @@ pub async fn run_test_server(
 
     if stdio {
         server.serve_stdio().await?;
+    } else if let Some(path) = unix {
+        let server_address = format!("unix://{}", path.display());
+        let serve = move || {
+            serve_connections(server, move |connections| async move {
+                transport::serve_unix(&path, connections).await
+            })
+        };
+        if interactive {
+            handle_interactive_mode(ctx, server_address, server_state, &output.clone(), serve)
+                .await?;
+        } else {
+            let _ = output.text("Transport: Unix socket");
+            let _ = output.trace_success(format!("Listening on: {server_address}"));
+            let _ = output.text("Press Ctrl+C to stop the server");
+            serve().await?;
+        }
+    } else if tls {
+        let addr = format!("127.0.0.1:{port}");
+        let server_address = format!("tls://{addr}");
+        let (certificate, _cert_file) = self_signed_certificate(&output)?;
+        let serve = move || {
+            serve_connections(server, move |connections| async move {
+                tls::serve(&addr, &certificate, connections).await
+            })
+        };
+        if interactive {
+            handle_interactive_mode(ctx, server_address, server_state, &output.clone(), serve)
+                .await?;
+        } else {
+            let _ = output.text("Transport: TLS");
+            let _ = output.trace_success(format!("Listening on: {server_address}"));
+            let _ = output.text("Press Ctrl+C to stop the server");
+            serve().await?;
+        }
+    } else if https {
+        let addr = format!("127.0.0.1:{port}");
+        let server_address = format!("https://{addr}");
+        let (certificate, _cert_file) = self_signed_certificate(&output)?;
+        let serve = move || async move { serve_https(server, &addr, &certificate).await };
+        if interactive {
+            handle_interactive_mode(ctx, server_address, server_state, &output.clone(), serve)
+                .await?;
+        } else {
+            let _ = output.text("Transport: HTTPS");
+            let _ = output.trace_success(format!("Listening on: {server_address}"));
+            let _ = output.text("Press Ctrl+C to stop the server");
+            serve().await?;
+        }
+    } else if ws {
+        let addr = format!("127.0.0.1:{port}");
+        let server_address = format!("ws://{addr}");
+        let serve = move || {
+            serve_connections(server, move |connections| async move {
+                ws::serve(&addr, connections).await
+            })
+        };
+        if interactive {
+            handle_interactive_mode(ctx, server_address, server_state, &output.clone(), serve)
+                .await?;
+        } else {
+            let _ = output.text("Transport: WebSocket");
+            let _ = output.trace_success(format!("Listening on: {server_address}"));
+            let _ = output.text("Press Ctrl+C to stop the server");
+            serve().await?;
+        }
     } else {
         let addr = format!("127.0.0.1:{port}");
         if interactive {
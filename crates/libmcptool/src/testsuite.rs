@@ -0,0 +1,337 @@
+mod expect;
+mod report;
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tokio::{sync::Semaphore, task::JoinSet};
+
+pub use expect::Expectations;
+pub use report::ReportFormat;
+
+use crate::{
+    Error, Result, client,
+    command::{McpCommand, execute_mcp_command_with_client},
+    ctx::Ctx,
+    target::Target,
+};
+
+/// A declarative suite of test cases, loaded from YAML, TOML or JSON
+#[derive(Debug, Clone, Deserialize)]
+pub struct Suite {
+    /// Optional suite name, used in reports
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Default target for cases that don't specify one
+    #[serde(default)]
+    pub target: Option<String>,
+    pub cases: Vec<TestCase>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TestCase {
+    pub name: String,
+    /// Target to run this case against, overriding the suite default
+    #[serde(default)]
+    pub target: Option<String>,
+    #[serde(flatten)]
+    pub operation: Operation,
+    #[serde(default)]
+    pub expect: Expectations,
+}
+
+/// The MCP operation a test case performs, with its inputs
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "operation", rename_all = "lowercase")]
+pub enum Operation {
+    Listtools,
+    Calltool {
+        tool: String,
+        #[serde(default)]
+        arguments: Map<String, Value>,
+    },
+    Readresource {
+        uri: String,
+    },
+    Getprompt {
+        prompt: String,
+        #[serde(default)]
+        arguments: Map<String, Value>,
+    },
+    Complete {
+        reference: String,
+        argument: String,
+    },
+}
+
+impl Operation {
+    /// The command that performs the operation. Arguments are passed on
+    /// exactly as given in the suite.
+    fn command(self) -> McpCommand {
+        match self {
+            Operation::Listtools => McpCommand::Listtools,
+            Operation::Calltool { tool, arguments } => McpCommand::Calltool {
+                tool_name: tool,
+                args: vec![],
+                interactive: false,
+                json: false,
+                arguments: Some(arguments),
+            },
+            Operation::Readresource { uri } => McpCommand::Readresource { uri },
+            Operation::Getprompt { prompt, arguments } => McpCommand::Getprompt {
+                name: prompt,
+                args: vec![],
+                arguments: Some(arguments),
+            },
+            Operation::Complete {
+                reference,
+                argument,
+            } => McpCommand::Complete {
+                reference,
+                argument,
+            },
+        }
+    }
+}
+
+/// The outcome of a single test case
+#[derive(Debug, Clone, Serialize)]
+pub struct CaseResult {
+    pub name: String,
+    pub target: String,
+    pub passed: bool,
+    /// Time taken by the operation itself, excluding connection setup
+    #[serde(rename = "duration_ms", serialize_with = "serialize_millis")]
+    pub duration: Duration,
+    pub failures: Vec<String>,
+}
+
+fn serialize_millis<S: serde::Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64() * 1000.0)
+}
+
+impl Suite {
+    /// Load a suite, choosing the format from the file extension
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("yaml" | "yml") => serde_yaml::from_str(&contents)
+                .map_err(|e| Error::Format(format!("Invalid YAML test suite: {e}"))),
+            Some("toml") => toml::from_str(&contents)
+                .map_err(|e| Error::Format(format!("Invalid TOML test suite: {e}"))),
+            Some("json") => Ok(serde_json::from_str(&contents)?),
+            _ => Err(Error::Format(format!(
+                "Unsupported test suite format: {}. Expected .yaml, .yml, .toml or .json",
+                path.display()
+            ))),
+        }
+    }
+}
+
+pub struct TestCommandArgs {
+    pub suite: PathBuf,
+    pub jobs: usize,
+    pub format: ReportFormat,
+    pub output: Option<PathBuf>,
+}
+
+pub async fn test_command(ctx: &Ctx, args: TestCommandArgs) -> Result<()> {
+    if args.output.is_some() && args.format == ReportFormat::Text {
+        return Err(Error::Other(
+            "--output can only be used with --format junit or --format tap".to_string(),
+        ));
+    }
+    let suite = Suite::load(&args.suite)?;
+    let suite_name = suite
+        .name
+        .clone()
+        .unwrap_or_else(|| args.suite.display().to_string());
+
+    if args.format == ReportFormat::Text {
+        ctx.output.h1(format!(
+            "Running {} test(s) from {suite_name}",
+            suite.cases.len()
+        ))?;
+    }
+
+    let results = run_suite(ctx, &suite, args.jobs).await?;
+    let failed = results.iter().filter(|r| !r.passed).count();
+
+    match args.format {
+        ReportFormat::Text if ctx.output.json => ctx.output.json_value(&results)?,
+        ReportFormat::Text => report::text(&ctx.output, &results)?,
+        ReportFormat::Junit | ReportFormat::Tap => {
+            let report = report::render(args.format, &suite_name, &results);
+            match &args.output {
+                Some(path) => std::fs::write(path, report)?,
+                None => {
+                    use std::io::Write;
+                    let mut stdout = std::io::stdout();
+                    stdout.write_all(report.as_bytes())?;
+                    stdout.flush()?;
+                }
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(Error::Other(format!(
+            "{failed} of {} test(s) failed",
+            results.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Run all cases in a suite, at most `jobs` at a time. Results are returned
+/// in the order the cases appear in the suite.
+pub async fn run_suite(ctx: &Ctx, suite: &Suite, jobs: usize) -> Result<Vec<CaseResult>> {
+    // Individual commands run silently; results are reported at the end
+    let quiet_ctx = Ctx {
+        output: ctx.output.clone().with_quiet(true),
+        ..ctx.clone()
+    };
+    let semaphore = Arc::new(Semaphore::new(jobs.max(1)));
+    let mut tasks = JoinSet::new();
+
+    for (idx, case) in suite.cases.iter().cloned().enumerate() {
+        let target = case
+            .target
+            .clone()
+            .or_else(|| suite.target.clone())
+            .ok_or_else(|| {
+                Error::Format(format!(
+                    "Test case '{}' has no target and the suite has no default target",
+                    case.name
+                ))
+            })?;
+        let ctx = quiet_ctx.clone();
+        let semaphore = semaphore.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            (idx, run_case(&ctx, case, target).await)
+        });
+    }
+
+    let mut results = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        results.push(joined.map_err(|e| Error::Internal(e.to_string()))?);
+    }
+    results.sort_by_key(|(idx, _)| *idx);
+    Ok(results.into_iter().map(|(_, result)| result).collect())
+}
+
+async fn run_case(ctx: &Ctx, case: TestCase, target: String) -> CaseResult {
+    let parsed = Target::parse(&target);
+    let mut result = CaseResult {
+        name: case.name.clone(),
+        // Reported by its redacted form, when it parses
+        target: match &parsed {
+            Ok(parsed) => parsed.redacted().to_string(),
+            Err(_) => target,
+        },
+        passed: false,
+        duration: Duration::ZERO,
+        failures: vec![],
+    };
+
+    let connection = match parsed {
+        Ok(parsed) => client::get_client(ctx, &parsed).await,
+        Err(e) => Err(e),
+    };
+    let (mut client, init_result) = match connection {
+        Ok(connection) => connection,
+        Err(e) => {
+            result.failures.push(format!("Failed to connect: {e}"));
+            return result;
+        }
+    };
+
+    let start = Instant::now();
+    let outcome =
+        execute_mcp_command_with_client(case.operation.command(), &mut client, &init_result, ctx)
+            .await;
+    result.duration = start.elapsed();
+
+    result.failures = match outcome {
+        Ok(value) => case.expect.check(&value, result.duration),
+        Err(e) => vec![format!("Operation failed: {e}")],
+    };
+    result.passed = result.failures.is_empty();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_yaml_suite() {
+        let suite: Suite = serde_yaml::from_str(
+            r#"
+name: smoke
+target: "cmd://./server"
+cases:
+  - name: echo
+    operation: calltool
+    tool: echo
+    arguments:
+      message: hello
+      count: 2
+    expect:
+      subset:
+        content:
+          - text: hello
+      max_latency_ms: 500
+  - name: tools
+    target: "tcp://localhost:3000"
+    operation: listtools
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(suite.name.as_deref(), Some("smoke"));
+        assert_eq!(suite.cases.len(), 2);
+        assert_eq!(suite.cases[0].expect.max_latency_ms, Some(500));
+        match &suite.cases[0].operation {
+            Operation::Calltool { tool, arguments } => {
+                assert_eq!(tool, "echo");
+                assert_eq!(arguments["message"], "hello");
+                assert_eq!(arguments["count"], 2);
+            }
+            _ => panic!("expected a calltool operation"),
+        }
+        assert!(matches!(suite.cases[1].operation, Operation::Listtools));
+    }
+
+    #[test]
+    fn test_parse_toml_suite() {
+        let suite: Suite = toml::from_str(
+            r#"
+target = "http://localhost:8080"
+
+[[cases]]
+name = "readme"
+operation = "readresource"
+uri = "file:///readme.md"
+
+[cases.expect]
+regex = "^# Title"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(suite.cases.len(), 1);
+        assert_eq!(suite.cases[0].expect.regex.as_deref(), Some("^# Title"));
+        assert!(matches!(
+            suite.cases[0].operation,
+            Operation::Readresource { .. }
+        ));
+    }
+}
@@ -0,0 +1,286 @@
+use std::time::Duration;
+
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Expectations a test case's response must meet. All specified expectations
+/// are checked, and every failure is reported.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Expectations {
+    /// The response must equal this value exactly
+    #[serde(default)]
+    pub exact: Option<Value>,
+    /// The response must contain this value. Objects match if every expected
+    /// key matches, arrays match element-wise from the start.
+    #[serde(default)]
+    pub subset: Option<Value>,
+    /// A regular expression that must match the response text content
+    #[serde(default)]
+    pub regex: Option<String>,
+    /// A JSON Schema the response must validate against
+    #[serde(default)]
+    pub schema: Option<Value>,
+    /// Maximum time the operation may take, in milliseconds
+    #[serde(default)]
+    pub max_latency_ms: Option<u64>,
+    /// The tool call must report an error with `isError`. Without this, a tool
+    /// error fails the case.
+    #[serde(default)]
+    pub error: bool,
+}
+
+impl Expectations {
+    /// Check a response against all expectations, returning a description of
+    /// each failure.
+    pub fn check(&self, actual: &Value, latency: Duration) -> Vec<String> {
+        let mut failures = vec![];
+
+        let is_error = actual.get("isError") == Some(&Value::Bool(true));
+        if is_error && !self.error {
+            failures.push(format!(
+                "Tool reported an error: {}",
+                text_content(actual).join(" ")
+            ));
+        } else if self.error && !is_error {
+            failures.push("Expected the tool to report an error".to_string());
+        }
+
+        if let Some(expected) = &self.exact
+            && expected != actual
+        {
+            failures.push(format!("Expected exactly {expected}, got {actual}"));
+        }
+
+        if let Some(expected) = &self.subset
+            && let Err(path) = is_subset(expected, actual, "$")
+        {
+            failures.push(format!(
+                "Response does not contain expected value at {path}"
+            ));
+        }
+
+        if let Some(pattern) = &self.regex {
+            match Regex::new(pattern) {
+                Ok(re) => {
+                    let texts = text_content(actual);
+                    if !texts.iter().any(|text| re.is_match(text)) {
+                        failures.push(format!("No response text matches /{pattern}/"));
+                    }
+                }
+                Err(e) => failures.push(format!("Invalid regex /{pattern}/: {e}")),
+            }
+        }
+
+        if let Some(schema) = &self.schema {
+            match jsonschema::validator_for(schema) {
+                Ok(validator) => {
+                    for error in validator.iter_errors(actual) {
+                        failures.push(format!(
+                            "Schema violation at {}: {error}",
+                            error.instance_path
+                        ));
+                    }
+                }
+                Err(e) => failures.push(format!("Invalid JSON schema: {e}")),
+            }
+        }
+
+        if let Some(max) = self.max_latency_ms {
+            let max = Duration::from_millis(max);
+            if latency > max {
+                failures.push(format!(
+                    "Took {}ms, exceeding the maximum of {}ms",
+                    latency.as_millis(),
+                    max.as_millis()
+                ));
+            }
+        }
+
+        failures
+    }
+}
+
+/// Check whether `expected` is contained in `actual`. On mismatch, returns the
+/// path of the first differing value.
+fn is_subset(expected: &Value, actual: &Value, path: &str) -> Result<(), String> {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            for (key, value) in expected {
+                let path = format!("{path}.{key}");
+                match actual.get(key) {
+                    Some(actual) => is_subset(value, actual, &path)?,
+                    None => return Err(path),
+                }
+            }
+            Ok(())
+        }
+        (Value::Array(expected), Value::Array(actual)) => {
+            if expected.len() > actual.len() {
+                return Err(path.to_string());
+            }
+            for (idx, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+                is_subset(expected, actual, &format!("{path}[{idx}]"))?;
+            }
+            Ok(())
+        }
+        (expected, actual) if expected == actual => Ok(()),
+        _ => Err(path.to_string()),
+    }
+}
+
+/// Collect the text content of a response: every string-valued `text` field,
+/// as found in tool results, resource contents and prompt messages. Responses
+/// without any text are matched as compact JSON.
+fn text_content(value: &Value) -> Vec<String> {
+    fn collect(value: &Value, texts: &mut Vec<String>) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map {
+                    match value {
+                        Value::String(text) if key == "text" => texts.push(text.clone()),
+                        other => collect(other, texts),
+                    }
+                }
+            }
+            Value::Array(items) => items.iter().for_each(|item| collect(item, texts)),
+            _ => {}
+        }
+    }
+
+    let mut texts = vec![];
+    collect(value, &mut texts);
+    if texts.is_empty() {
+        texts.push(value.to_string());
+    }
+    texts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn response() -> Value {
+        json!({
+            "content": [
+                {"type": "text", "text": "Hello, world"},
+                {"type": "text", "text": "Second"}
+            ],
+            "isError": false
+        })
+    }
+
+    #[test]
+    fn test_subset() {
+        struct TestCase {
+            expected: Value,
+            result: Result<(), String>,
+        }
+
+        let cases = vec![
+            TestCase {
+                expected: json!({"isError": false}),
+                result: Ok(()),
+            },
+            TestCase {
+                expected: json!({"content": [{"text": "Hello, world"}]}),
+                result: Ok(()),
+            },
+            TestCase {
+                expected: json!({"content": [{}, {"type": "text"}]}),
+                result: Ok(()),
+            },
+            TestCase {
+                expected: json!({"content": [{"text": "Goodbye"}]}),
+                result: Err("$.content[0].text".to_string()),
+            },
+            TestCase {
+                expected: json!({"content": [{}, {}, {}]}),
+                result: Err("$.content".to_string()),
+            },
+            TestCase {
+                expected: json!({"structuredContent": {}}),
+                result: Err("$.structuredContent".to_string()),
+            },
+        ];
+
+        for case in cases {
+            assert_eq!(
+                is_subset(&case.expected, &response(), "$"),
+                case.result,
+                "expected: {}",
+                case.expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let expect = Expectations {
+            regex: Some("^Second$".to_string()),
+            max_latency_ms: Some(100),
+            ..Default::default()
+        };
+        assert!(
+            expect
+                .check(&response(), Duration::from_millis(10))
+                .is_empty()
+        );
+        assert_eq!(
+            expect.check(&response(), Duration::from_millis(200)).len(),
+            1
+        );
+
+        let expect = Expectations {
+            exact: Some(json!({"tools": []})),
+            regex: Some("nomatch".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(expect.check(&response(), Duration::ZERO).len(), 2);
+
+        // Responses without text are matched as JSON
+        let expect = Expectations {
+            regex: Some(r#""tools":\[\]"#.to_string()),
+            ..Default::default()
+        };
+        assert!(
+            expect
+                .check(&json!({"tools": []}), Duration::ZERO)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_schema() {
+        let expect = Expectations {
+            schema: Some(json!({
+                "type": "object",
+                "required": ["content"],
+                "properties": {"isError": {"const": false}}
+            })),
+            ..Default::default()
+        };
+        assert!(expect.check(&response(), Duration::ZERO).is_empty());
+        assert_eq!(
+            expect
+                .check(&json!({"isError": true}), Duration::ZERO)
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_error() {
+        let failed = json!({"content": [{"type": "text", "text": "boom"}], "isError": true});
+        let failures = Expectations::default().check(&failed, Duration::ZERO);
+        assert_eq!(failures, vec!["Tool reported an error: boom".to_string()]);
+
+        let expect = Expectations {
+            error: true,
+            ..Default::default()
+        };
+        assert!(expect.check(&failed, Duration::ZERO).is_empty());
+        assert_eq!(expect.check(&response(), Duration::ZERO).len(), 1);
+    }
+}
@@ -0,0 +1,166 @@
+use std::time::Duration;
+
+use super::CaseResult;
+use crate::{Result, output::Output};
+
+/// Report formats for test results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReportFormat {
+    /// Human readable output
+    Text,
+    /// JUnit XML, for CI systems
+    Junit,
+    /// Test Anything Protocol
+    Tap,
+}
+
+/// Display results in the standard human readable form
+pub fn text(output: &Output, results: &[CaseResult]) -> Result<()> {
+    for result in results {
+        let label = format!(
+            "{} ({}, {}ms)",
+            result.name,
+            result.target,
+            result.duration.as_millis()
+        );
+        if result.passed {
+            output.success(format!("PASS {label}"))?;
+        } else {
+            output.trace_error(format!("FAIL {label}"))?;
+            let indented = output.indent();
+            for failure in &result.failures {
+                indented.text(failure)?;
+            }
+        }
+    }
+
+    let failed = results.iter().filter(|r| !r.passed).count();
+    output.text("")?;
+    output.kv("Passed", (results.len() - failed).to_string())?;
+    output.kv("Failed", failed.to_string())?;
+    Ok(())
+}
+
+/// Render results in a machine readable format. Text results are displayed
+/// through [`text`] instead, and render as JUnit here.
+pub fn render(format: ReportFormat, suite: &str, results: &[CaseResult]) -> String {
+    match format {
+        ReportFormat::Tap => tap(results),
+        ReportFormat::Text | ReportFormat::Junit => junit(suite, results),
+    }
+}
+
+fn junit(suite: &str, results: &[CaseResult]) -> String {
+    let failed = results.iter().filter(|r| !r.passed).count();
+    let total: Duration = results.iter().map(|r| r.duration).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites tests=\"{}\" failures=\"{failed}\" time=\"{:.3}\">\n",
+        results.len(),
+        total.as_secs_f64()
+    ));
+    xml.push_str(&format!(
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{failed}\" time=\"{:.3}\">\n",
+        escape_xml(suite),
+        results.len(),
+        total.as_secs_f64()
+    ));
+    for result in results {
+        xml.push_str(&format!(
+            "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+            escape_xml(&result.name),
+            escape_xml(&result.target),
+            result.duration.as_secs_f64()
+        ));
+        if result.passed {
+            xml.push_str("/>\n");
+        } else {
+            xml.push_str(">\n");
+            xml.push_str(&format!(
+                "      <failure message=\"{}\">{}</failure>\n",
+                escape_xml(result.failures.first().map(String::as_str).unwrap_or("")),
+                escape_xml(&result.failures.join("\n"))
+            ));
+            xml.push_str("    </testcase>\n");
+        }
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+fn tap(results: &[CaseResult]) -> String {
+    let mut tap = format!("TAP version 13\n1..{}\n", results.len());
+    for (idx, result) in results.iter().enumerate() {
+        let status = if result.passed { "ok" } else { "not ok" };
+        tap.push_str(&format!("{status} {} - {}\n", idx + 1, result.name));
+        if !result.passed {
+            tap.push_str("  ---\n");
+            tap.push_str(&format!("  target: {}\n", result.target));
+            tap.push_str(&format!("  duration_ms: {}\n", result.duration.as_millis()));
+            tap.push_str("  failures:\n");
+            for failure in &result.failures {
+                tap.push_str(&format!(
+                    "    - {}\n",
+                    serde_json::Value::from(failure.as_str())
+                ));
+            }
+            tap.push_str("  ...\n");
+        }
+    }
+    tap
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results() -> Vec<CaseResult> {
+        vec![
+            CaseResult {
+                name: "echo".to_string(),
+                target: "tcp://localhost:3000".to_string(),
+                passed: true,
+                duration: Duration::from_millis(12),
+                failures: vec![],
+            },
+            CaseResult {
+                name: "a <b>".to_string(),
+                target: "tcp://localhost:3000".to_string(),
+                passed: false,
+                duration: Duration::from_millis(5),
+                failures: vec!["Expected \"x\"".to_string()],
+            },
+        ]
+    }
+
+    #[test]
+    fn test_junit() {
+        let xml = render(ReportFormat::Junit, "suite", &results());
+        assert!(xml.contains("<testsuite name=\"suite\" tests=\"2\" failures=\"1\""));
+        assert!(xml.contains(
+            "<testcase name=\"echo\" classname=\"tcp://localhost:3000\" time=\"0.012\"/>"
+        ));
+        assert!(xml.contains("name=\"a &lt;b&gt;\""));
+        assert!(xml.contains("<failure message=\"Expected &quot;x&quot;\">"));
+    }
+
+    #[test]
+    fn test_tap() {
+        let tap = render(ReportFormat::Tap, "suite", &results());
+        let lines: Vec<&str> = tap.lines().collect();
+        assert_eq!(lines[0], "TAP version 13");
+        assert_eq!(lines[1], "1..2");
+        assert_eq!(lines[2], "ok 1 - echo");
+        assert_eq!(lines[3], "not ok 2 - a <b>");
+        assert!(tap.contains("    - \"Expected \\\"x\\\"\"\n"));
+    }
+}
@@ -0,0 +1,268 @@
+//! TLS settings for HTTPS, `wss://` and `tls://` connections, and the
+//! self-signed certificate used by the test server.
+
+use std::path::{Path, PathBuf};
+
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::{
+    Error, Result,
+    transport::{ACCEPT_RETRY_DELAY, Connections},
+};
+
+/// How to verify servers, and the certificate to present to them
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TlsOptions {
+    /// PEM file with additional CA certificates to trust
+    pub cacert: Option<PathBuf>,
+    /// PEM client certificate for mutual TLS
+    pub cert: Option<PathBuf>,
+    /// PEM PKCS#8 private key for the client certificate
+    pub key: Option<PathBuf>,
+    /// Accept any server certificate and hostname
+    pub insecure: bool,
+}
+
+impl TlsOptions {
+    pub fn is_empty(&self) -> bool {
+        *self == TlsOptions::default()
+    }
+
+    /// Apply these options to an HTTP client
+    pub fn configure(&self, mut builder: reqwest::ClientBuilder) -> Result<reqwest::ClientBuilder> {
+        if let Some(path) = &self.cacert {
+            for certificate in reqwest::Certificate::from_pem_bundle(&read(path)?)
+                .map_err(|e| tls_error(path, e))?
+            {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        if let Some((cert, key)) = self.identity_files()? {
+            let identity = reqwest::Identity::from_pkcs8_pem(&read(cert)?, &read(key)?)
+                .map_err(|e| tls_error(cert, e))?;
+            builder = builder.identity(identity);
+        }
+        if self.insecure {
+            builder = builder
+                .danger_accept_invalid_certs(true)
+                .danger_accept_invalid_hostnames(true);
+        }
+        Ok(builder)
+    }
+
+    /// A connector for TLS over raw TCP and WebSockets
+    pub fn connector(&self) -> Result<native_tls::TlsConnector> {
+        let mut builder = native_tls::TlsConnector::builder();
+        if let Some(path) = &self.cacert {
+            let bundle = read(path)?;
+            let bundle = std::str::from_utf8(&bundle).map_err(|e| tls_error(path, e))?;
+            let pems = pem_certificates(bundle);
+            if pems.is_empty() {
+                return Err(tls_error(path, "no certificates found"));
+            }
+            for pem in pems {
+                let certificate = native_tls::Certificate::from_pem(pem.as_bytes())
+                    .map_err(|e| tls_error(path, e))?;
+                builder.add_root_certificate(certificate);
+            }
+        }
+        if let Some((cert, key)) = self.identity_files()? {
+            let identity = native_tls::Identity::from_pkcs8(&read(cert)?, &read(key)?)
+                .map_err(|e| tls_error(cert, e))?;
+            builder.identity(identity);
+        }
+        if self.insecure {
+            builder
+                .danger_accept_invalid_certs(true)
+                .danger_accept_invalid_hostnames(true);
+        }
+        builder
+            .build()
+            .map_err(|e| Error::Other(format!("Failed to set up TLS: {e}")))
+    }
+
+    fn identity_files(&self) -> Result<Option<(&Path, &Path)>> {
+        match (&self.cert, &self.key) {
+            (Some(cert), Some(key)) => Ok(Some((cert, key))),
+            (None, None) => Ok(None),
+            _ => Err(Error::Other(
+                "A client certificate needs both --cert and --key".to_string(),
+            )),
+        }
+    }
+}
+
+/// Start TLS on an open TCP connection to `host`
+pub async fn connect(
+    stream: TcpStream,
+    host: &str,
+    options: &TlsOptions,
+) -> Result<tokio_native_tls::TlsStream<TcpStream>> {
+    let connector = tokio_native_tls::TlsConnector::from(options.connector()?);
+    connector
+        .connect(host, stream)
+        .await
+        .map_err(|e| Error::Other(format!("TLS handshake with {host} failed: {e}")))
+}
+
+/// A self-signed certificate for local testing
+pub struct SelfSigned {
+    /// PEM encoded certificate, which clients can trust with `--cacert`
+    pub cert_pem: String,
+    /// PEM encoded PKCS#8 private key
+    pub key_pem: String,
+}
+
+impl SelfSigned {
+    /// Generate a certificate valid for `localhost` and the loopback addresses
+    pub fn generate() -> Result<Self> {
+        let names = ["localhost", "127.0.0.1", "::1"].map(String::from).to_vec();
+        let rcgen::CertifiedKey { cert, key_pair } = rcgen::generate_simple_self_signed(names)
+            .map_err(|e| Error::Other(format!("Failed to generate certificate: {e}")))?;
+        Ok(SelfSigned {
+            cert_pem: cert.pem(),
+            key_pem: key_pair.serialize_pem(),
+        })
+    }
+}
+
+/// Accept TLS connections on `addr`, sending each to `connections` once the
+/// handshake completes. Returns once the receiver is dropped.
+pub async fn serve(addr: &str, certificate: &SelfSigned, connections: Connections) -> Result<()> {
+    let identity = native_tls::Identity::from_pkcs8(
+        certificate.cert_pem.as_bytes(),
+        certificate.key_pem.as_bytes(),
+    )
+    .map_err(|e| Error::Other(format!("Invalid certificate: {e}")))?;
+    let acceptor = native_tls::TlsAcceptor::new(identity)
+        .map_err(|e| Error::Other(format!("Failed to set up TLS: {e}")))?;
+    let acceptor = tokio_native_tls::TlsAcceptor::from(acceptor);
+
+    let listener = TcpListener::bind(addr).await?;
+    while !connections.is_closed() {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                tracing::warn!("Failed to accept a connection: {e}");
+                tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                continue;
+            }
+        };
+        let acceptor = acceptor.clone();
+        let connections = connections.clone();
+        tokio::spawn(async move {
+            if let Ok(stream) = acceptor.accept(stream).await {
+                let _ = connections.send(Box::new(stream)).await;
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Split a PEM bundle into its certificates, each still PEM encoded
+fn pem_certificates(bundle: &str) -> Vec<&str> {
+    const BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+    const END: &str = "-----END CERTIFICATE-----";
+    let mut certificates = Vec::new();
+    let mut rest = bundle;
+    while let Some(start) = rest.find(BEGIN) {
+        let Some(len) = rest[start..].find(END) else {
+            break;
+        };
+        let end = start + len + END.len();
+        certificates.push(&rest[start..end]);
+        rest = &rest[end..];
+    }
+    certificates
+}
+
+fn read(path: &Path) -> Result<Vec<u8>> {
+    std::fs::read(path).map_err(|e| Error::Other(format!("Failed to read {}: {e}", path.display())))
+}
+
+fn tls_error(path: &Path, error: impl std::fmt::Display) -> Error {
+    Error::Other(format!(
+        "Invalid certificate or key in {}: {error}",
+        path.display()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_self_signed_roundtrip() {
+        let certificate = SelfSigned::generate().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let cacert = dir.path().join("cert.pem");
+        // The server's certificate comes second in the bundle
+        let mut other = rcgen::CertificateParams::new(vec!["other.example".to_string()]).unwrap();
+        other
+            .distinguished_name
+            .push(rcgen::DnType::CommonName, "Other CA");
+        let other = other
+            .self_signed(&rcgen::KeyPair::generate().unwrap())
+            .unwrap();
+        std::fs::write(&cacert, other.pem() + &certificate.cert_pem).unwrap();
+
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let (connections, mut accepted) = tokio::sync::mpsc::channel(1);
+        tokio::spawn(async move {
+            serve(&format!("127.0.0.1:{port}"), &certificate, connections).await
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        // Echo a greeting back on the accepted connection
+        tokio::spawn(async move {
+            let mut stream = accepted.recv().await.unwrap();
+            let mut greeting = [0; 5];
+            stream.read_exact(&mut greeting).await.unwrap();
+            stream.write_all(&greeting).await.unwrap();
+        });
+
+        let open = || async move { TcpStream::connect(("localhost", port)).await.unwrap() };
+
+        // The self-signed certificate is only trusted when asked
+        assert!(
+            connect(open().await, "localhost", &TlsOptions::default())
+                .await
+                .is_err()
+        );
+        let options = TlsOptions {
+            cacert: Some(cacert),
+            ..Default::default()
+        };
+        let mut stream = connect(open().await, "localhost", &options).await.unwrap();
+        stream.write_all(b"hello").await.unwrap();
+        let mut greeting = [0; 5];
+        stream.read_exact(&mut greeting).await.unwrap();
+        assert_eq!(&greeting, b"hello");
+    }
+
+    #[test]
+    fn test_pem_certificates() {
+        let first = SelfSigned::generate().unwrap().cert_pem;
+        let second = SelfSigned::generate().unwrap().cert_pem;
+        let bundle = format!("# CA bundle\n{first}\n{second}");
+        let pems = pem_certificates(&bundle);
+        assert_eq!(pems, vec![first.trim_end(), second.trim_end()]);
+        assert!(pem_certificates("not a certificate").is_empty());
+    }
+
+    #[test]
+    fn test_cert_requires_key() {
+        let options = TlsOptions {
+            cert: Some(PathBuf::from("client.pem")),
+            ..Default::default()
+        };
+        assert!(options.connector().is_err());
+        assert!(options.configure(reqwest::Client::builder()).is_err());
+    }
+}
@@ -0,0 +1,165 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use chrono::Utc;
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, DuplexStream};
+
+use crate::Result;
+
+/// Size of the in-memory buffer between the client and a tapped stream
+const TAP_BUFFER: usize = 64 * 1024;
+
+/// Records an interactive session as JSON lines.
+///
+/// Every entry carries a timestamp and a `type`. Lines typed by the user are
+/// `command` entries, and commands that fail are followed by an `error`
+/// entry. Everything sent or received over the connection is
+/// recorded verbatim as a `request`, `response` or `notification`, with a
+/// `direction` of `sent` or `received`. Entries are written in the order they
+/// happen, so a session can be replayed by reading the file top to bottom.
+#[derive(Clone)]
+pub struct Transcript {
+    file: Arc<Mutex<File>>,
+}
+
+impl Transcript {
+    /// Create a transcript file, truncating any existing file at the path
+    pub fn create(path: &Path) -> Result<Self> {
+        Ok(Self {
+            file: Arc::new(Mutex::new(File::create(path)?)),
+        })
+    }
+
+    /// Record a line as typed by the user
+    pub fn command(&self, line: &str) -> Result<()> {
+        self.record(json!({ "type": "command", "data": { "line": line } }))
+    }
+
+    /// Record a failed command
+    pub fn error(&self, message: &str) -> Result<()> {
+        self.record(json!({ "type": "error", "data": { "message": message } }))
+    }
+
+    /// Record every message passing over a stream of newline-delimited
+    /// JSON-RPC, returning a stream to use in its place
+    pub fn tap<S>(&self, stream: S) -> DuplexStream
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let (local, remote) = tokio::io::duplex(TAP_BUFFER);
+        let (stream_reader, stream_writer) = tokio::io::split(stream);
+        let (remote_reader, remote_writer) = tokio::io::split(remote);
+        tokio::spawn(self.clone().forward(remote_reader, stream_writer, "sent"));
+        tokio::spawn(
+            self.clone()
+                .forward(stream_reader, remote_writer, "received"),
+        );
+        local
+    }
+
+    /// Copy lines from `reader` to `writer`, recording each one
+    async fn forward(
+        self,
+        reader: impl AsyncRead + Unpin,
+        mut writer: impl AsyncWrite + Unpin,
+        direction: &str,
+    ) {
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            // A failure to record mustn't break the connection
+            let _ = self.message(direction, &line);
+            let forwarded = writer.write_all(line.as_bytes()).await.is_ok()
+                && writer.write_all(b"\n").await.is_ok()
+                && writer.flush().await.is_ok();
+            if !forwarded {
+                break;
+            }
+        }
+        let _ = writer.shutdown().await;
+    }
+
+    /// Record a JSON-RPC message as it went over the wire
+    fn message(&self, direction: &str, line: &str) -> Result<()> {
+        if line.trim().is_empty() {
+            return Ok(());
+        }
+        let data = serde_json::from_str(line).unwrap_or_else(|_| Value::String(line.to_string()));
+        let kind = match (data.get("method"), data.get("id")) {
+            (Some(_), Some(_)) => "request",
+            (Some(_), None) => "notification",
+            _ => "response",
+        };
+        self.record(json!({ "type": kind, "direction": direction, "data": data }))
+    }
+
+    fn record(&self, mut entry: Value) -> Result<()> {
+        entry["timestamp"] = json!(Utc::now().to_rfc3339());
+        let line = serde_json::to_string(&entry)?;
+        let mut file = self.file.lock().unwrap();
+        writeln!(file, "{line}")?;
+        file.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_tap() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transcript.jsonl");
+        let transcript = Transcript::create(&path).unwrap();
+        transcript.command("ping").unwrap();
+
+        let (client, server) = tokio::io::duplex(1024);
+        let (client_reader, mut client_writer) = tokio::io::split(transcript.tap(client));
+        let (server_reader, mut server_writer) = tokio::io::split(server);
+
+        client_writer
+            .write_all(b"{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"ping\"}\n")
+            .await
+            .unwrap();
+        let mut line = String::new();
+        BufReader::new(server_reader)
+            .read_line(&mut line)
+            .await
+            .unwrap();
+        assert_eq!(line, "{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"ping\"}\n");
+
+        server_writer
+            .write_all(b"{\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{}}\n")
+            .await
+            .unwrap();
+        let mut line = String::new();
+        BufReader::new(client_reader)
+            .read_line(&mut line)
+            .await
+            .unwrap();
+        assert_eq!(line, "{\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{}}\n");
+
+        let entries: Vec<Value> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let kinds: Vec<(&str, Option<&str>)> = entries
+            .iter()
+            .map(|entry| (entry["type"].as_str().unwrap(), entry["direction"].as_str()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("command", None),
+                ("request", Some("sent")),
+                ("response", Some("received")),
+            ]
+        );
+        assert_eq!(entries[1]["data"]["method"], "ping");
+        assert_eq!(entries[2]["data"]["result"], json!({}));
+    }
+}
@@ -0,0 +1,153 @@
+//! Transports that the MCP client library doesn't speak natively.
+//!
+//! Each is adapted to a stream of newline-delimited JSON-RPC, the framing of
+//! the TCP transport. That stream is used directly by the raw client and the
+//! proxy, and handed to the MCP client library as a generic stream. On the
+//! server side, listeners hand each accepted connection over in the same form.
+
+use std::path::Path;
+use std::time::Duration;
+
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    sync::mpsc,
+};
+
+use crate::{Error, Result, egress, http::HttpOptions, sse, target::Target, tls, ws};
+
+/// A bidirectional stream of newline-delimited JSON-RPC messages
+pub trait LineStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> LineStream for T {}
+
+/// Where listeners send the connections they accept
+pub type Connections = mpsc::Sender<Box<dyn LineStream>>;
+
+/// How long listeners wait after a failed accept before trying again, so that
+/// running out of file descriptors doesn't make them spin
+pub const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Open a newline-delimited stream to a TCP target, or to one whose
+/// transport needs adapting
+pub async fn connect(target: &Target, http: &HttpOptions) -> Result<Box<dyn LineStream>> {
+    match target {
+        Target::Sse { host, port, path } => {
+            let url = sse::stream_url("http", host, *port, path);
+            Ok(Box::new(sse::connect(&url, http).await?))
+        }
+        Target::Sses { host, port, path } => {
+            let url = sse::stream_url("https", host, *port, path);
+            Ok(Box::new(sse::connect(&url, http).await?))
+        }
+        Target::Ws { .. } | Target::Wss { .. } => {
+            Ok(Box::new(ws::connect(&target.to_string(), http).await?))
+        }
+        Target::Tcp { host, port } => Ok(Box::new(
+            egress::connect_tcp(host, *port, &http.proxy).await?,
+        )),
+        Target::Tls { host, port } => {
+            let stream = egress::connect_tcp(host, *port, &http.proxy).await?;
+            Ok(Box::new(tls::connect(stream, host, &http.tls).await?))
+        }
+        Target::Unix { path } => connect_unix(path).await,
+        _ => Err(Error::Internal(format!(
+            "No stream adapter for target {}",
+            target.redacted()
+        ))),
+    }
+}
+
+#[cfg(unix)]
+async fn connect_unix(path: &Path) -> Result<Box<dyn LineStream>> {
+    let stream = tokio::net::UnixStream::connect(path).await.map_err(|e| {
+        Error::Other(format!(
+            "Failed to connect to Unix socket {}: {e}",
+            path.display()
+        ))
+    })?;
+    Ok(Box::new(stream))
+}
+
+#[cfg(not(unix))]
+async fn connect_unix(_path: &Path) -> Result<Box<dyn LineStream>> {
+    Err(Error::Other(
+        "Unix sockets are not supported on this platform".to_string(),
+    ))
+}
+
+/// Accept connections on a Unix socket, sending each to `connections`. A
+/// stale socket left at the path by an earlier run is replaced. Returns once
+/// the receiver is dropped.
+#[cfg(unix)]
+pub async fn serve_unix(path: &Path, connections: Connections) -> Result<()> {
+    use std::os::unix::fs::FileTypeExt;
+
+    if let Ok(metadata) = std::fs::symlink_metadata(path)
+        && metadata.file_type().is_socket()
+    {
+        std::fs::remove_file(path)?;
+    }
+    let listener = tokio::net::UnixListener::bind(path)
+        .map_err(|e| Error::Other(format!("Failed to listen on {}: {e}", path.display())))?;
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                tracing::warn!("Failed to accept a connection: {e}");
+                tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                continue;
+            }
+        };
+        if connections.send(Box::new(stream)).await.is_err() {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub async fn serve_unix(_path: &Path, _connections: Connections) -> Result<()> {
+    Err(Error::Other(
+        "Unix sockets are not supported on this platform".to_string(),
+    ))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_unix_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mcp.sock");
+        // A stale socket from an earlier run is replaced
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        let (connections, mut accepted) = mpsc::channel(1);
+        let listen_path = path.clone();
+        tokio::spawn(async move { serve_unix(&listen_path, connections).await });
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        let target = Target::Unix { path };
+        let stream = connect(&target, &HttpOptions::default()).await.unwrap();
+
+        // Echo lines back on the accepted side
+        let server = accepted.recv().await.unwrap();
+        tokio::spawn(async move {
+            let (reader, mut writer) = tokio::io::split(server);
+            let mut lines = BufReader::new(reader).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                writer
+                    .write_all(format!("{line}\n").as_bytes())
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let (reader, mut writer) = tokio::io::split(stream);
+        writer.write_all(b"{\"id\":1}\n").await.unwrap();
+        let mut line = String::new();
+        BufReader::new(reader).read_line(&mut line).await.unwrap();
+        assert_eq!(line, "{\"id\":1}\n");
+    }
+}
@@ -0,0 +1,328 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::Serialize;
+use tenx_mcp::{Client, schema::InitializeResult};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+use crate::{
+    Result, client,
+    command::{McpCommand, execute_mcp_command_with_client},
+    ctx::Ctx,
+    target::Target,
+    transport::ACCEPT_RETRY_DELAY,
+};
+
+pub struct WatchCommandArgs {
+    pub target: String,
+    /// Command to run on each check; ping if not given
+    pub command: Option<McpCommand>,
+    pub interval: Duration,
+    pub timeout: Duration,
+    /// Serve Prometheus metrics on this local port
+    pub metrics_port: Option<u16>,
+}
+
+/// The outcome of a single check
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckEvent {
+    pub timestamp: String,
+    pub up: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Counters and gauges exposed as Prometheus metrics
+#[derive(Debug, Clone, Default)]
+pub struct WatchMetrics {
+    pub up: bool,
+    pub last_latency: Option<Duration>,
+    pub last_check_timestamp: f64,
+    pub successes: u64,
+    pub failures: u64,
+    pub connects: u64,
+}
+
+impl WatchMetrics {
+    /// Render the metrics in the Prometheus text exposition format
+    pub fn render(&self, target: &str) -> String {
+        let target = target
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n");
+        let mut text = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, samples: &[(&str, String)]| {
+            text.push_str(&format!("# HELP {name} {help}\n# TYPE {name} {kind}\n"));
+            for (labels, value) in samples {
+                text.push_str(&format!("{name}{{target=\"{target}\"{labels}}} {value}\n"));
+            }
+        };
+
+        metric(
+            "mcptool_up",
+            "gauge",
+            "Whether the last check succeeded",
+            &[("", u8::from(self.up).to_string())],
+        );
+        if let Some(latency) = self.last_latency {
+            metric(
+                "mcptool_check_latency_seconds",
+                "gauge",
+                "Latency of the last successful check",
+                &[("", latency.as_secs_f64().to_string())],
+            );
+        }
+        metric(
+            "mcptool_last_check_timestamp_seconds",
+            "gauge",
+            "Unix time of the last check",
+            &[("", self.last_check_timestamp.to_string())],
+        );
+        metric(
+            "mcptool_checks_total",
+            "counter",
+            "Checks performed, by result",
+            &[
+                (",result=\"success\"", self.successes.to_string()),
+                (",result=\"failure\"", self.failures.to_string()),
+            ],
+        );
+        metric(
+            "mcptool_connects_total",
+            "counter",
+            "Connections established to the target",
+            &[("", self.connects.to_string())],
+        );
+        text
+    }
+}
+
+pub async fn watch_command(ctx: &Ctx, args: WatchCommandArgs) -> Result<()> {
+    let target = Target::parse(&args.target)?;
+    let command = args.command.unwrap_or(McpCommand::Ping);
+    let metrics = Arc::new(Mutex::new(WatchMetrics::default()));
+
+    if let Some(port) = args.metrics_port {
+        let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+        ctx.output.note(format!(
+            "Serving Prometheus metrics on http://127.0.0.1:{port}/metrics"
+        ))?;
+        tokio::spawn(serve_metrics(
+            listener,
+            metrics.clone(),
+            target.redacted().to_string(),
+        ));
+    }
+
+    ctx.output.h1(format!(
+        "Watching {} every {:.1}s (Ctrl-C to stop)",
+        target.redacted(),
+        args.interval.as_secs_f64()
+    ))?;
+
+    // Connections and commands run silently; only check results are shown
+    let quiet_ctx = Ctx {
+        output: ctx.output.clone().with_quiet(true),
+        ..ctx.clone()
+    };
+    let mut connection = None;
+    let mut was_up: Option<bool> = None;
+    let mut interval = tokio::time::interval(args.interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = &mut ctrl_c => break,
+        }
+        let event = tokio::select! {
+            event = check(
+                &quiet_ctx,
+                &target,
+                &command,
+                args.timeout,
+                &mut connection,
+                &metrics,
+            ) => event,
+            _ = &mut ctrl_c => break,
+        };
+        report(ctx, &event, &mut was_up, &metrics)?;
+    }
+
+    Ok(())
+}
+
+/// Run the command once, connecting first if there's no connection. A
+/// failed check drops the connection, so the next one starts afresh.
+async fn check(
+    ctx: &Ctx,
+    target: &Target,
+    command: &McpCommand,
+    timeout: Duration,
+    connection: &mut Option<(Client<()>, InitializeResult)>,
+    metrics: &Mutex<WatchMetrics>,
+) -> CheckEvent {
+    if connection.is_none() {
+        match tokio::time::timeout(timeout, client::get_client(ctx, target)).await {
+            Ok(Ok(connected)) => {
+                metrics.lock().unwrap().connects += 1;
+                *connection = Some(connected);
+            }
+            Ok(Err(e)) => return failure(format!("Connection failed: {e}")),
+            Err(_) => return failure("Connection timed out".to_string()),
+        }
+    }
+
+    let (client, init_result) = connection.as_mut().expect("connected above");
+    let start = std::time::Instant::now();
+    let outcome = tokio::time::timeout(
+        timeout,
+        execute_mcp_command_with_client(command.clone(), client, init_result, ctx),
+    )
+    .await;
+    let latency = start.elapsed();
+
+    let event = match outcome {
+        Ok(Ok(_)) => CheckEvent {
+            timestamp: timestamp(),
+            up: true,
+            latency_ms: Some(latency.as_secs_f64() * 1000.0),
+            error: None,
+        },
+        Ok(Err(e)) => failure(e.to_string()),
+        Err(_) => failure(format!("No response within {:.1}s", timeout.as_secs_f64())),
+    };
+    if !event.up {
+        *connection = None;
+    }
+    event
+}
+
+fn timestamp() -> String {
+    chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+fn failure(error: String) -> CheckEvent {
+    CheckEvent {
+        timestamp: timestamp(),
+        up: false,
+        latency_ms: None,
+        error: Some(error),
+    }
+}
+
+/// Record a check in the metrics and display it, calling out status changes
+fn report(
+    ctx: &Ctx,
+    event: &CheckEvent,
+    was_up: &mut Option<bool>,
+    metrics: &Mutex<WatchMetrics>,
+) -> Result<()> {
+    {
+        let mut metrics = metrics.lock().unwrap();
+        metrics.up = event.up;
+        metrics.last_check_timestamp = chrono::Utc::now().timestamp_millis() as f64 / 1000.0;
+        if event.up {
+            metrics.successes += 1;
+            metrics.last_latency = event
+                .latency_ms
+                .map(|ms| Duration::from_secs_f64(ms / 1000.0));
+        } else {
+            metrics.failures += 1;
+        }
+    }
+
+    if ctx.output.json {
+        return ctx.output.json_value(event);
+    }
+
+    let changed = *was_up != Some(event.up);
+    *was_up = Some(event.up);
+    match (event.up, changed) {
+        (true, true) => ctx.output.success(format!("{} UP", event.timestamp))?,
+        (false, true) => ctx.output.trace_error(format!(
+            "{} DOWN: {}",
+            event.timestamp,
+            event.error.as_deref().unwrap_or_default()
+        ))?,
+        (true, false) => {}
+        (false, false) => ctx.output.text(format!(
+            "{} still down: {}",
+            event.timestamp,
+            event.error.as_deref().unwrap_or_default()
+        ))?,
+    }
+    if let Some(latency) = event.latency_ms {
+        ctx.output
+            .text(format!("{} {latency:.2}ms", event.timestamp))?;
+    }
+    Ok(())
+}
+
+/// Answer every HTTP request with the current metrics. This is deliberately
+/// minimal: Prometheus only ever issues simple GETs.
+async fn serve_metrics(listener: TcpListener, metrics: Arc<Mutex<WatchMetrics>>, target: String) {
+    loop {
+        let Ok((mut stream, _)) = listener.accept().await else {
+            tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+            continue;
+        };
+        let body = metrics.lock().unwrap().render(&target);
+        tokio::spawn(async move {
+            // Read (and ignore) the request before responding
+            let mut buf = [0u8; 4096];
+            let _ = stream.read(&mut buf).await;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+            let _ = stream.shutdown().await;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_metrics() {
+        let metrics = WatchMetrics {
+            up: true,
+            last_latency: Some(Duration::from_millis(250)),
+            last_check_timestamp: 1700000000.5,
+            successes: 3,
+            failures: 1,
+            connects: 2,
+        };
+        let text = metrics.render("tcp://\"host\":3000");
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert!(lines.contains(&"# TYPE mcptool_up gauge"));
+        assert!(lines.contains(&r#"mcptool_up{target="tcp://\"host\":3000"} 1"#));
+        assert!(
+            lines.contains(&r#"mcptool_check_latency_seconds{target="tcp://\"host\":3000"} 0.25"#)
+        );
+        assert!(
+            lines.contains(
+                &r#"mcptool_checks_total{target="tcp://\"host\":3000",result="failure"} 1"#
+            )
+        );
+        assert!(lines.contains(&r#"mcptool_connects_total{target="tcp://\"host\":3000"} 2"#));
+    }
+
+    #[test]
+    fn test_render_metrics_before_first_success() {
+        let text = WatchMetrics::default().render("localhost:3000");
+        assert!(text.contains("mcptool_up{target=\"localhost:3000\"} 0\n"));
+        assert!(!text.contains("mcptool_check_latency_seconds"));
+    }
+}
@@ -0,0 +1,192 @@
+//! MCP over WebSockets, with one JSON-RPC message per text frame.
+//!
+//! Connections are adapted to newline-delimited JSON-RPC in both directions:
+//! [`connect`] for clients, and [`serve`] for servers.
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::Value;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, DuplexStream},
+    net::TcpListener,
+};
+use tokio_tungstenite::{
+    Connector, WebSocketStream,
+    tungstenite::{
+        Message,
+        client::IntoClientRequest,
+        http::{HeaderName, HeaderValue},
+    },
+};
+
+use crate::{
+    Error, Result, egress,
+    http::HttpOptions,
+    transport::{ACCEPT_RETRY_DELAY, Connections},
+};
+
+/// Buffer size of the in-memory stream handed back to the caller
+const STREAM_BUFFER: usize = 64 * 1024;
+
+/// Open a WebSocket connection. Returns a stream that carries one JSON-RPC
+/// message per line in each direction.
+pub async fn connect(url: &str, options: &HttpOptions) -> Result<DuplexStream> {
+    let mut request = url
+        .into_client_request()
+        .map_err(|e| Error::Format(format!("Invalid WebSocket URL {url}: {e}")))?;
+    for (name, value) in options.header_pairs() {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|e| Error::Format(format!("Invalid header name '{name}': {e}")))?;
+        let value = HeaderValue::from_str(&value)
+            .map_err(|e| Error::Format(format!("Invalid value for header '{name}': {e}")))?;
+        request.headers_mut().append(name, value);
+    }
+
+    // The TCP connection is opened here, so that it can go through a proxy
+    let parsed = url::Url::parse(url)
+        .map_err(|e| Error::Format(format!("Invalid WebSocket URL {url}: {e}")))?;
+    let host = parsed.host_str().unwrap_or_default();
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let port = parsed.port_or_known_default().unwrap_or(80);
+    let stream = egress::connect_tcp(host, port, &options.proxy).await?;
+
+    let connector = if parsed.scheme() == "wss" {
+        Some(Connector::NativeTls(options.tls.connector()?))
+    } else {
+        None
+    };
+    let (socket, _) =
+        tokio_tungstenite::client_async_tls_with_config(request, stream, None, connector)
+            .await
+            .map_err(|e| Error::Other(format!("Failed to connect to WebSocket {url}: {e}")))?;
+    let (local, remote) = tokio::io::duplex(STREAM_BUFFER);
+    tokio::spawn(pump(socket, remote));
+    Ok(local)
+}
+
+/// Accept WebSocket connections on `addr`, sending each to `connections` as a
+/// newline-delimited stream. Returns once the receiver is dropped.
+pub async fn serve(addr: &str, connections: Connections) -> Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    while !connections.is_closed() {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                tracing::warn!("Failed to accept a connection: {e}");
+                tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                continue;
+            }
+        };
+        let connections = connections.clone();
+        tokio::spawn(async move {
+            let Ok(socket) = tokio_tungstenite::accept_async(stream).await else {
+                return;
+            };
+            let (local, remote) = tokio::io::duplex(STREAM_BUFFER);
+            if connections.send(Box::new(local)).await.is_ok() {
+                pump(socket, remote).await;
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Copy messages between a WebSocket and a newline-delimited stream until
+/// either side closes
+async fn pump<S, L>(socket: WebSocketStream<S>, stream: L)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    L: AsyncRead + AsyncWrite + Unpin,
+{
+    let (mut sink, mut source) = socket.split();
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+    loop {
+        tokio::select! {
+            line = lines.next_line() => match line {
+                Ok(Some(line)) => {
+                    if !line.trim().is_empty() && sink.send(Message::text(line)).await.is_err() {
+                        break;
+                    }
+                }
+                _ => {
+                    let _ = sink.close().await;
+                    break;
+                }
+            },
+            message = source.next() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text.as_str().to_string(),
+                    Some(Ok(Message::Binary(data))) => String::from_utf8_lossy(&data).into_owned(),
+                    // Pings are answered by the WebSocket library
+                    Some(Ok(Message::Ping(_) | Message::Pong(_) | Message::Frame(_))) => continue,
+                    Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                };
+                if writer.write_all(format!("{}\n", single_line(text)).as_bytes()).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// A message on one line, so that pretty-printed JSON survives the framing
+fn single_line(text: String) -> String {
+    match serde_json::from_str::<Value>(&text) {
+        Ok(value) => value.to_string(),
+        Err(_) => text.replace('\n', " "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_line() {
+        assert_eq!(
+            single_line("{\n  \"id\": 1,\n  \"result\": {}\n}".to_string()),
+            r#"{"id":1,"result":{}}"#
+        );
+        assert_eq!(single_line("not\njson".to_string()), "not json");
+    }
+
+    #[tokio::test]
+    async fn test_serve_and_connect() {
+        let addr = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().to_string()
+        };
+        let (connections, mut accepted) = tokio::sync::mpsc::channel(1);
+        let listen_addr = addr.clone();
+        tokio::spawn(async move { serve(&listen_addr, connections).await });
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        // Echo lines back on each accepted connection
+        tokio::spawn(async move {
+            while let Some(stream) = accepted.recv().await {
+                tokio::spawn(async move {
+                    let (reader, mut writer) = tokio::io::split(stream);
+                    let mut lines = BufReader::new(reader).lines();
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        writer
+                            .write_all(format!("{line}\n").as_bytes())
+                            .await
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        let stream = connect(&format!("ws://{addr}/mcp"), &HttpOptions::default())
+            .await
+            .unwrap();
+        let (reader, mut writer) = tokio::io::split(stream);
+        writer
+            .write_all(b"{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"ping\"}\n")
+            .await
+            .unwrap();
+        let mut line = String::new();
+        BufReader::new(reader).read_line(&mut line).await.unwrap();
+        assert_eq!(line, "{\"id\":1,\"jsonrpc\":\"2.0\",\"method\":\"ping\"}\n");
+    }
+}
@@ -1,6 +1,6 @@
 use std::time::SystemTime;
 
-use libmcptool::storage::{StorageError, StoredAuth, TokenStorage};
+use libmcptool::storage::{AuthKind, StorageError, StoredAuth, TokenStorage};
 
 fn create_test_storage() -> TokenStorage {
     let test_dir = std::env::temp_dir().join("mcptool_test").join(format!(
@@ -38,6 +38,9 @@ fn test_auth_storage_lifecycle() {
         token_url: "https://test.example.com/token".to_string(),
         redirect_url: Some("http://localhost:8080".to_string()),
         scopes: vec!["read".to_string(), "write".to_string()],
+        kind: AuthKind::OAuth,
+        headers: vec![],
+        resource: None,
     };
 
     // Test store
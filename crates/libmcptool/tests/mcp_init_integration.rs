@@ -26,7 +26,17 @@ async fn test_mcp_init_with_test_server() {
 
     // Spawn the test server in the background
     let server_handle = tokio::spawn(async move {
-        libmcptool::testserver::run_test_server(&ctx, false, false, port, false).await
+        let args = libmcptool::testserver::TestServerArgs {
+            stdio: false,
+            tcp: false,
+            ws: false,
+            unix: None,
+            tls: false,
+            https: false,
+            port,
+            interactive: false,
+        };
+        libmcptool::testserver::run_test_server(&ctx, args).await
     });
 
     // Give the server time to start
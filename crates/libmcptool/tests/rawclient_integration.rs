@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use libmcptool::{
+    ctx::Ctx,
+    rawclient::{RawClient, Reply},
+    target::Target,
+};
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+
+/// A scripted line-based server: for every request it receives it first sends
+/// a notification and a server-to-client request, then the response.
+async fn scripted_server(listener: TcpListener) -> Vec<Value> {
+    let (stream, _) = listener.accept().await.expect("Failed to accept");
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    let mut received = vec![];
+
+    while let Some(line) = lines.next_line().await.expect("Failed to read") {
+        let message: Value = serde_json::from_str(&line).expect("Invalid JSON from client");
+        received.push(message.clone());
+        let Some(id) = message.get("id").cloned() else {
+            continue;
+        };
+        if message.get("method").is_none() {
+            // A reply to our server-to-client request
+            continue;
+        }
+        let response = match message["method"].as_str() {
+            Some("ping") => json!({"jsonrpc": "2.0", "id": id, "result": {}}),
+            _ => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": {"code": -32601, "message": "Method not found"}
+            }),
+        };
+        for outgoing in [
+            json!({"jsonrpc": "2.0", "method": "notifications/message", "params": {"level": "info", "data": "hi"}}),
+            json!({"jsonrpc": "2.0", "id": "srv-1", "method": "roots/list"}),
+            response,
+        ] {
+            writer
+                .write_all(format!("{outgoing}\n").as_bytes())
+                .await
+                .expect("Failed to write");
+        }
+    }
+    received
+}
+
+#[tokio::test]
+async fn test_raw_client_requests_and_notifications() {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind to local address");
+    let port = listener.local_addr().unwrap().port();
+    let server = tokio::spawn(scripted_server(listener));
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let ctx = Ctx::new(temp_dir.path().to_path_buf(), None, false, true, false, 80).unwrap();
+    let target = Target::parse(&format!("tcp://127.0.0.1:{port}")).unwrap();
+    let mut client = RawClient::connect(&ctx, &target, Duration::from_secs(5))
+        .await
+        .expect("Failed to connect");
+
+    let reply = client.request("ping", json!({})).await.unwrap();
+    assert_eq!(reply, Reply::Result(json!({})));
+
+    let reply = client.request("nonexistent", json!({})).await.unwrap();
+    assert!(matches!(reply, Reply::Error { code: -32601, .. }));
+
+    client
+        .notify("notifications/initialized", json!({}))
+        .await
+        .unwrap();
+
+    assert_eq!(client.notifications.len(), 2);
+    assert!(client.unexpected.is_empty());
+
+    drop(client);
+    let received = server.await.unwrap();
+
+    // Each server-to-client request was declined
+    let declined: Vec<&Value> = received
+        .iter()
+        .filter(|m| m.get("id") == Some(&json!("srv-1")))
+        .collect();
+    assert_eq!(declined.len(), 2);
+    assert_eq!(declined[0]["error"]["code"], -32601);
+    assert_eq!(
+        received.last().unwrap()["method"],
+        "notifications/initialized"
+    );
+}
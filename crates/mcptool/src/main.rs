@@ -1,10 +1,16 @@
 use clap::{Args, Parser, Subcommand};
 use libmcptool::{
-    LogLevel, auth,
-    command::{CliMcpCommand, execute_mcp_command},
-    connect, ctx, proxy,
-    target::Target,
-    testserver,
+    LogLevel, auth, bench, client, clientconfig,
+    command::{CliMcpCommand, McpCommand, execute_mcp_command},
+    conformance, connect, ctx, diff,
+    egress::ProxyOptions,
+    fanout,
+    http::{self, HttpOptions, resolve_secret},
+    probe, profile, proxy, snapshot,
+    target::{ProcessEnv, Target, parse_env_assignment},
+    testserver, testsuite,
+    tls::TlsOptions,
+    watch,
 };
 use terminal_size::{Width, terminal_size};
 
@@ -26,7 +32,7 @@ struct ProxyArgs {
 
 #[derive(Subcommand)]
 enum AuthCommands {
-    /// Add a new OAuth authentication entry
+    /// Add a new OAuth authentication entry, or static credentials with --bearer/--header
     Add {
         /// Name for the authentication entry
         name: String,
@@ -35,11 +41,11 @@ enum AuthCommands {
         #[arg(long)]
         server_url: Option<String>,
 
-        /// OAuth authorization URL
+        /// OAuth authorization URL (discovered from the server if not provided)
         #[arg(long)]
         auth_url: Option<String>,
 
-        /// OAuth token URL
+        /// OAuth token URL (discovered from the server if not provided)
         #[arg(long)]
         token_url: Option<String>,
 
@@ -55,11 +61,11 @@ enum AuthCommands {
         #[arg(long)]
         redirect_url: Option<String>,
 
-        /// Resource/Audience parameter for OAuth
+        /// Resource/Audience parameter for OAuth (discovered from the server if not provided)
         #[arg(long)]
         resource: Option<String>,
 
-        /// OAuth scopes (comma-separated)
+        /// OAuth scopes (comma-separated, discovered from the server if not provided)
         #[arg(long)]
         scopes: Option<String>,
 
@@ -86,6 +92,47 @@ enum AuthCommands {
     },
 }
 
+#[derive(Subcommand)]
+enum ProfileCommands {
+    /// Add a named profile for a target, with --env and --header stored alongside it
+    Add {
+        /// Name for the profile, used as @name or profile://name
+        name: String,
+
+        /// The MCP server target
+        target: String,
+
+        /// Send the token and headers of this static authentication entry
+        #[arg(long)]
+        auth: Option<String>,
+
+        /// Seconds to wait for the server
+        #[arg(long)]
+        timeout: Option<u64>,
+
+        /// Default output mode for commands run against this profile
+        #[arg(long, value_enum)]
+        output: Option<profile::OutputMode>,
+    },
+
+    /// List all profiles
+    #[command(alias = "ls")]
+    List,
+
+    /// Remove a profile
+    #[command(alias = "rm")]
+    Remove {
+        /// Name of the profile to remove
+        name: String,
+    },
+
+    /// Show a profile's target and settings
+    Show {
+        /// Name of the profile to show
+        name: String,
+    },
+}
+
 #[derive(Parser)]
 #[command(
     name = "mcptool",
@@ -113,6 +160,67 @@ struct Cli {
     #[arg(long, global = true)]
     quiet: bool,
 
+    /// Request this MCP protocol revision instead of the latest, on any transport
+    #[arg(long, global = true)]
+    protocol_version: Option<String>,
+
+    /// Add a header to requests to HTTP targets ('Name: value', repeatable)
+    #[arg(long = "header", global = true, value_name = "NAME: VALUE", value_parser = parse_header)]
+    headers: Vec<(String, String)>,
+
+    /// Send a bearer token to HTTP targets: a token, @file or env:VAR
+    #[arg(long, global = true, value_name = "TOKEN")]
+    bearer: Option<String>,
+
+    /// Trust the CA certificates in this PEM file for TLS connections
+    #[arg(long, global = true, value_name = "FILE")]
+    cacert: Option<std::path::PathBuf>,
+
+    /// Present this PEM client certificate for TLS connections (needs --key)
+    #[arg(long, global = true, value_name = "FILE", requires = "key")]
+    cert: Option<std::path::PathBuf>,
+
+    /// PEM PKCS#8 private key for --cert
+    #[arg(long, global = true, value_name = "FILE", requires = "cert")]
+    key: Option<std::path::PathBuf>,
+
+    /// Don't verify server certificates or hostnames
+    #[arg(long, global = true)]
+    insecure: bool,
+
+    /// Connect through this HTTP proxy (host:port or URL; CONNECT for TCP targets)
+    #[arg(
+        long,
+        global = true,
+        value_name = "URL",
+        conflicts_with = "socks_proxy"
+    )]
+    http_proxy: Option<String>,
+
+    /// Connect through this SOCKS5 proxy (host:port or URL)
+    #[arg(long, global = true, value_name = "URL")]
+    socks_proxy: Option<String>,
+
+    /// Write the stderr of cmd:// servers to this file instead of showing it
+    #[arg(long, global = true)]
+    server_stderr: Option<std::path::PathBuf>,
+
+    /// Set an environment variable for cmd:// servers (KEY=VALUE, repeatable)
+    #[arg(long = "env", global = true, value_name = "KEY=VALUE", value_parser = parse_env)]
+    env: Vec<(String, String)>,
+
+    /// Load environment variables for cmd:// servers from a file (repeatable)
+    #[arg(long, global = true)]
+    env_file: Vec<String>,
+
+    /// Working directory for cmd:// servers
+    #[arg(long, global = true)]
+    cwd: Option<String>,
+
+    /// Start cmd:// servers with an empty environment plus any --env variables
+    #[arg(long, global = true)]
+    clear_env: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -132,6 +240,22 @@ enum Commands {
     Connect {
         /// The MCP server target (e.g., "localhost:3000", "tcp://host:port", "http://host:port", "auth://name")
         target: String,
+
+        /// Record commands, requests, responses and notifications to a JSON lines file
+        #[arg(long)]
+        transcript: Option<std::path::PathBuf>,
+
+        /// Run commands from a script file instead of starting the REPL
+        #[arg(long)]
+        script: Option<std::path::PathBuf>,
+
+        /// Keep running the script after a command or assertion fails
+        #[arg(long, requires = "script")]
+        continue_on_error: bool,
+
+        /// Restart a cmd:// server whenever this file or directory changes
+        #[arg(long, conflicts_with = "script")]
+        watch: Option<std::path::PathBuf>,
     },
 
     /// Transparently proxy and print traffic forwarded to the target
@@ -150,7 +274,23 @@ enum Commands {
         #[arg(long)]
         tcp: bool,
 
-        /// Port to listen on (for HTTP/TCP transport)
+        /// Use WebSocket transport instead of HTTP
+        #[arg(long)]
+        ws: bool,
+
+        /// Listen on a Unix domain socket at this path instead of HTTP
+        #[arg(long, value_name = "PATH")]
+        unix: Option<std::path::PathBuf>,
+
+        /// Serve TCP over TLS with a generated self-signed certificate
+        #[arg(long)]
+        tls: bool,
+
+        /// Serve HTTP over TLS with a generated self-signed certificate
+        #[arg(long)]
+        https: bool,
+
+        /// Port to listen on (for HTTP/HTTPS/TCP/WebSocket transport)
         #[arg(short, long, default_value = "8080")]
         port: u16,
 
@@ -159,11 +299,192 @@ enum Commands {
         interactive: bool,
     },
 
-    /// Manage OAuth authentication entries
+    /// Benchmark an MCP command: throughput, error rate and latency percentiles
+    Bench {
+        /// The MCP server target
+        target: String,
+
+        /// Total number of requests to send
+        #[arg(short = 'n', long, default_value = "100")]
+        requests: u64,
+
+        /// Send requests for this many seconds instead of a fixed number
+        #[arg(short, long, conflicts_with = "requests", value_parser = parse_seconds)]
+        duration: Option<std::time::Duration>,
+
+        /// Number of concurrent workers
+        #[arg(short, long, default_value = "1")]
+        concurrency: usize,
+
+        /// Share one connection between all workers instead of opening one per worker
+        #[arg(long)]
+        shared: bool,
+
+        /// The command to benchmark
+        #[command(subcommand)]
+        command: McpCommand,
+    },
+
+    /// Check a server against the MCP specification and report pass/fail per check
+    Conformance {
+        /// The MCP server target
+        target: String,
+
+        /// Seconds to wait for each response
+        #[arg(long, default_value = "10")]
+        timeout: u64,
+    },
+
+    /// Try each known protocol revision and report which ones the server accepts
+    ProbeVersions {
+        /// The MCP server target
+        target: String,
+
+        /// Protocol versions to try instead of every known revision (can be specified multiple times)
+        #[arg(long = "try")]
+        versions: Vec<String>,
+
+        /// Seconds to wait for each response
+        #[arg(long, default_value = "10")]
+        timeout: u64,
+    },
+
+    /// Capture a server's tools, prompts and resources, or check it against a saved snapshot
+    Snapshot {
+        /// The MCP server target
+        target: String,
+
+        /// Write the snapshot to a file instead of stdout
+        #[arg(long, conflicts_with = "check")]
+        out: Option<std::path::PathBuf>,
+
+        /// Compare the server against a saved snapshot, failing if anything differs
+        #[arg(long)]
+        check: Option<std::path::PathBuf>,
+
+        /// A command whose result is included in the snapshot, e.g. "calltool echo --arg message=hi" (can be specified multiple times)
+        #[arg(long = "call", conflicts_with = "check")]
+        calls: Vec<String>,
+    },
+
+    /// Compare two servers, or a server and a saved snapshot, and report breaking changes
+    Diff {
+        /// The server or snapshot file to compare against
+        old: String,
+
+        /// The server or snapshot file being checked
+        new: String,
+    },
+
+    /// Run a declarative test suite (YAML, TOML or JSON) against MCP servers
+    Test {
+        /// Path to the test suite file
+        suite: std::path::PathBuf,
+
+        /// Maximum number of test cases to run concurrently
+        #[arg(short, long, default_value = "1")]
+        jobs: usize,
+
+        /// Report format
+        #[arg(long, value_enum, default_value = "text")]
+        format: testsuite::ReportFormat,
+
+        /// Write the JUnit or TAP report to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
+
+    /// Monitor a server by pinging (or running a command) periodically, reconnecting after failures
+    Watch {
+        /// The MCP server target
+        target: String,
+
+        /// Seconds between checks
+        #[arg(short, long, default_value = "5", value_parser = parse_seconds)]
+        interval: std::time::Duration,
+
+        /// Seconds to wait for each check before counting it as failed
+        #[arg(long, default_value = "10", value_parser = parse_seconds)]
+        timeout: std::time::Duration,
+
+        /// Serve Prometheus metrics on this local port
+        #[arg(long)]
+        metrics_port: Option<u16>,
+
+        /// The command to run on each check (defaults to ping)
+        #[command(subcommand)]
+        command: Option<McpCommand>,
+    },
+
+    /// Manage stored authentication entries
     Auth {
         #[command(subcommand)]
         command: AuthCommands,
     },
+
+    /// Manage named target profiles in config.toml
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommands,
+    },
+
+    /// Create profiles from the mcpServers JSON config of an MCP client (Claude Desktop, Cursor, VS Code)
+    Import {
+        /// Path to the client's configuration file
+        file: std::path::PathBuf,
+
+        /// Replace profiles that already exist
+        #[arg(long)]
+        overwrite: bool,
+    },
+
+    /// Write profiles as mcpServers JSON for MCP clients
+    Export {
+        /// Profiles to export (all of them if none are given)
+        profiles: Vec<String>,
+
+        /// Write to this file instead of stdout
+        #[arg(long)]
+        out: Option<std::path::PathBuf>,
+    },
+}
+
+impl Commands {
+    /// The target the command connects to, if it takes one
+    fn target(&self) -> Option<&str> {
+        match self {
+            Commands::Mcp { mcp_command } => mcp_command.target.as_deref(),
+            Commands::Proxy { proxy_args } => Some(&proxy_args.target),
+            Commands::Connect { target, .. }
+            | Commands::Bench { target, .. }
+            | Commands::Conformance { target, .. }
+            | Commands::ProbeVersions { target, .. }
+            | Commands::Snapshot { target, .. }
+            | Commands::Watch { target, .. } => Some(target),
+            _ => None,
+        }
+    }
+}
+
+fn parse_header(input: &str) -> Result<(String, String), String> {
+    http::parse_header(input).map_err(|e| e.to_string())
+}
+
+fn parse_env(input: &str) -> Result<(String, String), String> {
+    parse_env_assignment(input).map_err(|e| e.to_string())
+}
+
+/// A positive number of seconds, possibly fractional
+fn parse_seconds(input: &str) -> Result<std::time::Duration, String> {
+    match input
+        .parse::<f64>()
+        .map(std::time::Duration::try_from_secs_f64)
+    {
+        Ok(Ok(duration)) if !duration.is_zero() => Ok(duration),
+        _ => Err(format!(
+            "expected a positive number of seconds, got '{input}'"
+        )),
+    }
 }
 
 #[tokio::main]
@@ -192,8 +513,46 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         80
     };
 
+    // A profile can make JSON the default output for commands run against it
+    let json = cli.json
+        || match cli.command.target().map(Target::parse) {
+            Some(Ok(Target::Profile { name })) => {
+                profile::Config::load(&config_path)?.profile(&name)?.output
+                    == Some(profile::OutputMode::Json)
+            }
+            _ => false,
+        };
+
     // Create the MCPTool instance
-    let ctx = ctx::Ctx::new(config_path, cli.logs, cli.json, cli.quiet, color, width)?;
+    let ctx = ctx::Ctx::new(config_path, cli.logs, json, cli.quiet, color, width)?;
+    let connect_options = client::ConnectOptions {
+        protocol_version: cli.protocol_version,
+        server_stderr: cli.server_stderr,
+        http: HttpOptions {
+            headers: cli.headers,
+            bearer: cli.bearer.as_deref().map(resolve_secret).transpose()?,
+            tls: TlsOptions {
+                cacert: cli.cacert,
+                cert: cli.cert,
+                key: cli.key,
+                insecure: cli.insecure,
+            },
+            proxy: ProxyOptions {
+                http: cli.http_proxy,
+                socks: cli.socks_proxy,
+            },
+        },
+        process_env: ProcessEnv {
+            vars: cli.env,
+            env_files: cli.env_file,
+            cwd: cli.cwd,
+            clear: cli.clear_env,
+        },
+        server_output: Some(ctx.output.clone()),
+        transcript: None,
+        processes: None,
+    };
+    let ctx = ctx.with_connect_options(connect_options);
 
     match cli.command {
         Commands::Version => {
@@ -205,27 +564,166 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
 
         Commands::Mcp { mcp_command } => {
-            execute_mcp_command(mcp_command.command, &mcp_command.target, &ctx)
-                .await
-                .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)?;
+            let result = match &mcp_command.target {
+                Some(target) => execute_mcp_command(mcp_command.command, target, &ctx).await,
+                None => {
+                    let targets = match &mcp_command.targets {
+                        Some(path) => fanout::load_targets(path),
+                        None => fanout::profile_targets(&ctx),
+                    }
+                    .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)?;
+                    let args = fanout::FanoutCommandArgs {
+                        targets,
+                        jobs: mcp_command.jobs,
+                        timeout: std::time::Duration::from_secs(mcp_command.timeout),
+                    };
+                    fanout::fanout_command(&ctx, mcp_command.command, args).await
+                }
+            };
+            result.map_err(|e| Box::new(e) as Box<dyn std::error::Error>)?;
         }
 
-        Commands::Connect { target } => {
-            connect::connect_command(&ctx, target).await?;
+        Commands::Connect {
+            target,
+            transcript,
+            script,
+            continue_on_error,
+            watch,
+        } => {
+            let args = connect::ConnectArgs {
+                target,
+                transcript,
+                script,
+                continue_on_error,
+                watch,
+            };
+            connect::connect_command(&ctx, args).await?;
         }
 
         Commands::Proxy { proxy_args } => {
-            let target = Target::parse(&proxy_args.target)?;
-            proxy::proxy_command(target, proxy_args.log_file).await?;
+            let (ctx, target) = match Target::parse(&proxy_args.target)? {
+                Target::Profile { name } => profile::Config::load(&ctx.config_path)?
+                    .profile(&name)?
+                    .resolve(&ctx)?,
+                target => (ctx, target),
+            };
+            let target = target.with_process_env(&ctx.connect_options.process_env);
+            proxy::proxy_command(target, proxy_args.log_file, &ctx.connect_options.http).await?;
         }
 
         Commands::Testserver {
             stdio,
             tcp,
+            ws,
+            unix,
+            tls,
+            https,
             port,
             interactive,
         } => {
-            testserver::run_test_server(&ctx, stdio, tcp, port, interactive).await?;
+            let args = testserver::TestServerArgs {
+                stdio,
+                tcp,
+                ws,
+                unix,
+                tls,
+                https,
+                port,
+                interactive,
+            };
+            testserver::run_test_server(&ctx, args).await?;
+        }
+
+        Commands::Bench {
+            target,
+            requests,
+            duration,
+            concurrency,
+            shared,
+            command,
+        } => {
+            let args = bench::BenchCommandArgs {
+                target,
+                command,
+                requests,
+                duration,
+                concurrency,
+                shared,
+            };
+            bench::bench_command(&ctx, args).await?;
+        }
+
+        Commands::Conformance { target, timeout } => {
+            let args = conformance::ConformanceCommandArgs {
+                target,
+                timeout: std::time::Duration::from_secs(timeout),
+            };
+            conformance::conformance_command(&ctx, args).await?;
+        }
+
+        Commands::ProbeVersions {
+            target,
+            versions,
+            timeout,
+        } => {
+            let args = probe::ProbeVersionsCommandArgs {
+                target,
+                versions,
+                timeout: std::time::Duration::from_secs(timeout),
+            };
+            probe::probe_versions_command(&ctx, args).await?;
+        }
+
+        Commands::Snapshot {
+            target,
+            out,
+            check,
+            calls,
+        } => {
+            let args = snapshot::SnapshotCommandArgs {
+                target,
+                out,
+                check,
+                calls,
+            };
+            snapshot::snapshot_command(&ctx, args).await?;
+        }
+
+        Commands::Diff { old, new } => {
+            let args = diff::DiffCommandArgs { old, new };
+            diff::diff_command(&ctx, args).await?;
+        }
+
+        Commands::Test {
+            suite,
+            jobs,
+            format,
+            output,
+        } => {
+            let args = testsuite::TestCommandArgs {
+                suite,
+                jobs,
+                format,
+                output,
+            };
+            testsuite::test_command(&ctx, args).await?;
+        }
+
+        Commands::Watch {
+            target,
+            interval,
+            timeout,
+            metrics_port,
+            command,
+        } => {
+            let args = watch::WatchCommandArgs {
+                target,
+                command,
+                interval,
+                timeout,
+                metrics_port,
+            };
+            watch::watch_command(&ctx, args).await?;
         }
 
         Commands::Auth { command } => match command {
@@ -252,6 +750,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     resource,
                     scopes,
                     show_redirect_url,
+                    // With --bearer or --header, store static credentials
+                    bearer: ctx.connect_options.http.bearer.clone(),
+                    headers: ctx.connect_options.http.headers.clone(),
                 };
                 auth::add_command(&ctx, args).await?;
             }
@@ -259,6 +760,40 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             AuthCommands::Remove { name } => auth::remove_command(&ctx, name).await?,
             AuthCommands::Renew { name } => auth::renew_command(&ctx, name).await?,
         },
+
+        Commands::Profile { command } => match command {
+            ProfileCommands::Add {
+                name,
+                target,
+                auth,
+                timeout,
+                output,
+            } => {
+                let args = profile::AddCommandArgs {
+                    name,
+                    target,
+                    auth,
+                    timeout,
+                    output,
+                    env: ctx.connect_options.process_env.vars.clone(),
+                    headers: ctx.connect_options.http.headers.clone(),
+                };
+                profile::add_command(&ctx, args).await?;
+            }
+            ProfileCommands::List => profile::list_command(&ctx).await?,
+            ProfileCommands::Remove { name } => profile::remove_command(&ctx, name).await?,
+            ProfileCommands::Show { name } => profile::show_command(&ctx, name).await?,
+        },
+
+        Commands::Import { file, overwrite } => {
+            let args = clientconfig::ImportCommandArgs { file, overwrite };
+            clientconfig::import_command(&ctx, args).await?;
+        }
+
+        Commands::Export { profiles, out } => {
+            let args = clientconfig::ExportCommandArgs { out, profiles };
+            clientconfig::export_command(&ctx, args).await?;
+        }
     }
 
     Ok(())